@@ -0,0 +1,68 @@
+// Compares batch verification of N non-interactive proofs with verifying them one by one.
+// Run with `cargo bench --bench batch`.
+use std::time::{Duration, Instant};
+
+use rand::rngs::StdRng;
+use rand::SeedableRng;
+
+use zkp::zkrypto::batch::ChaumPedersenBatchVerifier;
+use zkp::zkrypto::chaum_pedersen::{ChaumPedersenProof, ChaumPedersenProver, ChaumPedersenVerifier};
+use zkp::zkrypto::generator::get_named;
+use zkp::zkrypto::group::Group;
+use zkp::zkrypto::ristretto::RistrettoGroup;
+
+// Batch sizes to measure
+const SIZES: [usize; 3] = [16, 64, 256];
+
+// Each measurement is repeated until it has run for at least this long
+const MIN_TIME: Duration = Duration::from_millis(500);
+
+// Returns the mean time of f over enough runs to fill MIN_TIME
+fn time<T>(mut f: impl FnMut() -> T) -> Duration {
+    let start = Instant::now();
+    let mut runs = 0;
+    while runs == 0 || start.elapsed() < MIN_TIME {
+        std::hint::black_box(f());
+        runs += 1;
+    }
+    start.elapsed() / runs
+}
+
+fn bench<G: Group>(name: &str, g: G, rng: &mut StdRng) {
+    let max = SIZES[SIZES.len() - 1];
+    let proofs: Vec<(ChaumPedersenVerifier<G>, ChaumPedersenProof<G>, Vec<u8>)> = (0..max)
+        .map(|i| {
+            let prover = ChaumPedersenProver::with_group(g.clone(), rng);
+            let context = format!("login {}", i).into_bytes();
+            let proof = prover.prove(&context, rng);
+            let verifier = ChaumPedersenVerifier::with_group(g.clone(), prover.kp.y1.clone(), prover.kp.y2.clone())
+                .expect("honest keys are group members");
+            (verifier, proof, context)
+        })
+        .collect();
+
+    for n in SIZES {
+        let single = time(|| proofs[..n].iter().all(|(v, p, ctx)| v.verify(p, ctx)));
+        let mut batch = ChaumPedersenBatchVerifier::with_group(g.clone());
+        for (verifier, proof, context) in &proofs[..n] {
+            batch.push(verifier, proof.clone(), context);
+        }
+        let batched = time(|| batch.verify(rng).is_ok());
+        println!(
+            "{:<14} n = {:<4} one by one {:>10.2?} ({:>8.2?}/proof)  batch {:>10.2?} ({:>8.2?}/proof)  speedup {:.2}x",
+            name,
+            n,
+            single,
+            single / n as u32,
+            batched,
+            batched / n as u32,
+            single.as_secs_f64() / batched.as_secs_f64(),
+        );
+    }
+}
+
+fn main() {
+    let mut rng = StdRng::seed_from_u64(0);
+    bench("modp2048-256", get_named("modp2048-256").expect("built-in group"), &mut rng);
+    bench("ristretto255", RistrettoGroup::new(), &mut rng);
+}
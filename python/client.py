@@ -7,12 +7,15 @@ from proto.zkp_pb2 import (
     RegisterRequest,
     AuthenticationChallengeRequest,
     AuthenticationAnswerRequest,
+    LoginNonceRequest,
     AsyncAuthenticationRequest
 )
 
 import proto.zkp_pb2_grpc as pt
 
 from zkrypto import ChaumPedersenProver, Generator
+from zkrypto.encoding import decode, encode
+from utils import DEFAULT_GROUP, async_login_context
 
 class ChaumPedersenProtocolClient():
 
@@ -23,36 +26,56 @@ class ChaumPedersenProtocolClient():
         self.client= pt.AuthStub(self.channel)
 
 
+    def element(self, x):
+        # Encodes a group element with the canonical length for the group
+        return encode(x, self.prover.p)
+
+    def scalar(self, x):
+        # Encodes a scalar with the canonical length for the group
+        return encode(x, self.prover.q)
+
     def Register(self):
 
-        req = RegisterRequest(user=self.user, y1=self.prover.y1, y2=self.prover.y2)
+        req = RegisterRequest(user=self.user, y1=self.element(self.prover.y1), y2=self.element(self.prover.y2), group=DEFAULT_GROUP)
         logging.debug("[%s] y1: %d" %(self.user, self.prover.y1))
         logging.debug("[%s] y2: %d" %(self.user, self.prover.y2))
         return self.client.Register(req)
     
     def Authenticate(self):
+        # The server only answers commitments sent with a single-use nonce
+        login_nonce = self.client.CreateLoginNonce(LoginNonceRequest(user=self.user)).login_nonce
         r1, r2 = self.prover.prove_sync_a()
-        req1 = AuthenticationChallengeRequest(user=self.user, r1=r1, r2=r2)
+        req1 = AuthenticationChallengeRequest(user=self.user, r1=self.element(r1), r2=self.element(r2), login_nonce=login_nonce)
         res1 = self.client.CreateAuthenticationChallenge(req1)
         logging.debug("[%s] r1: %d" %(self.user, self.prover.r1))
         logging.debug("[%s] r2: %d" %(self.user, self.prover.r2))
-        s = self.prover.prove_sync_b(res1.c)
+        c = decode(res1.c, self.prover.q)
+        s = self.prover.prove_sync_b(c)
         
-        logging.debug("[%s] c: %d" %(self.user, res1.c))
+        logging.debug("[%s] c: %d" %(self.user, c))
         logging.debug("[%s] s: %d" %(self.user, self.prover.s))
-        req2 = AuthenticationAnswerRequest(auth_id=res1.auth_id, s=s)
+        req2 = AuthenticationAnswerRequest(auth_id=res1.auth_id, s=self.scalar(s))
 
 
         res2 = self.client.VerifyAuthentication(req2)
         return res2.session_id
     
     def AuthenticateAsync(self):
-        r1, r2, c, s = self.prover.prove_async()
+        # Fetch a single-use nonce, so the proof cannot be replayed by anyone who captures it
+        login_nonce = self.client.CreateLoginNonce(LoginNonceRequest(user=self.user)).login_nonce
+        r1, r2, c, s = self.prover.prove_async(context=async_login_context(self.user, login_nonce))
         logging.debug("[%s] r1: %d" %(self.user, r1))
         logging.debug("[%s] r2: %d" %(self.user, r2))
         logging.debug("[%s] c: %d" %(self.user, c))
         logging.debug("[%s] s: %d" %(self.user, s))
-        req = AsyncAuthenticationRequest(user=self.user, r1=r1, r2=r2, c=c, s=s)
+        req = AsyncAuthenticationRequest(
+            user=self.user,
+            r1=self.element(r1),
+            r2=self.element(r2),
+            c=self.scalar(c),
+            s=self.scalar(s),
+            login_nonce=login_nonce
+        )
         res = self.client.VerifyAuthenticationAsync(req)
         return res.session_id
 
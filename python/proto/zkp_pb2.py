@@ -1,11 +1,22 @@
 # -*- coding: utf-8 -*-
 # Generated by the protocol buffer compiler.  DO NOT EDIT!
+# NO CHECKED-IN PROTOBUF GENCODE
 # source: zkp.proto
+# Protobuf Python Version: 6.31.1
 """Generated protocol buffer code."""
 from google.protobuf import descriptor as _descriptor
 from google.protobuf import descriptor_pool as _descriptor_pool
+from google.protobuf import runtime_version as _runtime_version
 from google.protobuf import symbol_database as _symbol_database
 from google.protobuf.internal import builder as _builder
+_runtime_version.ValidateProtobufRuntimeVersion(
+    _runtime_version.Domain.PUBLIC,
+    6,
+    31,
+    1,
+    '',
+    'zkp.proto'
+)
 # @@protoc_insertion_point(imports)
 
 _sym_db = _symbol_database.Default()
@@ -13,28 +24,55 @@ _sym_db = _symbol_database.Default()
 
 
 
-DESCRIPTOR = _descriptor_pool.Default().AddSerializedFile(b'\n\tzkp.proto\x12\x08zkp_auth\"7\n\x0fRegisterRequest\x12\x0c\n\x04user\x18\x01 \x01(\t\x12\n\n\x02y1\x18\x02 \x01(\x03\x12\n\n\x02y2\x18\x03 \x01(\x03\"\x12\n\x10RegisterResponse\"F\n\x1e\x41uthenticationChallengeRequest\x12\x0c\n\x04user\x18\x01 \x01(\t\x12\n\n\x02r1\x18\x02 \x01(\x03\x12\n\n\x02r2\x18\x03 \x01(\x03\"=\n\x1f\x41uthenticationChallengeResponse\x12\x0f\n\x07\x61uth_id\x18\x01 \x01(\t\x12\t\n\x01\x63\x18\x02 \x01(\x03\"9\n\x1b\x41uthenticationAnswerRequest\x12\x0f\n\x07\x61uth_id\x18\x01 \x01(\t\x12\t\n\x01s\x18\x02 \x01(\x03\"2\n\x1c\x41uthenticationAnswerResponse\x12\x12\n\nsession_id\x18\x01 \x01(\t\"X\n\x1a\x41syncAuthenticationRequest\x12\x0c\n\x04user\x18\x01 \x01(\t\x12\n\n\x02r1\x18\x02 \x01(\x03\x12\n\n\x02r2\x18\x03 \x01(\x03\x12\t\n\x01\x63\x18\x04 \x01(\x03\x12\t\n\x01s\x18\x05 \x01(\x03\x32\x99\x03\n\x04\x41uth\x12\x43\n\x08Register\x12\x19.zkp_auth.RegisterRequest\x1a\x1a.zkp_auth.RegisterResponse\"\x00\x12k\n\x19VerifyAuthenticationAsync\x12$.zkp_auth.AsyncAuthenticationRequest\x1a&.zkp_auth.AuthenticationAnswerResponse\"\x00\x12v\n\x1d\x43reateAuthenticationChallenge\x12(.zkp_auth.AuthenticationChallengeRequest\x1a).zkp_auth.AuthenticationChallengeResponse\"\x00\x12g\n\x14VerifyAuthentication\x12%.zkp_auth.AuthenticationAnswerRequest\x1a&.zkp_auth.AuthenticationAnswerResponse\"\x00\x62\x06proto3')
+DESCRIPTOR = _descriptor_pool.Default().AddSerializedFile(b'\n\tzkp.proto\x12\x08zkp_auth\"L\n\x0cPolicyFactor\x12$\n\x08protocol\x18\x01 \x01(\x0e\x32\x12.zkp_auth.Protocol\x12\n\n\x02y1\x18\x02 \x01(\x0c\x12\n\n\x02y2\x18\x03 \x01(\x0c\"Z\n\rKdfParameters\x12\x0c\n\x04salt\x18\x01 \x01(\x0c\x12\x12\n\nmemory_kib\x18\x02 \x01(\r\x12\x12\n\niterations\x18\x03 \x01(\r\x12\x13\n\x0bparallelism\x18\x04 \x01(\r\"\xba\x01\n\x0fRegisterRequest\x12\x0c\n\x04user\x18\x01 \x01(\t\x12\n\n\x02y1\x18\x02 \x01(\x0c\x12\n\n\x02y2\x18\x03 \x01(\x0c\x12\r\n\x05group\x18\x04 \x01(\t\x12$\n\x03kdf\x18\x05 \x01(\x0b\x32\x17.zkp_auth.KdfParameters\x12$\n\x08protocol\x18\x06 \x01(\x0e\x32\x12.zkp_auth.Protocol\x12&\n\x06policy\x18\x07 \x03(\x0b\x32\x16.zkp_auth.PolicyFactor\"\x12\n\x10RegisterResponse\"&\n\x16LoginParametersRequest\x12\x0c\n\x04user\x18\x01 \x01(\t\"t\n\x17LoginParametersResponse\x12\r\n\x05group\x18\x01 \x01(\t\x12$\n\x03kdf\x18\x02 \x01(\x0b\x32\x17.zkp_auth.KdfParameters\x12$\n\x08protocol\x18\x03 \x01(\x0e\x32\x12.zkp_auth.Protocol\"p\n\x1e\x41uthenticationChallengeRequest\x12\x0c\n\x04user\x18\x01 \x01(\t\x12\n\n\x02r1\x18\x02 \x01(\x0c\x12\n\n\x02r2\x18\x03 \x01(\x0c\x12\x13\n\x0b\x63ommitments\x18\x04 \x03(\x0c\x12\x13\n\x0blogin_nonce\x18\x05 \x01(\t\"=\n\x1f\x41uthenticationChallengeResponse\x12\x0f\n\x07\x61uth_id\x18\x01 \x01(\t\x12\t\n\x01\x63\x18\x02 \x01(\x0c\"L\n\x1b\x41uthenticationAnswerRequest\x12\x0f\n\x07\x61uth_id\x18\x01 \x01(\t\x12\t\n\x01s\x18\x02 \x01(\x0c\x12\x11\n\tresponses\x18\x03 \x03(\x0c\"2\n\x1c\x41uthenticationAnswerResponse\x12\x12\n\nsession_id\x18\x01 \x01(\t\"!\n\x11LoginNonceRequest\x12\x0c\n\x04user\x18\x01 \x01(\t\")\n\x12LoginNonceResponse\x12\x13\n\x0blogin_nonce\x18\x01 \x01(\t\"\x95\x01\n\x1a\x41syncAuthenticationRequest\x12\x0c\n\x04user\x18\x01 \x01(\t\x12\n\n\x02r1\x18\x02 \x01(\x0c\x12\n\n\x02r2\x18\x03 \x01(\x0c\x12\t\n\x01\x63\x18\x04 \x01(\x0c\x12\t\n\x01s\x18\x05 \x01(\x0c\x12\x13\n\x0blogin_nonce\x18\x06 \x01(\t\x12\x13\n\x0b\x63ommitments\x18\x07 \x03(\x0c\x12\x11\n\tresponses\x18\x08 \x03(\x0c\"5\n\rUserPublicKey\x12\x0c\n\x04user\x18\x01 \x01(\t\x12\n\n\x02y1\x18\x02 \x01(\x0c\x12\n\n\x02y2\x18\x03 \x01(\x0c\"7\n\x18RegisterUserGroupRequest\x12\x0c\n\x04name\x18\x01 \x01(\t\x12\r\n\x05users\x18\x02 \x03(\t\"\x1b\n\x19RegisterUserGroupResponse\" \n\x10UserGroupRequest\x12\x0c\n\x04name\x18\x01 \x01(\t\"a\n\x11UserGroupResponse\x12\r\n\x05group\x18\x01 \x01(\t\x12(\n\x07members\x18\x02 \x03(\x0b\x32\x17.zkp_auth.UserPublicKey\x12\x13\n\x0blogin_nonce\x18\x03 \x01(\t\";\n\x0bProofBranch\x12\n\n\x02r1\x18\x01 \x01(\x0c\x12\n\n\x02r2\x18\x02 \x01(\x0c\x12\t\n\x01\x63\x18\x03 \x01(\x0c\x12\t\n\x01s\x18\x04 \x01(\x0c\"l\n\x1eUserGroupAuthenticationRequest\x12\x0c\n\x04name\x18\x01 \x01(\t\x12\'\n\x08\x62ranches\x18\x02 \x03(\x0b\x32\x15.zkp_auth.ProofBranch\x12\x13\n\x0blogin_nonce\x18\x03 \x01(\t*7\n\x08Protocol\x12\x12\n\x0e\x43HAUM_PEDERSEN\x10\x00\x12\x0b\n\x07SCHNORR\x10\x01\x12\n\n\x06POLICY\x10\x02\x32\xe7\x06\n\x04\x41uth\x12\x43\n\x08Register\x12\x19.zkp_auth.RegisterRequest\x1a\x1a.zkp_auth.RegisterResponse\"\x00\x12O\n\x10\x43reateLoginNonce\x12\x1b.zkp_auth.LoginNonceRequest\x1a\x1c.zkp_auth.LoginNonceResponse\"\x00\x12[\n\x12GetLoginParameters\x12 .zkp_auth.LoginParametersRequest\x1a!.zkp_auth.LoginParametersResponse\"\x00\x12k\n\x19VerifyAuthenticationAsync\x12$.zkp_auth.AsyncAuthenticationRequest\x1a&.zkp_auth.AuthenticationAnswerResponse\"\x00\x12v\n\x1d\x43reateAuthenticationChallenge\x12(.zkp_auth.AuthenticationChallengeRequest\x1a).zkp_auth.AuthenticationChallengeResponse\"\x00\x12g\n\x14VerifyAuthentication\x12%.zkp_auth.AuthenticationAnswerRequest\x1a&.zkp_auth.AuthenticationAnswerResponse\"\x00\x12^\n\x11RegisterUserGroup\x12\".zkp_auth.RegisterUserGroupRequest\x1a#.zkp_auth.RegisterUserGroupResponse\"\x00\x12I\n\x0cGetUserGroup\x12\x1a.zkp_auth.UserGroupRequest\x1a\x1b.zkp_auth.UserGroupResponse\"\x00\x12s\n\x1dVerifyUserGroupAuthentication\x12(.zkp_auth.UserGroupAuthenticationRequest\x1a&.zkp_auth.AuthenticationAnswerResponse\"\x00\x62\x06proto3')
 
 _globals = globals()
 _builder.BuildMessageAndEnumDescriptors(DESCRIPTOR, _globals)
 _builder.BuildTopDescriptorsAndMessages(DESCRIPTOR, 'zkp_pb2', _globals)
-if _descriptor._USE_C_DESCRIPTORS == False:
-
-  DESCRIPTOR._options = None
-  _globals['_REGISTERREQUEST']._serialized_start=23
-  _globals['_REGISTERREQUEST']._serialized_end=78
-  _globals['_REGISTERRESPONSE']._serialized_start=80
-  _globals['_REGISTERRESPONSE']._serialized_end=98
-  _globals['_AUTHENTICATIONCHALLENGEREQUEST']._serialized_start=100
-  _globals['_AUTHENTICATIONCHALLENGEREQUEST']._serialized_end=170
-  _globals['_AUTHENTICATIONCHALLENGERESPONSE']._serialized_start=172
-  _globals['_AUTHENTICATIONCHALLENGERESPONSE']._serialized_end=233
-  _globals['_AUTHENTICATIONANSWERREQUEST']._serialized_start=235
-  _globals['_AUTHENTICATIONANSWERREQUEST']._serialized_end=292
-  _globals['_AUTHENTICATIONANSWERRESPONSE']._serialized_start=294
-  _globals['_AUTHENTICATIONANSWERRESPONSE']._serialized_end=344
-  _globals['_ASYNCAUTHENTICATIONREQUEST']._serialized_start=346
-  _globals['_ASYNCAUTHENTICATIONREQUEST']._serialized_end=434
-  _globals['_AUTH']._serialized_start=437
-  _globals['_AUTH']._serialized_end=846
+if not _descriptor._USE_C_DESCRIPTORS:
+  DESCRIPTOR._loaded_options = None
+  _globals['_PROTOCOL']._serialized_start=1542
+  _globals['_PROTOCOL']._serialized_end=1597
+  _globals['_POLICYFACTOR']._serialized_start=23
+  _globals['_POLICYFACTOR']._serialized_end=99
+  _globals['_KDFPARAMETERS']._serialized_start=101
+  _globals['_KDFPARAMETERS']._serialized_end=191
+  _globals['_REGISTERREQUEST']._serialized_start=194
+  _globals['_REGISTERREQUEST']._serialized_end=380
+  _globals['_REGISTERRESPONSE']._serialized_start=382
+  _globals['_REGISTERRESPONSE']._serialized_end=400
+  _globals['_LOGINPARAMETERSREQUEST']._serialized_start=402
+  _globals['_LOGINPARAMETERSREQUEST']._serialized_end=440
+  _globals['_LOGINPARAMETERSRESPONSE']._serialized_start=442
+  _globals['_LOGINPARAMETERSRESPONSE']._serialized_end=558
+  _globals['_AUTHENTICATIONCHALLENGEREQUEST']._serialized_start=560
+  _globals['_AUTHENTICATIONCHALLENGEREQUEST']._serialized_end=672
+  _globals['_AUTHENTICATIONCHALLENGERESPONSE']._serialized_start=674
+  _globals['_AUTHENTICATIONCHALLENGERESPONSE']._serialized_end=735
+  _globals['_AUTHENTICATIONANSWERREQUEST']._serialized_start=737
+  _globals['_AUTHENTICATIONANSWERREQUEST']._serialized_end=813
+  _globals['_AUTHENTICATIONANSWERRESPONSE']._serialized_start=815
+  _globals['_AUTHENTICATIONANSWERRESPONSE']._serialized_end=865
+  _globals['_LOGINNONCEREQUEST']._serialized_start=867
+  _globals['_LOGINNONCEREQUEST']._serialized_end=900
+  _globals['_LOGINNONCERESPONSE']._serialized_start=902
+  _globals['_LOGINNONCERESPONSE']._serialized_end=943
+  _globals['_ASYNCAUTHENTICATIONREQUEST']._serialized_start=946
+  _globals['_ASYNCAUTHENTICATIONREQUEST']._serialized_end=1095
+  _globals['_USERPUBLICKEY']._serialized_start=1097
+  _globals['_USERPUBLICKEY']._serialized_end=1150
+  _globals['_REGISTERUSERGROUPREQUEST']._serialized_start=1152
+  _globals['_REGISTERUSERGROUPREQUEST']._serialized_end=1207
+  _globals['_REGISTERUSERGROUPRESPONSE']._serialized_start=1209
+  _globals['_REGISTERUSERGROUPRESPONSE']._serialized_end=1236
+  _globals['_USERGROUPREQUEST']._serialized_start=1238
+  _globals['_USERGROUPREQUEST']._serialized_end=1270
+  _globals['_USERGROUPRESPONSE']._serialized_start=1272
+  _globals['_USERGROUPRESPONSE']._serialized_end=1369
+  _globals['_PROOFBRANCH']._serialized_start=1371
+  _globals['_PROOFBRANCH']._serialized_end=1430
+  _globals['_USERGROUPAUTHENTICATIONREQUEST']._serialized_start=1432
+  _globals['_USERGROUPAUTHENTICATIONREQUEST']._serialized_end=1540
+  _globals['_AUTH']._serialized_start=1600
+  _globals['_AUTH']._serialized_end=2471
 # @@protoc_insertion_point(module_scope)
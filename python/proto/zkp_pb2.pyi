@@ -1,65 +1,207 @@
+from google.protobuf.internal import containers as _containers
+from google.protobuf.internal import enum_type_wrapper as _enum_type_wrapper
 from google.protobuf import descriptor as _descriptor
 from google.protobuf import message as _message
-from typing import ClassVar as _ClassVar, Optional as _Optional
+from collections.abc import Iterable as _Iterable, Mapping as _Mapping
+from typing import ClassVar as _ClassVar, Optional as _Optional, Union as _Union
 
 DESCRIPTOR: _descriptor.FileDescriptor
 
+class Protocol(int, metaclass=_enum_type_wrapper.EnumTypeWrapper):
+    __slots__ = ()
+    CHAUM_PEDERSEN: _ClassVar[Protocol]
+    SCHNORR: _ClassVar[Protocol]
+    POLICY: _ClassVar[Protocol]
+CHAUM_PEDERSEN: Protocol
+SCHNORR: Protocol
+POLICY: Protocol
+
+class PolicyFactor(_message.Message):
+    __slots__ = ("protocol", "y1", "y2")
+    PROTOCOL_FIELD_NUMBER: _ClassVar[int]
+    Y1_FIELD_NUMBER: _ClassVar[int]
+    Y2_FIELD_NUMBER: _ClassVar[int]
+    protocol: Protocol
+    y1: bytes
+    y2: bytes
+    def __init__(self, protocol: _Optional[_Union[Protocol, str]] = ..., y1: _Optional[bytes] = ..., y2: _Optional[bytes] = ...) -> None: ...
+
+class KdfParameters(_message.Message):
+    __slots__ = ("salt", "memory_kib", "iterations", "parallelism")
+    SALT_FIELD_NUMBER: _ClassVar[int]
+    MEMORY_KIB_FIELD_NUMBER: _ClassVar[int]
+    ITERATIONS_FIELD_NUMBER: _ClassVar[int]
+    PARALLELISM_FIELD_NUMBER: _ClassVar[int]
+    salt: bytes
+    memory_kib: int
+    iterations: int
+    parallelism: int
+    def __init__(self, salt: _Optional[bytes] = ..., memory_kib: _Optional[int] = ..., iterations: _Optional[int] = ..., parallelism: _Optional[int] = ...) -> None: ...
+
 class RegisterRequest(_message.Message):
-    __slots__ = ["user", "y1", "y2"]
+    __slots__ = ("user", "y1", "y2", "group", "kdf", "protocol", "policy")
     USER_FIELD_NUMBER: _ClassVar[int]
     Y1_FIELD_NUMBER: _ClassVar[int]
     Y2_FIELD_NUMBER: _ClassVar[int]
+    GROUP_FIELD_NUMBER: _ClassVar[int]
+    KDF_FIELD_NUMBER: _ClassVar[int]
+    PROTOCOL_FIELD_NUMBER: _ClassVar[int]
+    POLICY_FIELD_NUMBER: _ClassVar[int]
     user: str
-    y1: int
-    y2: int
-    def __init__(self, user: _Optional[str] = ..., y1: _Optional[int] = ..., y2: _Optional[int] = ...) -> None: ...
+    y1: bytes
+    y2: bytes
+    group: str
+    kdf: KdfParameters
+    protocol: Protocol
+    policy: _containers.RepeatedCompositeFieldContainer[PolicyFactor]
+    def __init__(self, user: _Optional[str] = ..., y1: _Optional[bytes] = ..., y2: _Optional[bytes] = ..., group: _Optional[str] = ..., kdf: _Optional[_Union[KdfParameters, _Mapping]] = ..., protocol: _Optional[_Union[Protocol, str]] = ..., policy: _Optional[_Iterable[_Union[PolicyFactor, _Mapping]]] = ...) -> None: ...
 
 class RegisterResponse(_message.Message):
-    __slots__ = []
+    __slots__ = ()
     def __init__(self) -> None: ...
 
+class LoginParametersRequest(_message.Message):
+    __slots__ = ("user",)
+    USER_FIELD_NUMBER: _ClassVar[int]
+    user: str
+    def __init__(self, user: _Optional[str] = ...) -> None: ...
+
+class LoginParametersResponse(_message.Message):
+    __slots__ = ("group", "kdf", "protocol")
+    GROUP_FIELD_NUMBER: _ClassVar[int]
+    KDF_FIELD_NUMBER: _ClassVar[int]
+    PROTOCOL_FIELD_NUMBER: _ClassVar[int]
+    group: str
+    kdf: KdfParameters
+    protocol: Protocol
+    def __init__(self, group: _Optional[str] = ..., kdf: _Optional[_Union[KdfParameters, _Mapping]] = ..., protocol: _Optional[_Union[Protocol, str]] = ...) -> None: ...
+
 class AuthenticationChallengeRequest(_message.Message):
-    __slots__ = ["user", "r1", "r2"]
+    __slots__ = ("user", "r1", "r2", "commitments", "login_nonce")
     USER_FIELD_NUMBER: _ClassVar[int]
     R1_FIELD_NUMBER: _ClassVar[int]
     R2_FIELD_NUMBER: _ClassVar[int]
+    COMMITMENTS_FIELD_NUMBER: _ClassVar[int]
+    LOGIN_NONCE_FIELD_NUMBER: _ClassVar[int]
     user: str
-    r1: int
-    r2: int
-    def __init__(self, user: _Optional[str] = ..., r1: _Optional[int] = ..., r2: _Optional[int] = ...) -> None: ...
+    r1: bytes
+    r2: bytes
+    commitments: _containers.RepeatedScalarFieldContainer[bytes]
+    login_nonce: str
+    def __init__(self, user: _Optional[str] = ..., r1: _Optional[bytes] = ..., r2: _Optional[bytes] = ..., commitments: _Optional[_Iterable[bytes]] = ..., login_nonce: _Optional[str] = ...) -> None: ...
 
 class AuthenticationChallengeResponse(_message.Message):
-    __slots__ = ["auth_id", "c"]
+    __slots__ = ("auth_id", "c")
     AUTH_ID_FIELD_NUMBER: _ClassVar[int]
     C_FIELD_NUMBER: _ClassVar[int]
     auth_id: str
-    c: int
-    def __init__(self, auth_id: _Optional[str] = ..., c: _Optional[int] = ...) -> None: ...
+    c: bytes
+    def __init__(self, auth_id: _Optional[str] = ..., c: _Optional[bytes] = ...) -> None: ...
 
 class AuthenticationAnswerRequest(_message.Message):
-    __slots__ = ["auth_id", "s"]
+    __slots__ = ("auth_id", "s", "responses")
     AUTH_ID_FIELD_NUMBER: _ClassVar[int]
     S_FIELD_NUMBER: _ClassVar[int]
+    RESPONSES_FIELD_NUMBER: _ClassVar[int]
     auth_id: str
-    s: int
-    def __init__(self, auth_id: _Optional[str] = ..., s: _Optional[int] = ...) -> None: ...
+    s: bytes
+    responses: _containers.RepeatedScalarFieldContainer[bytes]
+    def __init__(self, auth_id: _Optional[str] = ..., s: _Optional[bytes] = ..., responses: _Optional[_Iterable[bytes]] = ...) -> None: ...
 
 class AuthenticationAnswerResponse(_message.Message):
-    __slots__ = ["session_id"]
+    __slots__ = ("session_id",)
     SESSION_ID_FIELD_NUMBER: _ClassVar[int]
     session_id: str
     def __init__(self, session_id: _Optional[str] = ...) -> None: ...
 
+class LoginNonceRequest(_message.Message):
+    __slots__ = ("user",)
+    USER_FIELD_NUMBER: _ClassVar[int]
+    user: str
+    def __init__(self, user: _Optional[str] = ...) -> None: ...
+
+class LoginNonceResponse(_message.Message):
+    __slots__ = ("login_nonce",)
+    LOGIN_NONCE_FIELD_NUMBER: _ClassVar[int]
+    login_nonce: str
+    def __init__(self, login_nonce: _Optional[str] = ...) -> None: ...
+
 class AsyncAuthenticationRequest(_message.Message):
-    __slots__ = ["user", "r1", "r2", "c", "s"]
+    __slots__ = ("user", "r1", "r2", "c", "s", "login_nonce", "commitments", "responses")
     USER_FIELD_NUMBER: _ClassVar[int]
     R1_FIELD_NUMBER: _ClassVar[int]
     R2_FIELD_NUMBER: _ClassVar[int]
     C_FIELD_NUMBER: _ClassVar[int]
     S_FIELD_NUMBER: _ClassVar[int]
+    LOGIN_NONCE_FIELD_NUMBER: _ClassVar[int]
+    COMMITMENTS_FIELD_NUMBER: _ClassVar[int]
+    RESPONSES_FIELD_NUMBER: _ClassVar[int]
     user: str
-    r1: int
-    r2: int
-    c: int
-    s: int
-    def __init__(self, user: _Optional[str] = ..., r1: _Optional[int] = ..., r2: _Optional[int] = ..., c: _Optional[int] = ..., s: _Optional[int] = ...) -> None: ...
+    r1: bytes
+    r2: bytes
+    c: bytes
+    s: bytes
+    login_nonce: str
+    commitments: _containers.RepeatedScalarFieldContainer[bytes]
+    responses: _containers.RepeatedScalarFieldContainer[bytes]
+    def __init__(self, user: _Optional[str] = ..., r1: _Optional[bytes] = ..., r2: _Optional[bytes] = ..., c: _Optional[bytes] = ..., s: _Optional[bytes] = ..., login_nonce: _Optional[str] = ..., commitments: _Optional[_Iterable[bytes]] = ..., responses: _Optional[_Iterable[bytes]] = ...) -> None: ...
+
+class UserPublicKey(_message.Message):
+    __slots__ = ("user", "y1", "y2")
+    USER_FIELD_NUMBER: _ClassVar[int]
+    Y1_FIELD_NUMBER: _ClassVar[int]
+    Y2_FIELD_NUMBER: _ClassVar[int]
+    user: str
+    y1: bytes
+    y2: bytes
+    def __init__(self, user: _Optional[str] = ..., y1: _Optional[bytes] = ..., y2: _Optional[bytes] = ...) -> None: ...
+
+class RegisterUserGroupRequest(_message.Message):
+    __slots__ = ("name", "users")
+    NAME_FIELD_NUMBER: _ClassVar[int]
+    USERS_FIELD_NUMBER: _ClassVar[int]
+    name: str
+    users: _containers.RepeatedScalarFieldContainer[str]
+    def __init__(self, name: _Optional[str] = ..., users: _Optional[_Iterable[str]] = ...) -> None: ...
+
+class RegisterUserGroupResponse(_message.Message):
+    __slots__ = ()
+    def __init__(self) -> None: ...
+
+class UserGroupRequest(_message.Message):
+    __slots__ = ("name",)
+    NAME_FIELD_NUMBER: _ClassVar[int]
+    name: str
+    def __init__(self, name: _Optional[str] = ...) -> None: ...
+
+class UserGroupResponse(_message.Message):
+    __slots__ = ("group", "members", "login_nonce")
+    GROUP_FIELD_NUMBER: _ClassVar[int]
+    MEMBERS_FIELD_NUMBER: _ClassVar[int]
+    LOGIN_NONCE_FIELD_NUMBER: _ClassVar[int]
+    group: str
+    members: _containers.RepeatedCompositeFieldContainer[UserPublicKey]
+    login_nonce: str
+    def __init__(self, group: _Optional[str] = ..., members: _Optional[_Iterable[_Union[UserPublicKey, _Mapping]]] = ..., login_nonce: _Optional[str] = ...) -> None: ...
+
+class ProofBranch(_message.Message):
+    __slots__ = ("r1", "r2", "c", "s")
+    R1_FIELD_NUMBER: _ClassVar[int]
+    R2_FIELD_NUMBER: _ClassVar[int]
+    C_FIELD_NUMBER: _ClassVar[int]
+    S_FIELD_NUMBER: _ClassVar[int]
+    r1: bytes
+    r2: bytes
+    c: bytes
+    s: bytes
+    def __init__(self, r1: _Optional[bytes] = ..., r2: _Optional[bytes] = ..., c: _Optional[bytes] = ..., s: _Optional[bytes] = ...) -> None: ...
+
+class UserGroupAuthenticationRequest(_message.Message):
+    __slots__ = ("name", "branches", "login_nonce")
+    NAME_FIELD_NUMBER: _ClassVar[int]
+    BRANCHES_FIELD_NUMBER: _ClassVar[int]
+    LOGIN_NONCE_FIELD_NUMBER: _ClassVar[int]
+    name: str
+    branches: _containers.RepeatedCompositeFieldContainer[ProofBranch]
+    login_nonce: str
+    def __init__(self, name: _Optional[str] = ..., branches: _Optional[_Iterable[_Union[ProofBranch, _Mapping]]] = ..., login_nonce: _Optional[str] = ...) -> None: ...
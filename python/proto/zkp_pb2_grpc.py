@@ -19,6 +19,16 @@ class AuthStub(object):
                 request_serializer=zkp__pb2.RegisterRequest.SerializeToString,
                 response_deserializer=zkp__pb2.RegisterResponse.FromString,
                 )
+        self.CreateLoginNonce = channel.unary_unary(
+                '/zkp_auth.Auth/CreateLoginNonce',
+                request_serializer=zkp__pb2.LoginNonceRequest.SerializeToString,
+                response_deserializer=zkp__pb2.LoginNonceResponse.FromString,
+                )
+        self.GetLoginParameters = channel.unary_unary(
+                '/zkp_auth.Auth/GetLoginParameters',
+                request_serializer=zkp__pb2.LoginParametersRequest.SerializeToString,
+                response_deserializer=zkp__pb2.LoginParametersResponse.FromString,
+                )
         self.VerifyAuthenticationAsync = channel.unary_unary(
                 '/zkp_auth.Auth/VerifyAuthenticationAsync',
                 request_serializer=zkp__pb2.AsyncAuthenticationRequest.SerializeToString,
@@ -34,6 +44,21 @@ class AuthStub(object):
                 request_serializer=zkp__pb2.AuthenticationAnswerRequest.SerializeToString,
                 response_deserializer=zkp__pb2.AuthenticationAnswerResponse.FromString,
                 )
+        self.RegisterUserGroup = channel.unary_unary(
+                '/zkp_auth.Auth/RegisterUserGroup',
+                request_serializer=zkp__pb2.RegisterUserGroupRequest.SerializeToString,
+                response_deserializer=zkp__pb2.RegisterUserGroupResponse.FromString,
+                )
+        self.GetUserGroup = channel.unary_unary(
+                '/zkp_auth.Auth/GetUserGroup',
+                request_serializer=zkp__pb2.UserGroupRequest.SerializeToString,
+                response_deserializer=zkp__pb2.UserGroupResponse.FromString,
+                )
+        self.VerifyUserGroupAuthentication = channel.unary_unary(
+                '/zkp_auth.Auth/VerifyUserGroupAuthentication',
+                request_serializer=zkp__pb2.UserGroupAuthenticationRequest.SerializeToString,
+                response_deserializer=zkp__pb2.AuthenticationAnswerResponse.FromString,
+                )
 
 
 class AuthServicer(object):
@@ -45,6 +70,18 @@ class AuthServicer(object):
         context.set_details('Method not implemented!')
         raise NotImplementedError('Method not implemented!')
 
+    def CreateLoginNonce(self, request, context):
+        """Missing associated documentation comment in .proto file."""
+        context.set_code(grpc.StatusCode.UNIMPLEMENTED)
+        context.set_details('Method not implemented!')
+        raise NotImplementedError('Method not implemented!')
+
+    def GetLoginParameters(self, request, context):
+        """Missing associated documentation comment in .proto file."""
+        context.set_code(grpc.StatusCode.UNIMPLEMENTED)
+        context.set_details('Method not implemented!')
+        raise NotImplementedError('Method not implemented!')
+
     def VerifyAuthenticationAsync(self, request, context):
         """Missing associated documentation comment in .proto file."""
         context.set_code(grpc.StatusCode.UNIMPLEMENTED)
@@ -63,6 +100,24 @@ class AuthServicer(object):
         context.set_details('Method not implemented!')
         raise NotImplementedError('Method not implemented!')
 
+    def RegisterUserGroup(self, request, context):
+        """Missing associated documentation comment in .proto file."""
+        context.set_code(grpc.StatusCode.UNIMPLEMENTED)
+        context.set_details('Method not implemented!')
+        raise NotImplementedError('Method not implemented!')
+
+    def GetUserGroup(self, request, context):
+        """Missing associated documentation comment in .proto file."""
+        context.set_code(grpc.StatusCode.UNIMPLEMENTED)
+        context.set_details('Method not implemented!')
+        raise NotImplementedError('Method not implemented!')
+
+    def VerifyUserGroupAuthentication(self, request, context):
+        """Missing associated documentation comment in .proto file."""
+        context.set_code(grpc.StatusCode.UNIMPLEMENTED)
+        context.set_details('Method not implemented!')
+        raise NotImplementedError('Method not implemented!')
+
 
 def add_AuthServicer_to_server(servicer, server):
     rpc_method_handlers = {
@@ -71,6 +126,16 @@ def add_AuthServicer_to_server(servicer, server):
                     request_deserializer=zkp__pb2.RegisterRequest.FromString,
                     response_serializer=zkp__pb2.RegisterResponse.SerializeToString,
             ),
+            'CreateLoginNonce': grpc.unary_unary_rpc_method_handler(
+                    servicer.CreateLoginNonce,
+                    request_deserializer=zkp__pb2.LoginNonceRequest.FromString,
+                    response_serializer=zkp__pb2.LoginNonceResponse.SerializeToString,
+            ),
+            'GetLoginParameters': grpc.unary_unary_rpc_method_handler(
+                    servicer.GetLoginParameters,
+                    request_deserializer=zkp__pb2.LoginParametersRequest.FromString,
+                    response_serializer=zkp__pb2.LoginParametersResponse.SerializeToString,
+            ),
             'VerifyAuthenticationAsync': grpc.unary_unary_rpc_method_handler(
                     servicer.VerifyAuthenticationAsync,
                     request_deserializer=zkp__pb2.AsyncAuthenticationRequest.FromString,
@@ -86,6 +151,21 @@ def add_AuthServicer_to_server(servicer, server):
                     request_deserializer=zkp__pb2.AuthenticationAnswerRequest.FromString,
                     response_serializer=zkp__pb2.AuthenticationAnswerResponse.SerializeToString,
             ),
+            'RegisterUserGroup': grpc.unary_unary_rpc_method_handler(
+                    servicer.RegisterUserGroup,
+                    request_deserializer=zkp__pb2.RegisterUserGroupRequest.FromString,
+                    response_serializer=zkp__pb2.RegisterUserGroupResponse.SerializeToString,
+            ),
+            'GetUserGroup': grpc.unary_unary_rpc_method_handler(
+                    servicer.GetUserGroup,
+                    request_deserializer=zkp__pb2.UserGroupRequest.FromString,
+                    response_serializer=zkp__pb2.UserGroupResponse.SerializeToString,
+            ),
+            'VerifyUserGroupAuthentication': grpc.unary_unary_rpc_method_handler(
+                    servicer.VerifyUserGroupAuthentication,
+                    request_deserializer=zkp__pb2.UserGroupAuthenticationRequest.FromString,
+                    response_serializer=zkp__pb2.AuthenticationAnswerResponse.SerializeToString,
+            ),
     }
     generic_handler = grpc.method_handlers_generic_handler(
             'zkp_auth.Auth', rpc_method_handlers)
@@ -113,6 +193,40 @@ class Auth(object):
             options, channel_credentials,
             insecure, call_credentials, compression, wait_for_ready, timeout, metadata)
 
+    @staticmethod
+    def CreateLoginNonce(request,
+            target,
+            options=(),
+            channel_credentials=None,
+            call_credentials=None,
+            insecure=False,
+            compression=None,
+            wait_for_ready=None,
+            timeout=None,
+            metadata=None):
+        return grpc.experimental.unary_unary(request, target, '/zkp_auth.Auth/CreateLoginNonce',
+            zkp__pb2.LoginNonceRequest.SerializeToString,
+            zkp__pb2.LoginNonceResponse.FromString,
+            options, channel_credentials,
+            insecure, call_credentials, compression, wait_for_ready, timeout, metadata)
+
+    @staticmethod
+    def GetLoginParameters(request,
+            target,
+            options=(),
+            channel_credentials=None,
+            call_credentials=None,
+            insecure=False,
+            compression=None,
+            wait_for_ready=None,
+            timeout=None,
+            metadata=None):
+        return grpc.experimental.unary_unary(request, target, '/zkp_auth.Auth/GetLoginParameters',
+            zkp__pb2.LoginParametersRequest.SerializeToString,
+            zkp__pb2.LoginParametersResponse.FromString,
+            options, channel_credentials,
+            insecure, call_credentials, compression, wait_for_ready, timeout, metadata)
+
     @staticmethod
     def VerifyAuthenticationAsync(request,
             target,
@@ -163,3 +277,54 @@ class Auth(object):
             zkp__pb2.AuthenticationAnswerResponse.FromString,
             options, channel_credentials,
             insecure, call_credentials, compression, wait_for_ready, timeout, metadata)
+
+    @staticmethod
+    def RegisterUserGroup(request,
+            target,
+            options=(),
+            channel_credentials=None,
+            call_credentials=None,
+            insecure=False,
+            compression=None,
+            wait_for_ready=None,
+            timeout=None,
+            metadata=None):
+        return grpc.experimental.unary_unary(request, target, '/zkp_auth.Auth/RegisterUserGroup',
+            zkp__pb2.RegisterUserGroupRequest.SerializeToString,
+            zkp__pb2.RegisterUserGroupResponse.FromString,
+            options, channel_credentials,
+            insecure, call_credentials, compression, wait_for_ready, timeout, metadata)
+
+    @staticmethod
+    def GetUserGroup(request,
+            target,
+            options=(),
+            channel_credentials=None,
+            call_credentials=None,
+            insecure=False,
+            compression=None,
+            wait_for_ready=None,
+            timeout=None,
+            metadata=None):
+        return grpc.experimental.unary_unary(request, target, '/zkp_auth.Auth/GetUserGroup',
+            zkp__pb2.UserGroupRequest.SerializeToString,
+            zkp__pb2.UserGroupResponse.FromString,
+            options, channel_credentials,
+            insecure, call_credentials, compression, wait_for_ready, timeout, metadata)
+
+    @staticmethod
+    def VerifyUserGroupAuthentication(request,
+            target,
+            options=(),
+            channel_credentials=None,
+            call_credentials=None,
+            insecure=False,
+            compression=None,
+            wait_for_ready=None,
+            timeout=None,
+            metadata=None):
+        return grpc.experimental.unary_unary(request, target, '/zkp_auth.Auth/VerifyUserGroupAuthentication',
+            zkp__pb2.UserGroupAuthenticationRequest.SerializeToString,
+            zkp__pb2.AuthenticationAnswerResponse.FromString,
+            options, channel_credentials,
+            insecure, call_credentials, compression, wait_for_ready, timeout, metadata)
@@ -4,15 +4,23 @@ import hashlib
 import logging
 import grpc
 import datetime
+import threading
+import time
+from collections import deque
 
 from proto.zkp_pb2 import (
+    CHAUM_PEDERSEN,
     RegisterResponse,
+    LoginParametersResponse,
     AuthenticationChallengeResponse,
-    AuthenticationAnswerResponse
+    AuthenticationAnswerResponse,
+    LoginNonceResponse
 )
 
 import proto.zkp_pb2_grpc as pt
-from zkrypto import ChaumPedersenVerifier
+from zkrypto import ChaumPedersenVerifier, Generator
+from zkrypto.encoding import EncodingError, decode, encode
+from utils import DEFAULT_GROUP, async_login_context
 
 def generate_non_colluding_random_string(colluding_set):
 
@@ -21,10 +29,78 @@ def generate_non_colluding_random_string(colluding_set):
         id = hashlib.sha256((str(random.randint(0, 1 << 64))).encode()).hexdigest() # Generate a random 64 bit int till non-colluding
     return id
         
+def decode_element(data, field, context):
+    # Decodes a group element of the default group, aborting unless it lies in the prime-order subgroup
+    p, q, _ = Generator.default
+    try:
+        x = decode(data, p)
+    except EncodingError as e:
+        context.abort(grpc.StatusCode.INVALID_ARGUMENT, "Invalid %s: %s" % (field, e))
+    if x < 2 or pow(x, q, p) != 1:
+        context.abort(grpc.StatusCode.INVALID_ARGUMENT, "Invalid %s: not in the prime-order subgroup" % (field))
+    return x
+
+def decode_scalar(data, field, context):
+    # Decodes a scalar of the default group, aborting if its encoding is not canonical
+    _, q, _ = Generator.default
+    try:
+        return decode(data, q)
+    except EncodingError as e:
+        context.abort(grpc.StatusCode.INVALID_ARGUMENT, "Invalid %s: %s" % (field, e))
+
+# Seconds a login nonce stays valid, and how many one user and all users can hold, as on the Rust server
+LOGIN_NONCE_TTL = 60
+MAX_LOGIN_NONCES_PER_USER = 8
+MAX_LOGIN_NONCES = 10000
+
+class LoginNonces:
+    # Unused login nonces and the user they were issued to. A new nonce replaces the user's oldest once
+    # they hold MAX_LOGIN_NONCES_PER_USER, so flooding one user locks no one else out. A user's expired
+    # nonces are dropped when they get a new one, and every user's at most once per LOGIN_NONCE_TTL.
+    def __init__(self):
+        self.nonces = {} # Login nonce -> (user, time issued)
+        self.by_user = {} # User -> their login nonces, oldest first
+        self.swept = time.monotonic()
+        self.lock = threading.Lock()
+
+    def issue(self, user):
+        # Returns a new login nonce for user, or None while MAX_LOGIN_NONCES live ones are outstanding
+        with self.lock:
+            now = time.monotonic()
+            if now - self.swept >= LOGIN_NONCE_TTL:
+                self.nonces = {n: v for n, v in self.nonces.items() if now - v[1] < LOGIN_NONCE_TTL}
+                self.by_user = {u: deque(n for n in ns if n in self.nonces) for u, ns in self.by_user.items()}
+                self.by_user = {u: ns for u, ns in self.by_user.items() if ns}
+                self.swept = now
+            nonces = self.by_user.setdefault(user, deque())
+            while nonces and (len(nonces) >= MAX_LOGIN_NONCES_PER_USER or now - self.nonces[nonces[0]][1] >= LOGIN_NONCE_TTL):
+                del self.nonces[nonces.popleft()]
+            if len(self.nonces) >= MAX_LOGIN_NONCES:
+                if not nonces:
+                    del self.by_user[user]
+                return None
+            login_nonce = generate_non_colluding_random_string(self.nonces)
+            self.nonces[login_nonce] = (user, now)
+            nonces.append(login_nonce)
+            return login_nonce
+
+    def take(self, login_nonce, user):
+        # Consumes a login nonce, which only succeeds once, before it expires and only for the user it was issued to
+        with self.lock:
+            issued = self.nonces.pop(login_nonce, None)
+            if issued is None:
+                return False
+            owner, issued_at = issued
+            self.by_user[owner].remove(login_nonce)
+            if not self.by_user[owner]:
+                del self.by_user[owner]
+            return owner == user and time.monotonic() - issued_at < LOGIN_NONCE_TTL
+
 class User:
-    def __init__(self, name, y1, y2):
+    def __init__(self, name, y1, y2, kdf=None):
         self.name = name
         self.verifier = ChaumPedersenVerifier(y1, y2, default=True)
+        self.kdf = kdf # Password salt and parameters, returned to clients before they log in
         self.auth_id = None
 
     def set_auth_id(self, auth_id):
@@ -44,6 +120,7 @@ class ChaumPedersenProtocolServer(pt.AuthServicer):
         self.users = {}
         self.auth_ids = {}
         self.sessions = {}
+        self.login_nonces = LoginNonces()
         self.verifier = None
 
 
@@ -51,10 +128,37 @@ class ChaumPedersenProtocolServer(pt.AuthServicer):
         if request.user in self.users:
             logging.warning("User %s already exists" % (request.user))
             context.abort(grpc.StatusCode.ALREADY_EXISTS, "User already exists")
-        self.users[request.user] = User(request.user, request.y1, request.y2)
+        # Other groups, Schnorr and policy users are only served by the Rust server
+        if request.group not in ("", DEFAULT_GROUP):
+            context.abort(grpc.StatusCode.INVALID_ARGUMENT, "Unknown group %s" % (request.group))
+        if request.protocol != CHAUM_PEDERSEN or len(request.policy) > 0:
+            context.abort(grpc.StatusCode.INVALID_ARGUMENT, "Unsupported protocol %d" % (request.protocol))
+        y1 = decode_element(request.y1, "y1", context)
+        y2 = decode_element(request.y2, "y2", context)
+        kdf = request.kdf if request.HasField("kdf") else None
+        self.users[request.user] = User(request.user, y1, y2, kdf)
         logging.info("Succesfully registered user: %s" % (str(self.users[request.user])))
         return RegisterResponse()
     
+    def GetLoginParameters(self, request, context):
+        if not request.user in self.users:
+            logging.warning("User %s not found" % (request.user))
+            context.abort(grpc.StatusCode.NOT_FOUND, "User not found")
+        user = self.users[request.user]
+        response = LoginParametersResponse(group=DEFAULT_GROUP, protocol=CHAUM_PEDERSEN)
+        if user.kdf is not None:
+            response.kdf.CopyFrom(user.kdf)
+        return response
+
+    def CreateLoginNonce(self, request, context):
+        if not request.user in self.users:
+            logging.warning("User %s not found" % (request.user))
+            context.abort(grpc.StatusCode.NOT_FOUND, "User not found")
+        login_nonce = self.login_nonces.issue(request.user)
+        if login_nonce is None:
+            context.abort(grpc.StatusCode.RESOURCE_EXHAUSTED, "Too many logins in progress, try again later")
+        return LoginNonceResponse(login_nonce=login_nonce)
+
     def CreateAuthenticationChallenge(self, request, context):
         # Check if user is registered
         if not request.user is None and not request.user in self.users:
@@ -63,13 +167,19 @@ class ChaumPedersenProtocolServer(pt.AuthServicer):
         
         user = self.users[request.user]
 
+        # Each commitment is bound to a fresh login nonce, as on the Rust server
+        if not self.login_nonces.take(request.login_nonce, request.user):
+            context.abort(grpc.StatusCode.PERMISSION_DENIED, "Login nonce not found, expired or already used")
+
         auth_id = generate_non_colluding_random_string(self.auth_ids)
         user.set_auth_id(auth_id)
         self.auth_ids[auth_id] = user # Mapping user object too
 
-        c = user.verifier.verify_sync_a(request.r1, request.r2) # Challenge
+        r1 = decode_element(request.r1, "r1", context)
+        r2 = decode_element(request.r2, "r2", context)
+        c = user.verifier.verify_sync_a(r1, r2) # Challenge
         logging.info("Created challenge for user: %s" % (str(self.users[request.user])))
-        return AuthenticationChallengeResponse(auth_id=auth_id, c=c)
+        return AuthenticationChallengeResponse(auth_id=auth_id, c=encode(c, user.verifier.q))
     
     def VerifyAuthentication(self, request, context):
 
@@ -82,7 +192,7 @@ class ChaumPedersenProtocolServer(pt.AuthServicer):
             logging.warning("Simultaneous authentication of user %s != %s" % (user.auth_id , request.auth_id))
             context.abort(grpc.StatusCode.ABORTED, "Simultaneous authentication of user %s != %s" % (user.auth_id , request.auth_id))
         
-        verification_result = user.verifier.verify_sync_b(request.s)
+        verification_result = user.verifier.verify_sync_b(decode_scalar(request.s, "s", context))
         logging.info("Verification Successful for user: %s" % (str(user)))
         if not verification_result:
             logging.warning("Verification Unsuccesful %s != %s" % (user.auth_id , request.auth_id))
@@ -108,12 +218,20 @@ class ChaumPedersenProtocolServer(pt.AuthServicer):
 
         user = self.users[request.user]
 
+        # The nonce is spent even if the proof fails, so every attempt needs a fresh one
+        if not self.login_nonces.take(request.login_nonce, request.user):
+            context.abort(grpc.StatusCode.PERMISSION_DENIED, "Login nonce not found, expired or already used")
 
-        verification_result = user.verifier.verify_async(request.r1, request.r2, request.c, request.s)
+        r1 = decode_element(request.r1, "r1", context)
+        r2 = decode_element(request.r2, "r2", context)
+        c = decode_scalar(request.c, "c", context)
+        s = decode_scalar(request.s, "s", context)
+        login_context = async_login_context(request.user, request.login_nonce)
+        verification_result = user.verifier.verify_async(r1, r2, c, s, login_context)
 
         
         if not verification_result:
-            logging.warning("Verification Unsuccesful %s != %s" % (user.auth_id , request.auth_id))
+            logging.warning("Async Verification Unsuccesful for user %s" % (request.user))
             context.abort(grpc.StatusCode.PERMISSION_DENIED, "Verification Unsuccesful")
         logging.info("Async Verification Successful for user: %s" % (str(user)))
         session_id = generate_non_colluding_random_string(self.sessions)
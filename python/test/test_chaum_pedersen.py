@@ -1,5 +1,7 @@
 import pytest
 from zkrypto import ChaumPedersenProver, ChaumPedersenVerifier
+from zkrypto.encoding import EncodingError, decode, encode
+from utils import async_login_context
 
 class TestSchnorrProtocol:
     @staticmethod
@@ -306,6 +308,24 @@ class TestSchnorrProtocol:
         verification_result = verifier.verify_sync_b(s)
         
         assert verification_result == False
+
+    @staticmethod
+    def test_chaum_pedersen_accepts_rust_proof():
+        # Non-interactive proof made by the Rust prover in the default group for user "alice"
+        # and login nonce "nonce123"
+        y1, y2, r1, r2, c, s = 48963572, 364719980, 275609825, 250471004, 426148, 401843
+        verifier = ChaumPedersenVerifier(y1, y2, default=True)
+        context = async_login_context("alice", "nonce123")
+
+        assert verifier.verify(r1, r2, c, s, context)
+        assert not verifier.verify(r1, r2, c, s, async_login_context("alice", "nonce124"))
+
+        # Both implementations put the same bytes on the wire
+        assert encode(y1, verifier.p) == bytes([2, 235, 31, 244])
+        assert encode(c, verifier.q) == bytes([6, 128, 164])
+        assert decode(encode(c, verifier.q), verifier.q) == c
+        with pytest.raises(EncodingError):
+            decode(bytes([6, 128]), verifier.q)
 # Run pytest when the script is executed
 if __name__ == "__main__":
     pytest.main()
\ No newline at end of file
@@ -0,0 +1,9 @@
+# Name of the only group the Python server registers users in, the same group as the Rust "default"
+DEFAULT_GROUP = "default"
+
+def async_login_context(user, login_nonce):
+    # Context bound into non-interactive login proofs, byte for byte as in the Rust utils module, so a
+    # proof for one user cannot be replayed for another and, as each login nonce is accepted once,
+    # not for the same user either
+    user = user.encode()
+    return b"zkp_auth/verify-authentication-async/" + len(user).to_bytes(8, "big") + user + login_nonce.encode()
@@ -3,6 +3,39 @@ import hashlib
 
 from zkrypto.prime import Prime
 from zkrypto.generator import Generator
+from zkrypto.encoding import byte_len, encode, encode_parameters
+
+# Domain separation tag for the Fiat-Shamir challenge hash, shared with the Rust implementation
+FIAT_SHAMIR_DOMAIN = b"zkp_auth/chaum-pedersen/fiat-shamir/v1"
+
+# Extra hash bytes drawn beyond the length of q, so that reducing mod q is close to uniform
+HASH_EXTRA_BYTES = 16
+
+def absorb(hasher, data):
+    # Absorbs a length-prefixed byte string so that adjacent fields cannot be confused
+    hasher.update(len(data).to_bytes(8, "big"))
+    hasher.update(data)
+
+def scalar_from_hash(digest, q):
+    # Expands a digest with a counter to byte_len(q) + HASH_EXTRA_BYTES bytes and reduces it mod q
+    length = byte_len(q) + HASH_EXTRA_BYTES
+    wide = b""
+    counter = 0
+    while len(wide) < length:
+        wide += hashlib.sha256(counter.to_bytes(4, "big") + digest).digest()
+        counter += 1
+    return int.from_bytes(wide[:length], "big") % q
+
+def fiat_shamir_challenge(p, q, g, h, y1, y2, r1, r2, context=b""):
+    # Computes the Fiat-Shamir challenge H(domain, group, y1, y2, r1, r2, context) mod q, as the Rust
+    # implementation does, so proofs made by either are accepted by both
+    hasher = hashlib.sha256()
+    absorb(hasher, FIAT_SHAMIR_DOMAIN)
+    absorb(hasher, encode_parameters(p, q, g, h))
+    for x in (y1, y2, r1, r2):
+        absorb(hasher, encode(x, p))
+    absorb(hasher, context)
+    return scalar_from_hash(hasher.digest(), q)
 
 class ChaumPedersenProver:
 
@@ -36,15 +69,15 @@ class ChaumPedersenProver:
         self.y2 = pow(self.h, self._x, self.p)
         return self._x, self.y1, self.y2
 
-    def prove_async(self, c=None):
+    def prove_async(self, c=None, context=b""):
         # Prover's part of the ChaumPedersen protocol
 
         self.prove_sync_a()
 
 
-        # Compute challenge c = H(r1 || r2 || y1 || y2)
+        # Compute challenge c = H(domain, group, y1, y2, r1, r2, context)
         if c is None:
-            c = int(hashlib.sha256((str(self.r1) + str(self.r2) + str(self.y1) + str(self.y2)).encode()).hexdigest(), 16) % self.p
+            c = fiat_shamir_challenge(self.p, self.q, self.g, self.h, self.y1, self.y2, self.r1, self.r2, context)
         
         s = self.prove_sync_b(c)
 
@@ -88,15 +121,15 @@ class ChaumPedersenVerifier():
         self.y1 = y1
         self.y2 = y2
     
-    def verify(self, r1, r2, c, s):
-        return self.verify_async(r1, r2, c, s)
+    def verify(self, r1, r2, c, s, context=b""):
+        return self.verify_async(r1, r2, c, s, context)
 
-    def verify_async(self, r1, r2, c, s):
+    def verify_async(self, r1, r2, c, s, context=b""):
         self.r1 = r1
         self.r2 = r2
         self.c = c 
         # Verify the commitment is a proper challenge
-        c_ =  int(hashlib.sha256((str(self.r1) + str(self.r2) + str(self.y1) + str(self.y2)).encode()).hexdigest(), 16) % self.p
+        c_ = fiat_shamir_challenge(self.p, self.q, self.g, self.h, self.y1, self.y2, self.r1, self.r2, context)
         if self.c != c_:
             return False
         return self.verify_sync_b(s)
@@ -104,7 +137,8 @@ class ChaumPedersenVerifier():
     def verify_sync_a(self, r1, r2):
         self.r1 = r1
         self.r2 = r2
-        self.c = random.randint(1, self.p - 2)
+        # The challenge is a scalar, drawn below q so that it has a canonical encoding
+        self.c = random.randint(1, self.q - 1)
         return self.c
     
     def verify_sync_b(self, s):
@@ -0,0 +1,33 @@
+class EncodingError(ValueError):
+    # Raised when a value cannot be converted to or from its fixed-length wire encoding
+    pass
+
+def byte_len(modulus):
+    # Number of bytes needed to encode any integer in [0, modulus)
+    return max(1, ((modulus - 1).bit_length() + 7) // 8)
+
+def encode(n, modulus):
+    # Encodes n in [0, modulus) as a big-endian byte string of exactly byte_len(modulus) bytes
+    if n < 0:
+        raise EncodingError("negative value cannot be encoded")
+    if n >= modulus:
+        raise EncodingError("value is not reduced modulo the group modulus")
+    return n.to_bytes(byte_len(modulus), "big")
+
+def decode(data, modulus):
+    # Decodes a big-endian byte string produced by encode, rejecting non-canonical inputs
+    length = byte_len(modulus)
+    if len(data) != length:
+        raise EncodingError("expected %d bytes, got %d" % (length, len(data)))
+    n = int.from_bytes(data, "big")
+    if n >= modulus:
+        raise EncodingError("value is not reduced modulo the group modulus")
+    return n
+
+def encode_parameters(p, q, g, h):
+    # Length-prefixed big-endian p, q, g and h, as hashed into Fiat-Shamir challenges
+    out = b"modp"
+    for x in (p, q, g, h):
+        data = x.to_bytes((x.bit_length() + 7) // 8, "big")
+        out += len(data).to_bytes(4, "big") + data
+    return out
@@ -30,4 +30,5 @@ class Generator(object):
                 if len(G) == num:
                     return p, q, G  # Return p, q, and g as generator parameters
     
-    default = (33599304334943, 1820705773, [25395732195142, 12433296605365]) # Default params
\ No newline at end of file
+    # Default params, the group registered as "default" on both the Python and Rust servers
+    default = (421360559, 455033, [103117051, 339533085])
\ No newline at end of file
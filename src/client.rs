@@ -3,13 +3,23 @@ use std::thread;
 use std::time::Duration;
 use std::env;
 
-use num_bigint::BigInt;
-use num_traits::ToPrimitive;
-
 use log::{debug, LevelFilter};
+use rand::rngs::OsRng;
+use rand::seq::SliceRandom;
+use tonic::transport::Channel;
 
 // Import modules from the current crate
-use crate::zkrypto::chaum_pedersen::ChaumPedersenProver;
+use crate::zkrypto::chaum_pedersen::{ChaumPedersenProver, KeyPair};
+use crate::zkrypto::composition::{AndProver, FactorKind, OrProver};
+use crate::zkrypto::encoding::EncodingError;
+use crate::zkrypto::nonce::NonceMode;
+use crate::zkrypto::generator::get_named;
+use crate::zkrypto::group::Group;
+use crate::zkrypto::password::{self, KdfParams};
+use crate::zkrypto::ristretto::RistrettoGroup;
+use crate::zkrypto::schnorr::SchnorrProver;
+use crate::zkrypto::scalar::Secret;
+use crate::utils::{group_validation, DEFAULT_GROUP, RISTRETTO_GROUP};
 
 // Define the module structure for the generated proto files
 pub mod zkp_auth {
@@ -20,117 +30,491 @@ pub mod zkp_auth {
 use zkp_auth::auth_client::AuthClient;
 use zkp_auth::{
     RegisterRequest,
+    KdfParameters,
+    LoginParametersRequest,
     AuthenticationChallengeRequest,
     AuthenticationAnswerRequest,
+    LoginNonceRequest,
+    AsyncAuthenticationRequest,
+    Protocol,
+    RegisterUserGroupRequest,
+    UserGroupRequest,
+    UserGroupAuthenticationRequest,
+    ProofBranch,
+    PolicyFactor,
 };
 
 // Import other modules from the crate
-pub mod zkrypto;
+use zkp::zkrypto;
 pub mod utils;
 
-// Main async function
-#[tokio::main]
-async fn main() -> Result<(), Box<dyn std::error::Error>> {
-    // Initialize the logging system
-    env_logger::init();
-    log::set_max_level(LevelFilter::Debug);
+// Registers a fresh user with a password-derived key in the given group and protocol, then
+// fetches the user's salt and parameters, re-derives the key and logs in interactively and non-interactively
+async fn register_and_login<G: Group>(
+    client: &mut AuthClient<Channel>,
+    params: G,
+    group_name: &str,
+    protocol: Protocol,
+) -> Result<(), Box<dyn std::error::Error>> {
+    // Operating system randomness, which unlike thread_rng can be held across awaits
+    let mut rng = OsRng;
 
-    // Parse command line arguments
-    let args: Vec<String> = env::args().collect();
-    let host: String;
-    match args.len() {
-        2 => host = format!("https://{}:50051", &args[1]),
-        3 => host = format!("https://{}:{}", &args[1], &args[2]),
-        _ => host = "https://localhost:50051".to_string(),
-    }
-    debug!("[C] Waiting for server to come online");
-    thread::sleep(Duration::from_secs(3));
-    debug!("[C] Starting connection to host {}", host.to_string());
+    // Generate a random username, and a random password standing in for one typed by a human
+    let username = utils::generate_random_string(32, &mut rng);
+    let user_password = utils::generate_random_string(16, &mut rng);
+    debug!("[C] Starting Registration for {} in group {} with {:?}", username, group_name, protocol);
 
-    // Connect to the gRPC server
-    let mut client = AuthClient::connect(host).await?;
+    // Derive the secret from the password with a fresh salt; the key pair is dropped after registering
+    let salt = password::generate_salt(&mut rng);
+    let kdf = KdfParams::default();
+    let x = password::derive_secret(&params, &username, user_password.as_bytes(), &salt, &kdf)?;
+    let registered = KeyPair::from_secret(&params, x);
 
-    // Continuous loop for registration and authentication
-    loop {
-        thread::sleep(Duration::from_secs(2));
+    // Policy users also hold a random recovery key, which must be proven together with the password one
+    let recovery = match protocol {
+        Protocol::Policy => Some(KeyPair::from_secret(&params, Secret::new(params.random_nonzero_scalar(&mut rng)))),
+        _ => None,
+    };
 
-        // Generate a random username
-        let username = utils::generate_random_string(32);
-        debug!("[C] Starting Registration for {}", username);
+    // Encode the public keys with the canonical length for the group; single-key users only send y1,
+    // and policy users send their keys as factors instead
+    let (y1, y2, policy) = match &recovery {
+        None if protocol == Protocol::Schnorr => (params.encode_element(&registered.y1), Vec::new(), Vec::new()),
+        None => (params.encode_element(&registered.y1), params.encode_element(&registered.y2), Vec::new()),
+        Some(recovery) => {
+            let policy = vec![
+                PolicyFactor {
+                    protocol: Protocol::ChaumPedersen.into(),
+                    y1: params.encode_element(&registered.y1),
+                    y2: params.encode_element(&registered.y2),
+                },
+                PolicyFactor { protocol: Protocol::Schnorr.into(), y1: params.encode_element(&recovery.y1), y2: Vec::new() },
+            ];
+            (Vec::new(), Vec::new(), policy)
+        }
+    };
+    drop(registered);
+
+    // Send registration request to the server, which stores the salt and parameters
+    let _ = client.register(tonic::Request::new(
+        RegisterRequest {
+            user: username.to_string(),
+            y1,
+            y2,
+            group: group_name.to_string(),
+            kdf: Some(KdfParameters {
+                salt,
+                memory_kib: kdf.memory_kib,
+                iterations: kdf.iterations,
+                parallelism: kdf.parallelism,
+            }),
+            protocol: protocol.into(),
+            policy,
+        },
+    )).await?;
 
-        // Initialize the Chaum-Pedersen prover
-        let mut prover = ChaumPedersenProver::new();
+    debug!("[C] Requesting Login Parameters for {}", username);
 
-        // Convert BigInt values to i64 for serialization
-        let y1: i64;
-        let y2: i64;
-        match prover.kp.y1.to_i64() {
-            Some(x) => y1 = x,
-            None => panic!("Error casting"),
+    // Fetch the salt and parameters back, as a client logging in from scratch would
+    let login = client.get_login_parameters(tonic::Request::new(
+        LoginParametersRequest { user: username.to_string() },
+    )).await?.into_inner();
+    if login.group != group_name || login.protocol() != protocol {
+        return Err(format!("User {} is registered in group {} with {:?}", username, login.group, login.protocol()).into());
+    }
+    let stored = login.kdf.ok_or_else(|| format!("User {} has no password parameters", username))?;
+    let kdf = KdfParams { memory_kib: stored.memory_kib, iterations: stored.iterations, parallelism: stored.parallelism };
+
+    // The server chose these, so refuse any that would exhaust memory or time before deriving
+    kdf.check()?;
+
+    // Re-derive the secret and initialize the prover. Hedged nonces stay safe with a weak RNG as long
+    // as each proof is bound to a unique context, which the single-use login nonces provide.
+    let x = password::derive_secret(&params, &username, user_password.as_bytes(), &stored.salt, &kdf)?;
+    match protocol {
+        Protocol::ChaumPedersen => {
+            let prover = ChaumPedersenProver::with_secret(params.clone(), x).with_nonce_mode(NonceMode::Hedged);
+            login_chaum_pedersen(client, &params, &username, &prover).await?;
+            user_group_login(client, &params, group_name, &username, prover.kp.secret().clone()).await
+        }
+        Protocol::Schnorr => {
+            let prover = SchnorrProver::with_secret(params.clone(), x).with_nonce_mode(NonceMode::Hedged);
+            login_schnorr(client, &params, &username, &prover).await
         }
-        match prover.kp.y2.to_i64() {
-            Some(x) => y2 = x,
-            None => panic!("Error casting"),
+        Protocol::Policy => {
+            let recovery = recovery.ok_or("Policy users hold a recovery key")?;
+            let keys = vec![(FactorKind::ChaumPedersen, KeyPair::from_secret(&params, x)), (FactorKind::Schnorr, recovery)];
+            let prover = AndProver::with_keypairs(params.clone(), keys)?.with_nonce_mode(NonceMode::Hedged);
+            login_policy(client, &params, &username, &prover).await
         }
+    }
+}
+
+// Logs in as a two-key user interactively and then non-interactively
+async fn login_chaum_pedersen<G: Group>(
+    client: &mut AuthClient<Channel>,
+    params: &G,
+    username: &str,
+    prover: &ChaumPedersenProver<G>,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let mut rng = OsRng;
 
-        // Send registration request to the server
+    // Fetch a single-use nonce for the commitment, so its nonce cannot repeat even if the RNG does
+    let login_nonce = client.create_login_nonce(tonic::Request::new(
+        LoginNonceRequest { user: username.to_string() },
+    )).await?.into_inner().login_nonce;
+
+    // Prove the challenge for synchronization A
+    let commitment = prover.prove_sync_a_with_context(&utils::interactive_login_context(username, &login_nonce), &mut rng);
+
+    // Encode the commitments for serialization
+    let r1 = params.encode_element(commitment.r1());
+    let r2 = params.encode_element(commitment.r2());
+
+    debug!("[C] Requesting Challenge for {}", username);
+
+    // Request challenge from the server
+    let challenge_response = client.create_authentication_challenge(tonic::Request::new(
+        AuthenticationChallengeRequest {
+            user: username.to_string(),
+            r1,
+            r2,
+            commitments: Vec::new(),
+            login_nonce,
+        },
+    )).await?;
+
+    let c = params.decode_scalar(&challenge_response.get_ref().c)?;
+
+    // Prove the challenge for synchronization B
+    let bs = commitment.prove_sync_b(&c);
+
+    // Encode the response for serialization
+    let s = params.encode_scalar(&bs);
+
+    debug!("[C] Requesting Authentication for {}", username);
+
+    // Verify authentication response from the server
+    let authentication_response = client.verify_authentication(tonic::Request::new(
+        AuthenticationAnswerRequest {
+            auth_id: challenge_response.get_ref().auth_id.to_string(),
+            s,
+            responses: Vec::new(),
+        },
+    )).await?;
+
+    // Print authentication response
+    println!("Authentication Response {}", authentication_response.get_ref().session_id);
+
+    // Fetch a single-use nonce, so the proof cannot be replayed by anyone who captures it
+    let login_nonce = client.create_login_nonce(tonic::Request::new(
+        LoginNonceRequest { user: username.to_string() },
+    )).await?.into_inner().login_nonce;
+
+    // Produce a non-interactive proof bound to this user and login nonce for the single round-trip login
+    let proof = prover.prove(&utils::async_login_context(username, &login_nonce), &mut rng);
+
+    debug!("[C] Requesting Async Authentication for {}", username);
+
+    let async_authentication_response = client.verify_authentication_async(tonic::Request::new(
+        AsyncAuthenticationRequest {
+            user: username.to_string(),
+            r1: params.encode_element(&proof.r1),
+            r2: params.encode_element(&proof.r2),
+            c: params.encode_scalar(&proof.c),
+            s: params.encode_scalar(&proof.s),
+            login_nonce,
+            commitments: Vec::new(),
+            responses: Vec::new(),
+        },
+    )).await?;
+
+    // Print async authentication response
+    println!("Async Authentication Response {}", async_authentication_response.get_ref().session_id);
+    Ok(())
+}
+
+// Puts the user in a fresh user group with two other freshly registered users, then logs in
+// on behalf of the user group with an OR proof that does not reveal which member is proving
+async fn user_group_login<G: Group>(
+    client: &mut AuthClient<Channel>,
+    params: &G,
+    group_name: &str,
+    username: &str,
+    x: Secret<G::Scalar>,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let mut rng = OsRng;
+
+    // Register the other members with random keys, and list everyone in a random order
+    let mut users = vec![username.to_string()];
+    for _ in 0..2 {
+        let other = ChaumPedersenProver::with_group(params.clone(), &mut rng);
+        let user = utils::generate_random_string(32, &mut rng);
         let _ = client.register(tonic::Request::new(
             RegisterRequest {
-                user: username.to_string(),
-                y1: y1,
-                y2: y2,
+                user: user.to_string(),
+                y1: params.encode_element(&other.kp.y1),
+                y2: params.encode_element(&other.kp.y2),
+                group: group_name.to_string(),
+                kdf: None,
+                protocol: Protocol::ChaumPedersen.into(),
+                policy: Vec::new(),
             },
         )).await?;
+        users.push(user);
+    }
+    users.shuffle(&mut rng);
+    let name = format!("team-{}", utils::generate_random_string(16, &mut rng));
 
-        // Prove the challenge for synchronization A
-        let (br1, br2) = prover.prove_sync_a();
+    debug!("[C] Requesting User Group Registration for {}", name);
+    let _ = client.register_user_group(tonic::Request::new(
+        RegisterUserGroupRequest { name: name.to_string(), users },
+    )).await?;
 
-        // Convert BigInt values to i64 for serialization
-        let r1: i64;
-        let r2: i64;
-        match br1.to_i64() {
-            Some(x) => r1 = x,
-            None => panic!("Error casting"),
-        }
-        match br2.to_i64() {
-            Some(x) => r2 = x,
-            None => panic!("Error casting"),
-        }
+    // Fetch the members' keys back, as a member logging in from scratch would
+    let user_group = client.get_user_group(tonic::Request::new(
+        UserGroupRequest { name: name.to_string() },
+    )).await?.into_inner();
+    let statement = user_group
+        .members
+        .iter()
+        .map(|m| Ok((params.decode_element(&m.y1)?, params.decode_element(&m.y2)?)))
+        .collect::<Result<Vec<_>, EncodingError>>()?;
+    let prover = OrProver::with_secret(params.clone(), x, statement)
+        .ok_or_else(|| format!("User {} is not a member of {}", username, name))?;
 
-        debug!("[C] Requesting Challenge for {}", username);
+    // Produce an OR proof bound to the user group and login nonce for the single round-trip login
+    let proof = prover.prove(&utils::user_group_login_context(&name, &user_group.login_nonce), &mut rng);
 
-        // Request challenge from the server
-        let challenge_response = client.create_authentication_challenge(tonic::Request::new(
-            AuthenticationChallengeRequest {
-                user: username.to_string(),
-                r1: r1,
-                r2: r2,
-            },
-        )).await?;
+    debug!("[C] Requesting User Group Authentication for {}", name);
 
-        let c = challenge_response.get_ref().c;
+    let user_group_authentication_response = client.verify_user_group_authentication(tonic::Request::new(
+        UserGroupAuthenticationRequest {
+            name: name.to_string(),
+            branches: proof
+                .branches
+                .iter()
+                .map(|b| ProofBranch {
+                    r1: params.encode_element(&b.r1),
+                    r2: params.encode_element(&b.r2),
+                    c: params.encode_scalar(&b.c),
+                    s: params.encode_scalar(&b.s),
+                })
+                .collect(),
+            login_nonce: user_group.login_nonce.to_string(),
+        },
+    )).await?;
 
-        // Prove the challenge for synchronization B
-        let bs: BigInt = prover.prove_sync_b(&BigInt::from(c));
+    // Print user group authentication response
+    println!("User Group Authentication Response {}", user_group_authentication_response.get_ref().session_id);
+    Ok(())
+}
 
-        // Convert BigInt value to i64 for serialization
-        let s: i64;
-        match bs.to_i64() {
-            Some(x) => s = x,
-            None => panic!("Error casting"),
-        }
+// Logs in as a single-key user interactively and then non-interactively, sending the
+// commitment as r1 and leaving r2 empty
+async fn login_schnorr<G: Group>(
+    client: &mut AuthClient<Channel>,
+    params: &G,
+    username: &str,
+    prover: &SchnorrProver<G>,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let mut rng = OsRng;
 
-        debug!("[C] Requesting Authentication for {}", username);
+    // Fetch a single-use nonce for the commitment, so its nonce cannot repeat even if the RNG does
+    let login_nonce = client.create_login_nonce(tonic::Request::new(
+        LoginNonceRequest { user: username.to_string() },
+    )).await?.into_inner().login_nonce;
 
-        // Verify authentication response from the server
-        let authentication_response = client.verify_authentication(tonic::Request::new(
-            AuthenticationAnswerRequest {
-                auth_id: challenge_response.get_ref().auth_id.to_string(),
-                s: s,
-            },
-        )).await?;
+    // Prove the challenge for synchronization A
+    let commitment = prover.prove_sync_a_with_context(&utils::interactive_login_context(username, &login_nonce), &mut rng);
+
+    debug!("[C] Requesting Schnorr Challenge for {}", username);
+
+    // Request challenge from the server
+    let challenge_response = client.create_authentication_challenge(tonic::Request::new(
+        AuthenticationChallengeRequest {
+            user: username.to_string(),
+            r1: params.encode_element(commitment.r()),
+            r2: Vec::new(),
+            commitments: Vec::new(),
+            login_nonce,
+        },
+    )).await?;
+
+    let c = params.decode_scalar(&challenge_response.get_ref().c)?;
+
+    // Prove the challenge for synchronization B
+    let s = params.encode_scalar(&commitment.prove_sync_b(&c));
+
+    debug!("[C] Requesting Schnorr Authentication for {}", username);
+
+    // Verify authentication response from the server
+    let authentication_response = client.verify_authentication(tonic::Request::new(
+        AuthenticationAnswerRequest {
+            auth_id: challenge_response.get_ref().auth_id.to_string(),
+            s,
+            responses: Vec::new(),
+        },
+    )).await?;
+
+    // Print authentication response
+    println!("Authentication Response {}", authentication_response.get_ref().session_id);
+
+    // Fetch a single-use nonce, so the proof cannot be replayed by anyone who captures it
+    let login_nonce = client.create_login_nonce(tonic::Request::new(
+        LoginNonceRequest { user: username.to_string() },
+    )).await?.into_inner().login_nonce;
+
+    // Produce a non-interactive proof bound to this user and login nonce for the single round-trip login
+    let proof = prover.prove(&utils::async_login_context(username, &login_nonce), &mut rng);
+
+    debug!("[C] Requesting Async Schnorr Authentication for {}", username);
+
+    let async_authentication_response = client.verify_authentication_async(tonic::Request::new(
+        AsyncAuthenticationRequest {
+            user: username.to_string(),
+            r1: params.encode_element(&proof.r),
+            r2: Vec::new(),
+            c: params.encode_scalar(&proof.c),
+            s: params.encode_scalar(&proof.s),
+            login_nonce,
+            commitments: Vec::new(),
+            responses: Vec::new(),
+        },
+    )).await?;
+
+    // Print async authentication response
+    println!("Async Authentication Response {}", async_authentication_response.get_ref().session_id);
+    Ok(())
+}
+
+// Logs in as a policy user interactively and then non-interactively, sending every factor's
+// commitments and responses in order and leaving r1, r2 and s empty
+async fn login_policy<G: Group>(
+    client: &mut AuthClient<Channel>,
+    params: &G,
+    username: &str,
+    prover: &AndProver<G>,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let mut rng = OsRng;
+
+    // Fetch a single-use nonce for the commitment, so its nonce cannot repeat even if the RNG does
+    let login_nonce = client.create_login_nonce(tonic::Request::new(
+        LoginNonceRequest { user: username.to_string() },
+    )).await?.into_inner().login_nonce;
+
+    // Prove the challenge for synchronization A
+    let commitment = prover.prove_sync_a_with_context(&utils::interactive_login_context(username, &login_nonce), &mut rng);
+
+    debug!("[C] Requesting Policy Challenge for {}", username);
+
+    // Request challenge from the server
+    let challenge_response = client.create_authentication_challenge(tonic::Request::new(
+        AuthenticationChallengeRequest {
+            user: username.to_string(),
+            r1: Vec::new(),
+            r2: Vec::new(),
+            commitments: commitment.r().iter().map(|r| params.encode_element(r)).collect(),
+            login_nonce,
+        },
+    )).await?;
+
+    let c = params.decode_scalar(&challenge_response.get_ref().c)?;
+
+    // Prove the challenge for synchronization B, answering for every factor at once
+    let responses = commitment.prove_sync_b(&c).iter().map(|s| params.encode_scalar(s)).collect();
+
+    debug!("[C] Requesting Policy Authentication for {}", username);
+
+    // Verify authentication response from the server
+    let authentication_response = client.verify_authentication(tonic::Request::new(
+        AuthenticationAnswerRequest {
+            auth_id: challenge_response.get_ref().auth_id.to_string(),
+            s: Vec::new(),
+            responses,
+        },
+    )).await?;
+
+    // Print authentication response
+    println!("Authentication Response {}", authentication_response.get_ref().session_id);
+
+    // Fetch a single-use nonce, so the proof cannot be replayed by anyone who captures it
+    let login_nonce = client.create_login_nonce(tonic::Request::new(
+        LoginNonceRequest { user: username.to_string() },
+    )).await?.into_inner().login_nonce;
+
+    // Produce a non-interactive proof bound to this user and login nonce for the single round-trip login
+    let proof = prover.prove(&utils::async_login_context(username, &login_nonce), &mut rng);
 
-        // Print authentication response
-        println!("Authentication Response {}", authentication_response.get_ref().session_id.to_string());
+    debug!("[C] Requesting Async Policy Authentication for {}", username);
+
+    let async_authentication_response = client.verify_authentication_async(tonic::Request::new(
+        AsyncAuthenticationRequest {
+            user: username.to_string(),
+            r1: Vec::new(),
+            r2: Vec::new(),
+            c: params.encode_scalar(&proof.c),
+            s: Vec::new(),
+            login_nonce,
+            commitments: proof.r.iter().map(|r| params.encode_element(r)).collect(),
+            responses: proof.s.iter().map(|s| params.encode_scalar(s)).collect(),
+        },
+    )).await?;
+
+    // Print async authentication response
+    println!("Async Authentication Response {}", async_authentication_response.get_ref().session_id);
+    Ok(())
+}
+
+// Main async function
+#[tokio::main]
+async fn main() -> Result<(), Box<dyn std::error::Error>> {
+    // Initialize the logging system
+    env_logger::init();
+    log::set_max_level(LevelFilter::Debug);
+
+    // Parse command line arguments
+    let args: Vec<String> = env::args().collect();
+    let host: String = match args.len() {
+        2 => format!("https://{}:50051", &args[1]),
+        3..=5 => format!("https://{}:{}", &args[1], &args[2]),
+        _ => "https://localhost:50051".to_string(),
+    };
+    // Group to register users in, selectable as the last argument
+    let group_name: String = match args.len() {
+        4 | 5 => args[3].to_string(),
+        _ => DEFAULT_GROUP.to_string(),
+    };
+    // Protocol to register users for, "chaum-pedersen" (the default), "schnorr" or "policy", after the group
+    let protocol = match args.len() {
+        5 => Protocol::from_str_name(&args[4].to_uppercase().replace('-', "_"))
+            .ok_or_else(|| format!("Unknown protocol {}", args[4]))?,
+        _ => Protocol::ChaumPedersen,
+    };
+    debug!("[C] Waiting for server to come online");
+    thread::sleep(Duration::from_secs(3));
+    debug!("[C] Starting connection to host {}", host);
+
+    // Connect to the gRPC server
+    let mut client = AuthClient::connect(host).await?;
+
+    // Finite-field parameters are validated once, before they are used
+    let params = match group_name.as_str() {
+        RISTRETTO_GROUP => None,
+        name => {
+            let params = get_named(name).ok_or_else(|| format!("Unknown group {}", name))?;
+            params.validate(&group_validation(name), &mut OsRng)?;
+            Some(params)
+        }
+    };
+
+    // Continuous loop for registration and authentication
+    loop {
+        thread::sleep(Duration::from_secs(2));
+
+        match &params {
+            None => register_and_login(&mut client, RistrettoGroup::new(), &group_name, protocol).await?,
+            Some(params) => register_and_login(&mut client, params.clone(), &group_name, protocol).await?,
+        }
     }
 }
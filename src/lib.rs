@@ -0,0 +1,3 @@
+// Cryptographic library shared by the server, client and demo binaries, and importable by external
+// benchmarks and security tests
+pub mod zkrypto;
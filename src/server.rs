@@ -1,14 +1,21 @@
 // Import required libraries and modules
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet, VecDeque};
 use std::env;
-
-use num_bigint::BigInt;
-use num_traits::ToPrimitive;
+use std::time::{Duration, Instant};
 
 use log::{debug, LevelFilter};
+use rand::rngs::OsRng;
 
 // Import modules from the current crate
-use crate::zkrypto::chaum_pedersen::ChaumPedersenVerifier;
+use crate::zkrypto::chaum_pedersen::{ChaumPedersenChallenge, ChaumPedersenProof, ChaumPedersenVerifier, StatementError};
+use crate::zkrypto::composition::{AndChallenge, AndProof, AndVerifier, Factor, OrProof, OrVerifier};
+use crate::zkrypto::encoding::EncodingError;
+use crate::zkrypto::generator::{Generator, ValidationError, get_default, get_named};
+use crate::zkrypto::group::{ElementError, Group};
+use crate::zkrypto::password::{KdfError, KdfParams, SALT_LEN};
+use crate::zkrypto::ristretto::RistrettoGroup;
+use crate::zkrypto::schnorr::{SchnorrChallenge, SchnorrProof, SchnorrVerifier};
+use crate::utils::{group_validation, RISTRETTO_GROUP};
 
 // Define the module structure for the generated proto files
 pub mod zkp_auth {
@@ -16,7 +23,7 @@ pub mod zkp_auth {
 }
 
 // Import other modules from the crate
-pub mod zkrypto;
+use zkp::zkrypto;
 pub mod utils;
 
 // Import required modules from the Tonic crate
@@ -28,18 +35,181 @@ use zkp_auth::auth_server::{Auth, AuthServer};
 use zkp_auth::{
     RegisterRequest,
     RegisterResponse,
+    KdfParameters,
+    LoginParametersRequest,
+    LoginParametersResponse,
     AuthenticationChallengeRequest,
     AuthenticationChallengeResponse,
     AuthenticationAnswerRequest,
     AuthenticationAnswerResponse,
+    LoginNonceRequest,
+    LoginNonceResponse,
+    AsyncAuthenticationRequest,
+    Protocol,
+    UserPublicKey,
+    RegisterUserGroupRequest,
+    RegisterUserGroupResponse,
+    UserGroupRequest,
+    UserGroupResponse,
+    UserGroupAuthenticationRequest,
 };
 
+// Verifier of a registered user, in the group and protocol selected at registration
+#[derive(Debug)]
+pub enum UserVerifier {
+    Modp(ChaumPedersenVerifier<Generator>),
+    Ristretto(Box<ChaumPedersenVerifier<RistrettoGroup>>),
+    SchnorrModp(SchnorrVerifier<Generator>),
+    SchnorrRistretto(Box<SchnorrVerifier<RistrettoGroup>>),
+    PolicyModp(AndVerifier<Generator>),
+    PolicyRistretto(Box<AndVerifier<RistrettoGroup>>),
+}
+
+// Challenge state of an interactive attempt, in the group and protocol of the user it belongs to
+#[derive(Debug)]
+pub enum PendingChallenge {
+    Modp(ChaumPedersenChallenge<Generator>),
+    Ristretto(Box<ChaumPedersenChallenge<RistrettoGroup>>),
+    SchnorrModp(SchnorrChallenge<Generator>),
+    SchnorrRistretto(Box<SchnorrChallenge<RistrettoGroup>>),
+    PolicyModp(AndChallenge<Generator>),
+    PolicyRistretto(Box<AndChallenge<RistrettoGroup>>),
+}
+
+// Interactive authentication attempt awaiting the prover's answer
+#[derive(Debug)]
+pub struct PendingAuthentication {
+    user: String,
+    challenge: PendingChallenge,
+}
+
+// How long a login nonce or interactive challenge waits for its answer
+const PENDING_TTL: Duration = Duration::from_secs(60);
+
+// Most login nonces, and separately most interactive challenges, one user or user group can have
+// outstanding. A new one replaces the owner's oldest, so flooding one owner locks no one else out.
+const MAX_PENDING_PER_OWNER: usize = 8;
+
+// Most login nonces, and separately most interactive challenges, outstanding across all owners
+const MAX_PENDING: usize = 10_000;
+
+// Login nonce or interactive challenge, refused once it outlives PENDING_TTL
+#[derive(Debug)]
+struct Pending<T> {
+    owner: SessionOwner,
+    value: T,
+    issued: Instant,
+}
+
+// Login nonces or interactive challenges awaiting an answer, by key. An owner's expired entries are
+// dropped whenever they get a new one, and every owner's at most once per PENDING_TTL.
+#[derive(Debug)]
+pub struct PendingMap<T> {
+    entries: HashMap<String, Pending<T>>,
+    by_owner: HashMap<SessionOwner, VecDeque<String>>, // Keys of each owner's entries, oldest first
+    swept: Instant,                                    // When the expired entries of every owner were last dropped
+}
+
+impl<T> PendingMap<T> {
+    fn new() -> PendingMap<T> {
+        PendingMap { entries: HashMap::new(), by_owner: HashMap::new(), swept: Instant::now() }
+    }
+
+    // Adds an entry for the owner, replacing their oldest once they have MAX_PENDING_PER_OWNER, and
+    // refuses it while MAX_PENDING live entries are outstanding
+    fn insert(&mut self, owner: SessionOwner, key: String, value: T) -> Result<(), RequestError> {
+        if self.swept.elapsed() >= PENDING_TTL {
+            self.entries.retain(|_, p| p.issued.elapsed() < PENDING_TTL);
+            let entries = &self.entries;
+            self.by_owner.retain(|_, keys| {
+                keys.retain(|k| entries.contains_key(k));
+                !keys.is_empty()
+            });
+            self.swept = Instant::now();
+        }
+        if let Some(keys) = self.by_owner.get_mut(&owner) {
+            while let Some(oldest) = keys.front() {
+                if keys.len() < MAX_PENDING_PER_OWNER && self.entries[oldest].issued.elapsed() < PENDING_TTL {
+                    break;
+                }
+                self.entries.remove(oldest);
+                keys.pop_front();
+            }
+        }
+        if self.entries.len() >= MAX_PENDING {
+            return Err(RequestError::TooManyPending);
+        }
+        self.by_owner.entry(owner.clone()).or_default().push_back(key.to_string());
+        self.entries.insert(key, Pending { owner, value, issued: Instant::now() });
+        Ok(())
+    }
+
+    // Removes an entry, returning its owner and value unless it has expired
+    fn take(&mut self, key: &str) -> Option<(SessionOwner, T)> {
+        let pending = self.entries.remove(key)?;
+        if let Some(keys) = self.by_owner.get_mut(&pending.owner) {
+            keys.retain(|k| k != key);
+            if keys.is_empty() {
+                self.by_owner.remove(&pending.owner);
+            }
+        }
+        (pending.issued.elapsed() < PENDING_TTL).then_some((pending.owner, pending.value))
+    }
+}
+
+impl<T> Default for PendingMap<T> {
+    fn default() -> PendingMap<T> {
+        PendingMap::new()
+    }
+}
+
+// Salt and Argon2id parameters of a user whose secret is derived from a password
+#[derive(Debug, Clone)]
+pub struct PasswordRecord {
+    salt: Vec<u8>,
+    params: KdfParams,
+}
+
+// What a client is told before logging in as a registered user
+#[derive(Debug, Clone)]
+pub struct LoginRecord {
+    group: String,
+    protocol: Protocol,
+    password: Option<PasswordRecord>,
+}
+
+// Verifier of the OR proofs of a user group, in the group its members registered in
+#[derive(Debug)]
+pub enum UserGroupVerifier {
+    Modp(OrVerifier<Generator>),
+    Ristretto(Box<OrVerifier<RistrettoGroup>>),
+}
+
+// A named set of users, any of whom can log in on its behalf without revealing who
+#[derive(Debug)]
+pub struct UserGroup {
+    group: String,          // Group the members registered in
+    members: Vec<String>,   // Usernames, in the order of the proof branches
+    verifier: UserGroupVerifier,
+}
+
+// Who a session was issued to
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum SessionOwner {
+    User(String),      // A user who proved knowledge of their own key
+    UserGroup(String), // Some member of a user group, who stays anonymous within it
+}
+
 // Main state struct to hold verifier, auth ID, and session data
 #[derive(Debug, Default)]
 pub struct MyAuthState {
-    verifiers: Mutex<HashMap<String, ChaumPedersenVerifier>>,
-    auth_ids: Mutex<HashMap<String, String>>,
-    sessions: Mutex<HashMap<String, String>>,
+    verifiers: Mutex<HashMap<String, UserVerifier>>,
+    logins: Mutex<HashMap<String, LoginRecord>>, // Updated together with verifiers, under its lock
+    auth_ids: Mutex<PendingMap<PendingAuthentication>>,
+    sessions: Mutex<HashMap<String, SessionOwner>>,
+    login_nonces: Mutex<PendingMap<()>>, // Unused login nonces, by who they were issued to
+    user_groups: Mutex<HashMap<String, UserGroup>>, // Taken after the verifiers and logins locks
+    groups: Mutex<HashMap<String, Generator>>, // Finite-field groups that passed validation, by name
 }
 
 // Main implementation struct for the server
@@ -54,11 +224,393 @@ impl MyAuth {
         MyAuth {
             state: Arc::new(MyAuthState {
                 verifiers: std::sync::Mutex::new(HashMap::new()),
-                auth_ids: std::sync::Mutex::new(HashMap::new()),
+                logins: std::sync::Mutex::new(HashMap::new()),
+                auth_ids: std::sync::Mutex::new(PendingMap::new()),
                 sessions: std::sync::Mutex::new(HashMap::new()),
+                login_nonces: std::sync::Mutex::new(PendingMap::new()),
+                user_groups: std::sync::Mutex::new(HashMap::new()),
+                groups: std::sync::Mutex::new(HashMap::new()),
             }),
         }
     }
+
+    // Opens a new session for an authenticated user or user group and returns its ID
+    fn create_session(&self, owner: SessionOwner) -> String {
+        let session_id = utils::generate_random_string(32, &mut OsRng);
+        let mut sessions: std::sync::MutexGuard<'_, HashMap<String, SessionOwner>> = self.state.sessions.lock().unwrap();
+        sessions.insert(session_id.to_string(), owner);
+        session_id
+    }
+
+    // Issues a single-use login nonce to bind into the owner's next commitment or non-interactive proof
+    fn issue_login_nonce(&self, owner: SessionOwner) -> Result<String, RequestError> {
+        let login_nonce = utils::generate_random_string(32, &mut OsRng);
+        self.state.login_nonces.lock().unwrap().insert(owner, login_nonce.to_string(), ())?;
+        Ok(login_nonce)
+    }
+
+    // Consumes a login nonce, which only succeeds once, before it expires and only for the owner it
+    // was issued to
+    fn take_login_nonce(&self, login_nonce: &str, owner: &SessionOwner) -> bool {
+        let issued = self.state.login_nonces.lock().unwrap().take(login_nonce);
+        issued.map(|(issued_to, ())| issued_to).as_ref() == Some(owner)
+    }
+
+    // Loads a finite-field group by name, validating it the first time it is requested. The
+    // primality tests on 2048-4096-bit parameters run on the blocking pool, not a runtime thread.
+    async fn load_group(&self, name: &str) -> Result<Generator, RequestError> {
+        if let Some(g) = self.state.groups.lock().unwrap().get(name) {
+            return Ok(g.clone());
+        }
+        let g = match name {
+            "" => get_default(),
+            name => get_named(name).ok_or_else(|| RequestError::UnknownGroup(name.to_string()))?,
+        };
+        let params = group_validation(name);
+        let g = tokio::task::spawn_blocking(move || g.validate(&params, &mut OsRng).map(|_| g))
+            .await
+            .expect("group validation does not panic")
+            .map_err(|e| RequestError::InvalidGroup(name.to_string(), e))?;
+        self.state.groups.lock().unwrap().insert(name.to_string(), g.clone());
+        Ok(g)
+    }
+
+    // Builds the verifier for the group and protocol named in a registration request
+    async fn user_verifier(&self, request: &RegisterRequest) -> Result<UserVerifier, RequestError> {
+        match (request.group.as_str(), protocol(request)?) {
+            (RISTRETTO_GROUP, Protocol::ChaumPedersen) => {
+                Ok(UserVerifier::Ristretto(Box::new(new_verifier(RistrettoGroup::new(), request)?)))
+            }
+            (RISTRETTO_GROUP, Protocol::Schnorr) => {
+                Ok(UserVerifier::SchnorrRistretto(Box::new(new_schnorr_verifier(RistrettoGroup::new(), request)?)))
+            }
+            (name, Protocol::ChaumPedersen) => Ok(UserVerifier::Modp(new_verifier(self.load_group(name).await?, request)?)),
+            (RISTRETTO_GROUP, Protocol::Policy) => {
+                Ok(UserVerifier::PolicyRistretto(Box::new(new_policy_verifier(RistrettoGroup::new(), request)?)))
+            }
+            (name, Protocol::Schnorr) => Ok(UserVerifier::SchnorrModp(new_schnorr_verifier(self.load_group(name).await?, request)?)),
+            (name, Protocol::Policy) => Ok(UserVerifier::PolicyModp(new_policy_verifier(self.load_group(name).await?, request)?)),
+        }
+    }
+}
+
+// Errors raised while handling a request, mapped to gRPC statuses below
+#[derive(Debug)]
+pub enum RequestError {
+    Field(&'static str, EncodingError), // A field could not be decoded
+    UnknownGroup(String),               // The requested group is not supported
+    InvalidGroup(String, ValidationError), // The requested group failed parameter validation
+    Element(ElementError),              // A decoded element is degenerate or outside the subgroup
+    Kdf(KdfError),                      // The password salt or parameters are unusable
+    UnknownProtocol(i32),               // The requested protocol is not supported
+    UnexpectedField(&'static str),      // A field the user's protocol leaves empty is set
+    InvalidFactor(i32),                 // A policy factor names a protocol that cannot be a factor
+    GroupMismatch,                      // Pending challenge and verifier are in different groups or protocols
+    UnknownUser(String),                // A user group lists a user who is not registered
+    DuplicateMember(String),            // A user group lists a user twice
+    MixedUserGroup,                     // User group members differ in group or are not Chaum-Pedersen users
+    Statement(StatementError),          // An OR or AND statement or proof has the wrong shape or invalid elements
+    TooManyPending,                     // Too many login nonces or challenges are awaiting an answer
+}
+
+impl From<RequestError> for Status {
+    fn from(e: RequestError) -> Status {
+        match e {
+            RequestError::Field(field, e) => Status::invalid_argument(format!("Invalid {}: {}", field, e)),
+            RequestError::UnknownGroup(name) => Status::invalid_argument(format!("Unknown group {}", name)),
+            RequestError::InvalidGroup(name, e) => Status::failed_precondition(format!("Group {}: {}", name, e)),
+            RequestError::Element(e) => Status::invalid_argument(format!("Invalid {}", e)),
+            RequestError::Kdf(e) => Status::invalid_argument(format!("Invalid kdf: {}", e)),
+            RequestError::UnknownProtocol(p) => Status::invalid_argument(format!("Unknown protocol {}", p)),
+            RequestError::UnexpectedField(field) => {
+                Status::invalid_argument(format!("Unexpected {} for the user's protocol", field))
+            }
+            RequestError::InvalidFactor(p) => Status::invalid_argument(format!("Invalid policy factor protocol {}", p)),
+            RequestError::GroupMismatch => Status::internal("Challenge does not match the user's group"),
+            RequestError::UnknownUser(user) => Status::not_found(format!("User Not Found {}", user)),
+            RequestError::DuplicateMember(user) => Status::invalid_argument(format!("Duplicate member {}", user)),
+            RequestError::MixedUserGroup => {
+                Status::invalid_argument("User group members must be Chaum-Pedersen users of one group")
+            }
+            RequestError::Statement(e) => Status::invalid_argument(format!("Invalid statement: {}", e)),
+            RequestError::TooManyPending => Status::resource_exhausted("Too many logins in progress, try again later"),
+        }
+    }
+}
+
+impl From<ElementError> for RequestError {
+    fn from(e: ElementError) -> RequestError {
+        RequestError::Element(e)
+    }
+}
+
+impl From<StatementError> for RequestError {
+    fn from(e: StatementError) -> RequestError {
+        RequestError::Statement(e)
+    }
+}
+
+// Checks the password parameters sent at registration, which must meet the floor, before they are stored
+fn password_record(kdf: &KdfParameters) -> Result<PasswordRecord, RequestError> {
+    if kdf.salt.len() < SALT_LEN {
+        return Err(RequestError::Kdf(KdfError::SaltTooShort { len: kdf.salt.len(), min: SALT_LEN }));
+    }
+    let params = KdfParams { memory_kib: kdf.memory_kib, iterations: kdf.iterations, parallelism: kdf.parallelism };
+    params.check_registration().map_err(RequestError::Kdf)?;
+    Ok(PasswordRecord { salt: kdf.salt.clone(), params })
+}
+
+// Reads the protocol of a registration request, rejecting values this server does not know
+fn protocol(request: &RegisterRequest) -> Result<Protocol, RequestError> {
+    Protocol::from_i32(request.protocol).ok_or(RequestError::UnknownProtocol(request.protocol))
+}
+
+// Rejects a field that the user's protocol leaves empty
+fn expect_empty<T>(field: &'static str, items: &[T]) -> Result<(), RequestError> {
+    match items.is_empty() {
+        true => Ok(()),
+        false => Err(RequestError::UnexpectedField(field)),
+    }
+}
+
+// Builds a verifier in the given group from the public keys sent at registration
+fn new_verifier<G: Group>(g: G, request: &RegisterRequest) -> Result<ChaumPedersenVerifier<G>, RequestError> {
+    expect_empty("policy", &request.policy)?;
+    let y1 = g.decode_element(&request.y1).map_err(|e| RequestError::Field("y1", e))?;
+    let y2 = g.decode_element(&request.y2).map_err(|e| RequestError::Field("y2", e))?;
+    Ok(ChaumPedersenVerifier::with_group(g, y1, y2)?)
+}
+
+// Builds a Schnorr verifier in the given group from the single public key y1 sent at registration
+fn new_schnorr_verifier<G: Group>(g: G, request: &RegisterRequest) -> Result<SchnorrVerifier<G>, RequestError> {
+    expect_empty("y2", &request.y2)?;
+    expect_empty("policy", &request.policy)?;
+    let y = g.decode_element(&request.y1).map_err(|e| RequestError::Field("y1", e))?;
+    Ok(SchnorrVerifier::with_group(g, y)?)
+}
+
+// Builds an AND verifier in the given group from the policy factors sent at registration, whose
+// keys replace y1 and y2
+fn new_policy_verifier<G: Group>(g: G, request: &RegisterRequest) -> Result<AndVerifier<G>, RequestError> {
+    expect_empty("y1", &request.y1)?;
+    expect_empty("y2", &request.y2)?;
+    let factors = request
+        .policy
+        .iter()
+        .map(|f| match Protocol::from_i32(f.protocol) {
+            Some(Protocol::ChaumPedersen) => Ok(Factor::ChaumPedersen {
+                y1: g.decode_element(&f.y1).map_err(|e| RequestError::Field("y1", e))?,
+                y2: g.decode_element(&f.y2).map_err(|e| RequestError::Field("y2", e))?,
+            }),
+            Some(Protocol::Schnorr) => {
+                expect_empty("y2", &f.y2)?;
+                Ok(Factor::Schnorr { y: g.decode_element(&f.y1).map_err(|e| RequestError::Field("y1", e))? })
+            }
+            _ => Err(RequestError::InvalidFactor(f.protocol)),
+        })
+        .collect::<Result<Vec<_>, RequestError>>()?;
+    Ok(AndVerifier::with_group(g, factors)?)
+}
+
+// Decodes a list of group elements, such as the commitments of a policy user
+fn decode_elements<G: Group>(g: &G, field: &'static str, items: &[Vec<u8>]) -> Result<Vec<G::Element>, RequestError> {
+    items.iter().map(|b| g.decode_element(b).map_err(|e| RequestError::Field(field, e))).collect()
+}
+
+// Decodes a list of scalars, such as the responses of a policy user
+fn decode_scalars<G: Group>(g: &G, field: &'static str, items: &[Vec<u8>]) -> Result<Vec<G::Scalar>, RequestError> {
+    items.iter().map(|b| g.decode_scalar(b).map_err(|e| RequestError::Field(field, e))).collect()
+}
+
+// Decodes the commitments of an attempt and draws its challenge, returning it with its encoding
+fn create_challenge<G: Group>(
+    verifier: &ChaumPedersenVerifier<G>,
+    request: &AuthenticationChallengeRequest,
+) -> Result<(ChaumPedersenChallenge<G>, Vec<u8>), RequestError> {
+    expect_empty("commitments", &request.commitments)?;
+    let g = verifier.group();
+    let r1 = g.decode_element(&request.r1).map_err(|e| RequestError::Field("r1", e))?;
+    let r2 = g.decode_element(&request.r2).map_err(|e| RequestError::Field("r2", e))?;
+    let challenge = verifier.verify_sync_a(r1, r2, &mut OsRng)?;
+    let c = g.encode_scalar(challenge.c());
+    Ok((challenge, c))
+}
+
+// Decodes the single commitment r1 of a Schnorr attempt and draws its challenge
+fn create_schnorr_challenge<G: Group>(
+    verifier: &SchnorrVerifier<G>,
+    request: &AuthenticationChallengeRequest,
+) -> Result<(SchnorrChallenge<G>, Vec<u8>), RequestError> {
+    expect_empty("r2", &request.r2)?;
+    expect_empty("commitments", &request.commitments)?;
+    let g = verifier.group();
+    let r = g.decode_element(&request.r1).map_err(|e| RequestError::Field("r1", e))?;
+    let challenge = verifier.verify_sync_a(r, &mut OsRng)?;
+    let c = g.encode_scalar(challenge.c());
+    Ok((challenge, c))
+}
+
+// Decodes the commitments of a policy attempt, sent in place of r1 and r2, and draws its challenge
+fn create_policy_challenge<G: Group>(
+    verifier: &AndVerifier<G>,
+    request: &AuthenticationChallengeRequest,
+) -> Result<(AndChallenge<G>, Vec<u8>), RequestError> {
+    expect_empty("r1", &request.r1)?;
+    expect_empty("r2", &request.r2)?;
+    let g = verifier.group();
+    let r = decode_elements(g, "commitments", &request.commitments)?;
+    let challenge = verifier.verify_sync_a(r, &mut OsRng)?;
+    let c = g.encode_scalar(challenge.c());
+    Ok((challenge, c))
+}
+
+// Decodes the answer to a challenge and checks it
+fn answer_challenge<G: Group>(
+    verifier: &ChaumPedersenVerifier<G>,
+    challenge: ChaumPedersenChallenge<G>,
+    request: &AuthenticationAnswerRequest,
+) -> Result<bool, RequestError> {
+    expect_empty("responses", &request.responses)?;
+    let s = verifier.group().decode_scalar(&request.s).map_err(|e| RequestError::Field("s", e))?;
+    Ok(verifier.verify_sync_b(challenge, s))
+}
+
+// Decodes the answer to a Schnorr challenge and checks it
+fn answer_schnorr_challenge<G: Group>(
+    verifier: &SchnorrVerifier<G>,
+    challenge: SchnorrChallenge<G>,
+    request: &AuthenticationAnswerRequest,
+) -> Result<bool, RequestError> {
+    expect_empty("responses", &request.responses)?;
+    let s = verifier.group().decode_scalar(&request.s).map_err(|e| RequestError::Field("s", e))?;
+    Ok(verifier.verify_sync_b(challenge, s))
+}
+
+// Decodes the responses to a policy challenge, one per factor, and checks them
+fn answer_policy_challenge<G: Group>(
+    verifier: &AndVerifier<G>,
+    challenge: AndChallenge<G>,
+    request: &AuthenticationAnswerRequest,
+) -> Result<bool, RequestError> {
+    expect_empty("s", &request.s)?;
+    let s = decode_scalars(verifier.group(), "responses", &request.responses)?;
+    Ok(verifier.verify_sync_b(challenge, &s))
+}
+
+// Decodes a non-interactive proof and checks it against the user's login context and nonce
+fn verify_proof<G: Group>(
+    verifier: &ChaumPedersenVerifier<G>,
+    request: &AsyncAuthenticationRequest,
+) -> Result<bool, RequestError> {
+    expect_empty("commitments", &request.commitments)?;
+    expect_empty("responses", &request.responses)?;
+    let g = verifier.group();
+    let proof = ChaumPedersenProof {
+        r1: g.decode_element(&request.r1).map_err(|e| RequestError::Field("r1", e))?,
+        r2: g.decode_element(&request.r2).map_err(|e| RequestError::Field("r2", e))?,
+        c: g.decode_scalar(&request.c).map_err(|e| RequestError::Field("c", e))?,
+        s: g.decode_scalar(&request.s).map_err(|e| RequestError::Field("s", e))?,
+    };
+    Ok(verifier.verify(&proof, &utils::async_login_context(&request.user, &request.login_nonce)))
+}
+
+// Decodes a non-interactive Schnorr proof, whose commitment is sent as r1, and checks it
+fn verify_schnorr_proof<G: Group>(
+    verifier: &SchnorrVerifier<G>,
+    request: &AsyncAuthenticationRequest,
+) -> Result<bool, RequestError> {
+    expect_empty("r2", &request.r2)?;
+    expect_empty("commitments", &request.commitments)?;
+    expect_empty("responses", &request.responses)?;
+    let g = verifier.group();
+    let proof = SchnorrProof {
+        r: g.decode_element(&request.r1).map_err(|e| RequestError::Field("r1", e))?,
+        c: g.decode_scalar(&request.c).map_err(|e| RequestError::Field("c", e))?,
+        s: g.decode_scalar(&request.s).map_err(|e| RequestError::Field("s", e))?,
+    };
+    Ok(verifier.verify(&proof, &utils::async_login_context(&request.user, &request.login_nonce)))
+}
+
+// Decodes a non-interactive policy proof, sent through commitments, c and responses, and checks it
+fn verify_policy_proof<G: Group>(verifier: &AndVerifier<G>, request: &AsyncAuthenticationRequest) -> Result<bool, RequestError> {
+    expect_empty("r1", &request.r1)?;
+    expect_empty("r2", &request.r2)?;
+    expect_empty("s", &request.s)?;
+    let g = verifier.group();
+    let proof = AndProof {
+        r: decode_elements(g, "commitments", &request.commitments)?,
+        c: g.decode_scalar(&request.c).map_err(|e| RequestError::Field("c", e))?,
+        s: decode_scalars(g, "responses", &request.responses)?,
+    };
+    Ok(verifier.verify(&proof, &utils::async_login_context(&request.user, &request.login_nonce)))
+}
+
+// Builds the OR verifier over the keys of the given users, who must be distinct Chaum-Pedersen
+// users of one group
+fn user_group_verifier(verifiers: &HashMap<String, UserVerifier>, users: &[String]) -> Result<UserGroupVerifier, RequestError> {
+    let mut seen = HashSet::new();
+    let mut members = Vec::with_capacity(users.len());
+    for user in users {
+        if !seen.insert(user) {
+            return Err(RequestError::DuplicateMember(user.to_string()));
+        }
+        members.push(verifiers.get(user).ok_or_else(|| RequestError::UnknownUser(user.to_string()))?);
+    }
+    match members.first() {
+        None => Err(StatementError::EmptyStatement.into()),
+        Some(UserVerifier::Modp(first)) => {
+            let statement = members
+                .iter()
+                .map(|m| match m {
+                    UserVerifier::Modp(v) if v.group() == first.group() => Ok((v.y1().clone(), v.y2().clone())),
+                    _ => Err(RequestError::MixedUserGroup),
+                })
+                .collect::<Result<Vec<_>, _>>()?;
+            Ok(UserGroupVerifier::Modp(OrVerifier::with_group(first.group().clone(), statement)?))
+        }
+        Some(UserVerifier::Ristretto(first)) => {
+            let statement = members
+                .iter()
+                .map(|m| match m {
+                    UserVerifier::Ristretto(v) => Ok((*v.y1(), *v.y2())),
+                    _ => Err(RequestError::MixedUserGroup),
+                })
+                .collect::<Result<Vec<_>, _>>()?;
+            Ok(UserGroupVerifier::Ristretto(Box::new(OrVerifier::with_group(first.group().clone(), statement)?)))
+        }
+        Some(_) => Err(RequestError::MixedUserGroup),
+    }
+}
+
+// Encodes the public keys of the members of a user group, in branch order
+fn member_keys<G: Group>(verifier: &OrVerifier<G>, members: &[String]) -> Vec<UserPublicKey> {
+    let g = verifier.group();
+    members
+        .iter()
+        .zip(verifier.statement())
+        .map(|(user, (y1, y2))| UserPublicKey { user: user.to_string(), y1: g.encode_element(y1), y2: g.encode_element(y2) })
+        .collect()
+}
+
+// Decodes an OR proof and checks it against the user group's login context and nonce
+fn verify_or_proof<G: Group>(verifier: &OrVerifier<G>, request: &UserGroupAuthenticationRequest) -> Result<bool, RequestError> {
+    let g = verifier.group();
+    let branches = request
+        .branches
+        .iter()
+        .map(|b| {
+            Ok(ChaumPedersenProof {
+                r1: g.decode_element(&b.r1).map_err(|e| RequestError::Field("r1", e))?,
+                r2: g.decode_element(&b.r2).map_err(|e| RequestError::Field("r2", e))?,
+                c: g.decode_scalar(&b.c).map_err(|e| RequestError::Field("c", e))?,
+                s: g.decode_scalar(&b.s).map_err(|e| RequestError::Field("s", e))?,
+            })
+        })
+        .collect::<Result<Vec<_>, RequestError>>()?;
+    if branches.len() != verifier.statement().len() {
+        return Err(StatementError::CountMismatch { expected: verifier.statement().len(), actual: branches.len() }.into());
+    }
+    Ok(verifier.verify(&OrProof { branches }, &utils::user_group_login_context(&request.name, &request.login_nonce)))
 }
 
 // Implementation of the Auth trait for the server
@@ -70,27 +622,60 @@ impl Auth for MyAuth {
         request: Request<RegisterRequest>,
     ) -> Result<Response<RegisterResponse>, Status> {
         let reply = RegisterResponse {};
-        let mut verifiers: std::sync::MutexGuard<'_, HashMap<String, ChaumPedersenVerifier>> =
+        // Group validation can be slow the first time, so it runs before taking the lock
+        let verifier = self.user_verifier(request.get_ref()).await?;
+        let login = LoginRecord {
+            group: request.get_ref().group.to_string(),
+            protocol: protocol(request.get_ref())?,
+            password: request.get_ref().kdf.as_ref().map(password_record).transpose()?,
+        };
+        let mut verifiers: std::sync::MutexGuard<'_, HashMap<String, UserVerifier>> =
             self.state.verifiers.lock().unwrap();
         let username = request.get_ref().user.to_string();
-        debug!("[S] Requesting Registration for {}", username.to_string());
-        if let Some(_) = verifiers.get(&username) {
+        debug!(
+            "[S] Requesting Registration for {} in group {:?} with {:?}",
+            username,
+            request.get_ref().group,
+            login.protocol
+        );
+        if verifiers.contains_key(&username) {
             // User exists
             return Err(Status::already_exists(format!(
                 "User Already Exists {}",
-                username.to_string()
+                username
             )));
         } else {
             // User doesn't exist
-            verifiers.insert(
-                username.clone(),
-                ChaumPedersenVerifier::new(
-                    BigInt::from(request.get_ref().y1),
-                    BigInt::from(request.get_ref().y2),
-                ),
-            );
+            verifiers.insert(username.clone(), verifier);
+            self.state.logins.lock().unwrap().insert(username, login);
+        }
+        Ok(Response::new(reply))
+    }
+
+    // Implementation of the get_login_parameters function
+    async fn get_login_parameters(
+        &self,
+        request: Request<LoginParametersRequest>,
+    ) -> Result<Response<LoginParametersResponse>, Status> {
+        let username = request.get_ref().user.to_string();
+        debug!("[S] Requesting Login Parameters for {}", username);
+        let logins: std::sync::MutexGuard<'_, HashMap<String, LoginRecord>> = self.state.logins.lock().unwrap();
+        if let Some(login) = logins.get(&username) {
+            // User exists
+            let kdf = login.password.as_ref().map(|p| KdfParameters {
+                salt: p.salt.clone(),
+                memory_kib: p.params.memory_kib,
+                iterations: p.params.iterations,
+                parallelism: p.params.parallelism,
+            });
+            Ok(Response::new(LoginParametersResponse {
+                group: login.group.clone(),
+                kdf,
+                protocol: login.protocol.into(),
+            }))
+        } else {
+            Err(Status::not_found(format!("User Not Found {}", username)))
         }
-        return Ok(Response::new(reply));
     }
 
     // Implementation of the create_authentication_challenge function
@@ -99,35 +684,55 @@ impl Auth for MyAuth {
         request: Request<AuthenticationChallengeRequest>,
     ) -> Result<Response<AuthenticationChallengeResponse>, Status> {
         let username = request.get_ref().user.to_string();
-        debug!("[S] Requesting Challenge for {}", username.to_string());
-        let mut verifiers: std::sync::MutexGuard<'_, HashMap<String, ChaumPedersenVerifier>> =
+        debug!("[S] Requesting Challenge for {}", username);
+        let verifiers: std::sync::MutexGuard<'_, HashMap<String, UserVerifier>> =
             self.state.verifiers.lock().unwrap();
-        if let Some(verifier) = verifiers.get_mut(&username) {
+        if let Some(verifier) = verifiers.get(&username) {
             // User exists
-
-            let auth_id = utils::generate_random_string(32);
-            let c: BigInt = verifier.verify_sync_a(
-                BigInt::from(request.get_ref().r1),
-                BigInt::from(request.get_ref().r2),
-            );
-            let mut auth_ids: std::sync::MutexGuard<'_, HashMap<String, String>> =
-                self.state.auth_ids.lock().unwrap();
-            auth_ids.insert(auth_id.to_string(), username.to_string());
-
-            match c.to_i64() {
-                Some(x) => return Ok(Response::new(AuthenticationChallengeResponse { auth_id, c: x })),
-                None => {
-                    return Err(Status::aborted(format!(
-                        "Wrong format for number {}",
-                        username.to_string()
-                    )))
-                }
+            // Each commitment is bound to a fresh login nonce, so its nonce cannot repeat even if the client's RNG does
+            let login_nonce = request.get_ref().login_nonce.to_string();
+            if !self.take_login_nonce(&login_nonce, &SessionOwner::User(username.clone())) {
+                return Err(Status::permission_denied(format!("Login nonce not found, expired or already used {}", login_nonce)));
             }
+            // The challenge state belongs to this attempt only, so concurrent logins don't interfere
+            let (challenge, c) = match verifier {
+                UserVerifier::Modp(v) => {
+                    let (challenge, c) = create_challenge(v, request.get_ref())?;
+                    (PendingChallenge::Modp(challenge), c)
+                }
+                UserVerifier::Ristretto(v) => {
+                    let (challenge, c) = create_challenge(v, request.get_ref())?;
+                    (PendingChallenge::Ristretto(Box::new(challenge)), c)
+                }
+                UserVerifier::SchnorrModp(v) => {
+                    let (challenge, c) = create_schnorr_challenge(v, request.get_ref())?;
+                    (PendingChallenge::SchnorrModp(challenge), c)
+                }
+                UserVerifier::SchnorrRistretto(v) => {
+                    let (challenge, c) = create_schnorr_challenge(v, request.get_ref())?;
+                    (PendingChallenge::SchnorrRistretto(Box::new(challenge)), c)
+                }
+                UserVerifier::PolicyModp(v) => {
+                    let (challenge, c) = create_policy_challenge(v, request.get_ref())?;
+                    (PendingChallenge::PolicyModp(challenge), c)
+                }
+                UserVerifier::PolicyRistretto(v) => {
+                    let (challenge, c) = create_policy_challenge(v, request.get_ref())?;
+                    (PendingChallenge::PolicyRistretto(Box::new(challenge)), c)
+                }
+            };
+            let auth_id = utils::generate_random_string(32, &mut OsRng);
+            let mut auth_ids: std::sync::MutexGuard<'_, PendingMap<PendingAuthentication>> =
+                self.state.auth_ids.lock().unwrap();
+            let owner = SessionOwner::User(username.clone());
+            auth_ids.insert(owner, auth_id.to_string(), PendingAuthentication { user: username, challenge })?;
+
+            Ok(Response::new(AuthenticationChallengeResponse { auth_id, c }))
         } else {
-            return Err(Status::not_found(format!(
-                "User Already Exists {}",
-                username.to_string()
-            )));
+            Err(Status::not_found(format!(
+                "User Not Found {}",
+                username
+            )))
         }
     }
 
@@ -137,46 +742,179 @@ impl Auth for MyAuth {
         request: Request<AuthenticationAnswerRequest>,
     ) -> Result<Response<AuthenticationAnswerResponse>, Status>{
         let auth_id =  request.get_ref().auth_id.to_string();
-        let mut auth_ids: std::sync::MutexGuard<'_, HashMap<String, String>> = self.state.auth_ids.lock().unwrap();
-        if let Some(username) = auth_ids.clone().get_mut(&auth_id) {
-            debug!("[S] Requesting Authentication for {}", username.to_string());
-            auth_ids.remove(&auth_id);
-            let mut verifiers: std::sync::MutexGuard<'_, HashMap<String, ChaumPedersenVerifier>> = self.state.verifiers.lock().unwrap();
-            if let Some(verifier) = verifiers.get_mut(&username.to_string()){
+        // Each auth_id can be answered once and before it expires; release the lock before taking the verifiers one
+        let pending = self.state.auth_ids.lock().unwrap().take(&auth_id).map(|(_, pending)| pending);
+        if let Some(PendingAuthentication { user: username, challenge }) = pending {
+            debug!("[S] Requesting Authentication for {}", username);
+            let verifiers: std::sync::MutexGuard<'_, HashMap<String, UserVerifier>> = self.state.verifiers.lock().unwrap();
+            if let Some(verifier) = verifiers.get(&username){
                 // User exists 
-            
-                let verification_result: bool = verifier.verify_sync_b(
-                    BigInt::from(request.get_ref().s)
-                );
+                let verification_result: bool = match (verifier, challenge) {
+                    (UserVerifier::Modp(v), PendingChallenge::Modp(c)) => answer_challenge(v, c, request.get_ref())?,
+                    (UserVerifier::Ristretto(v), PendingChallenge::Ristretto(c)) => answer_challenge(v, *c, request.get_ref())?,
+                    (UserVerifier::SchnorrModp(v), PendingChallenge::SchnorrModp(c)) => {
+                        answer_schnorr_challenge(v, c, request.get_ref())?
+                    }
+                    (UserVerifier::SchnorrRistretto(v), PendingChallenge::SchnorrRistretto(c)) => {
+                        answer_schnorr_challenge(v, *c, request.get_ref())?
+                    }
+                    (UserVerifier::PolicyModp(v), PendingChallenge::PolicyModp(c)) => {
+                        answer_policy_challenge(v, c, request.get_ref())?
+                    }
+                    (UserVerifier::PolicyRistretto(v), PendingChallenge::PolicyRistretto(c)) => {
+                        answer_policy_challenge(v, *c, request.get_ref())?
+                    }
+                    _ => return Err(RequestError::GroupMismatch.into()),
+                };
                 if verification_result {
-                    let session_id = utils::generate_random_string(32);
                     let reply: AuthenticationAnswerResponse = AuthenticationAnswerResponse {
-                        session_id: session_id.to_string()
+                        session_id: self.create_session(SessionOwner::User(username.clone()))
                     };
-                    let mut sessions: std::sync::MutexGuard<'_, HashMap<String, String>> = self.state.sessions.lock().unwrap();
-                    sessions.insert(session_id.to_string(), username.to_string());
-                    return Ok(Response::new(reply));
+                    Ok(Response::new(reply))
                 } else {
-                    return Err(
+                    Err(
                         Status::permission_denied(
-                            format!("Verification Unsuccesful {}", username.to_string())
+                            format!("Verification Unsuccesful {}", username)
                         )
-                    );
+                    )
                 }
             } else {
-                return Err(Status::not_found(
+                Err(Status::not_found(
                     format!("User for Auth_ID Not Found {} - {}", 
-                    auth_id.to_string(), 
-                    username.to_string()
+                    auth_id, 
+                    username
                 ))
-            );
+            )
+            }
+        } else {
+            Err(Status::not_found(format!("Auth_ID Not Found {}", auth_id)))
+        }
+    }
+
+    // Implementation of the create_login_nonce function
+    async fn create_login_nonce(
+        &self,
+        request: Request<LoginNonceRequest>,
+    ) -> Result<Response<LoginNonceResponse>, Status> {
+        let username = request.get_ref().user.to_string();
+        debug!("[S] Requesting Login Nonce for {}", username);
+        if self.state.verifiers.lock().unwrap().contains_key(&username) {
+            // User exists
+            Ok(Response::new(LoginNonceResponse { login_nonce: self.issue_login_nonce(SessionOwner::User(username))? }))
+        } else {
+            Err(Status::not_found(format!("User Not Found {}", username)))
+        }
+    }
+
+    // Implementation of the verify_authentication_async function
+    async fn verify_authentication_async(
+        &self,
+        request: Request<AsyncAuthenticationRequest>,
+    ) -> Result<Response<AuthenticationAnswerResponse>, Status> {
+        let username = request.get_ref().user.to_string();
+        debug!("[S] Requesting Async Authentication for {}", username);
+        let verifiers: std::sync::MutexGuard<'_, HashMap<String, UserVerifier>> =
+            self.state.verifiers.lock().unwrap();
+        if let Some(verifier) = verifiers.get(&username) {
+            // User exists
+            // The nonce is spent even if the proof fails, so every attempt needs a fresh one
+            let login_nonce = request.get_ref().login_nonce.to_string();
+            if !self.take_login_nonce(&login_nonce, &SessionOwner::User(username.clone())) {
+                return Err(Status::permission_denied(format!("Login nonce not found, expired or already used {}", login_nonce)));
+            }
+            let verification_result: bool = match verifier {
+                UserVerifier::Modp(v) => verify_proof(v, request.get_ref())?,
+                UserVerifier::Ristretto(v) => verify_proof(v, request.get_ref())?,
+                UserVerifier::SchnorrModp(v) => verify_schnorr_proof(v, request.get_ref())?,
+                UserVerifier::SchnorrRistretto(v) => verify_schnorr_proof(v, request.get_ref())?,
+                UserVerifier::PolicyModp(v) => verify_policy_proof(v, request.get_ref())?,
+                UserVerifier::PolicyRistretto(v) => verify_policy_proof(v, request.get_ref())?,
+            };
+            if verification_result {
+                let reply: AuthenticationAnswerResponse = AuthenticationAnswerResponse {
+                    session_id: self.create_session(SessionOwner::User(username.clone()))
+                };
+                Ok(Response::new(reply))
+            } else {
+                Err(Status::permission_denied(format!("Verification Unsuccesful {}", username)))
             }
         } else {
-            return Err(Status::not_found(format!("Auth_ID Not Found {}", auth_id.to_string())));
+            Err(Status::not_found(format!("User Not Found {}", username)))
+        }
+    }
+
+    // Implementation of the register_user_group function
+    async fn register_user_group(
+        &self,
+        request: Request<RegisterUserGroupRequest>,
+    ) -> Result<Response<RegisterUserGroupResponse>, Status> {
+        let name = request.get_ref().name.to_string();
+        let users = &request.get_ref().users;
+        debug!("[S] Requesting User Group Registration for {} with {} members", name, users.len());
+        let verifiers: std::sync::MutexGuard<'_, HashMap<String, UserVerifier>> = self.state.verifiers.lock().unwrap();
+        let verifier = user_group_verifier(&verifiers, users)?;
+        let group = self.state.logins.lock().unwrap()[&users[0]].group.clone();
+        let mut user_groups: std::sync::MutexGuard<'_, HashMap<String, UserGroup>> = self.state.user_groups.lock().unwrap();
+        if user_groups.contains_key(&name) {
+            return Err(Status::already_exists(format!("User Group Already Exists {}", name)));
+        }
+        user_groups.insert(name, UserGroup { group, members: users.clone(), verifier });
+        Ok(Response::new(RegisterUserGroupResponse {}))
+    }
+
+    // Implementation of the get_user_group function
+    async fn get_user_group(
+        &self,
+        request: Request<UserGroupRequest>,
+    ) -> Result<Response<UserGroupResponse>, Status> {
+        let name = request.get_ref().name.to_string();
+        debug!("[S] Requesting User Group {}", name);
+        let user_groups: std::sync::MutexGuard<'_, HashMap<String, UserGroup>> = self.state.user_groups.lock().unwrap();
+        if let Some(user_group) = user_groups.get(&name) {
+            let members = match &user_group.verifier {
+                UserGroupVerifier::Modp(v) => member_keys(v, &user_group.members),
+                UserGroupVerifier::Ristretto(v) => member_keys(v, &user_group.members),
+            };
+            Ok(Response::new(UserGroupResponse {
+                group: user_group.group.clone(),
+                members,
+                login_nonce: self.issue_login_nonce(SessionOwner::UserGroup(name))?,
+            }))
+        } else {
+            Err(Status::not_found(format!("User Group Not Found {}", name)))
         }
+    }
 
-        
-        
+    // Implementation of the verify_user_group_authentication function
+    async fn verify_user_group_authentication(
+        &self,
+        request: Request<UserGroupAuthenticationRequest>,
+    ) -> Result<Response<AuthenticationAnswerResponse>, Status> {
+        let name = request.get_ref().name.to_string();
+        debug!("[S] Requesting User Group Authentication for {}", name);
+        // As with user logins, the nonce is spent even if the proof fails
+        let login_nonce = request.get_ref().login_nonce.to_string();
+        if !self.take_login_nonce(&login_nonce, &SessionOwner::UserGroup(name.clone())) {
+            return Err(Status::permission_denied(format!("Login nonce not found, expired or already used {}", login_nonce)));
+        }
+        let user_groups: std::sync::MutexGuard<'_, HashMap<String, UserGroup>> = self.state.user_groups.lock().unwrap();
+        if let Some(user_group) = user_groups.get(&name) {
+            let verification_result: bool = match &user_group.verifier {
+                UserGroupVerifier::Modp(v) => verify_or_proof(v, request.get_ref())?,
+                UserGroupVerifier::Ristretto(v) => verify_or_proof(v, request.get_ref())?,
+            };
+            if verification_result {
+                // The session names the user group only, as the proof does not reveal the member
+                let reply: AuthenticationAnswerResponse = AuthenticationAnswerResponse {
+                    session_id: self.create_session(SessionOwner::UserGroup(name))
+                };
+                Ok(Response::new(reply))
+            } else {
+                Err(Status::permission_denied(format!("Verification Unsuccesful for user group {}", name)))
+            }
+        } else {
+            Err(Status::not_found(format!("User Group Not Found {}", name)))
+        }
     }
 }
 // Main async function
@@ -188,13 +926,12 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
 
     // Parse command line arguments
     let args: Vec<String> = env::args().collect();
-    let host: String;
 
     // Determine the host
-    match args.len() {
-        2 => host = format!("0.0.0.0:{}", &args[1]),
-        _ => host = "0.0.0.0:50051".to_string(),
-    }
+    let host: String = match args.len() {
+        2 => format!("0.0.0.0:{}", &args[1]),
+        _ => "0.0.0.0:50051".to_string(),
+    };
 
     // Parse the address
     let addr = host.parse()?;
@@ -210,4 +947,219 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
         .await?;
 
     Ok(())
-}
\ No newline at end of file
+}
+#[cfg(test)]
+mod test {
+    use rand::rngs::OsRng;
+    use tonic::{Code, Request};
+
+    use crate::utils;
+    use crate::zkrypto::chaum_pedersen::ChaumPedersenProver;
+    use crate::zkrypto::composition::OrProver;
+    use crate::zkrypto::generator::{get_default, get_named, ValidationParams};
+    use crate::zkrypto::group::Group;
+
+    use crate::zkp_auth::ProofBranch;
+
+    use super::{
+        AsyncAuthenticationRequest, Auth, AuthenticationAnswerRequest, AuthenticationChallengeRequest, LoginNonceRequest, MyAuth,
+        PendingMap, RegisterRequest, RegisterUserGroupRequest, RequestError, SessionOwner, UserGroupAuthenticationRequest,
+        UserGroupRequest, MAX_PENDING, MAX_PENDING_PER_OWNER, PENDING_TTL,
+    };
+
+    // Registers a user with a random key in the default group
+    async fn register(auth: &MyAuth, user: &str) -> ChaumPedersenProver {
+        let prover = ChaumPedersenProver::new(&mut OsRng);
+        let g = prover.group();
+        let request = RegisterRequest {
+            user: user.to_string(),
+            y1: g.encode_element(&prover.kp.y1),
+            y2: g.encode_element(&prover.kp.y2),
+            group: utils::DEFAULT_GROUP.to_string(),
+            ..Default::default()
+        };
+        auth.register(Request::new(request)).await.unwrap();
+        prover
+    }
+
+    async fn login_nonce(auth: &MyAuth, user: &str) -> String {
+        let request = LoginNonceRequest { user: user.to_string() };
+        auth.create_login_nonce(Request::new(request)).await.unwrap().into_inner().login_nonce
+    }
+
+    // Builds a non-interactive login request for the given login nonce
+    fn async_request(prover: &ChaumPedersenProver, user: &str, login_nonce: &str) -> AsyncAuthenticationRequest {
+        let g = prover.group();
+        let proof = prover.prove(&utils::async_login_context(user, login_nonce), &mut OsRng);
+        AsyncAuthenticationRequest {
+            user: user.to_string(),
+            r1: g.encode_element(&proof.r1),
+            r2: g.encode_element(&proof.r2),
+            c: g.encode_scalar(&proof.c),
+            s: g.encode_scalar(&proof.s),
+            login_nonce: login_nonce.to_string(),
+            ..Default::default()
+        }
+    }
+
+    #[tokio::test]
+    async fn test_size_floors_apply_to_all_but_toy_groups() {
+        let mut params = utils::group_validation("ffdhe2048");
+        assert_eq!(params, ValidationParams::default());
+        params.rounds = 2;
+        assert!(get_named("ffdhe2048").unwrap().validate(&params, &mut OsRng).is_ok());
+        // The toy groups keep every other check, and their parameters still fail the real floors
+        assert!(get_default().validate(&ValidationParams::default(), &mut OsRng).is_err());
+        let auth = MyAuth::new();
+        assert!(auth.load_group("").await.is_ok());
+        assert!(auth.load_group("debug").await.is_ok());
+        assert!(matches!(auth.load_group("no-such-group").await, Err(RequestError::UnknownGroup(_))));
+    }
+
+    #[tokio::test]
+    async fn test_async_authentication_rejects_replay() {
+        let auth = MyAuth::new();
+        let prover = register(&auth, "alice").await;
+        let request = async_request(&prover, "alice", &login_nonce(&auth, "alice").await);
+
+        assert!(auth.verify_authentication_async(Request::new(request.clone())).await.is_ok());
+        let replayed = auth.verify_authentication_async(Request::new(request)).await.unwrap_err();
+        assert_eq!(replayed.code(), Code::PermissionDenied);
+
+        // A fresh nonce logs in again, but not one issued to another user
+        let request = async_request(&prover, "alice", &login_nonce(&auth, "alice").await);
+        assert!(auth.verify_authentication_async(Request::new(request)).await.is_ok());
+        register(&auth, "bob").await;
+        let request = async_request(&prover, "alice", &login_nonce(&auth, "bob").await);
+        let stolen = auth.verify_authentication_async(Request::new(request)).await.unwrap_err();
+        assert_eq!(stolen.code(), Code::PermissionDenied);
+    }
+
+    #[tokio::test]
+    async fn test_authentication_challenge_requires_fresh_nonce() {
+        let auth = MyAuth::new();
+        let prover = register(&auth, "alice").await;
+        let g = prover.group();
+        let login_nonce = login_nonce(&auth, "alice").await;
+        let commitment = prover.prove_sync_a_with_context(&utils::interactive_login_context("alice", &login_nonce), &mut OsRng);
+        let request = AuthenticationChallengeRequest {
+            user: "alice".to_string(),
+            r1: g.encode_element(commitment.r1()),
+            r2: g.encode_element(commitment.r2()),
+            login_nonce,
+            ..Default::default()
+        };
+
+        let challenge = auth.create_authentication_challenge(Request::new(request.clone())).await.unwrap().into_inner();
+        let s = commitment.prove_sync_b(&g.decode_scalar(&challenge.c).unwrap());
+        let answer = AuthenticationAnswerRequest { auth_id: challenge.auth_id, s: g.encode_scalar(&s), ..Default::default() };
+        assert!(auth.verify_authentication(Request::new(answer)).await.is_ok());
+
+        // Neither a spent nonce nor no nonce at all gets another challenge
+        let reused = auth.create_authentication_challenge(Request::new(request.clone())).await.unwrap_err();
+        assert_eq!(reused.code(), Code::PermissionDenied);
+        let request = AuthenticationChallengeRequest { login_nonce: String::new(), ..request };
+        let missing = auth.create_authentication_challenge(Request::new(request)).await.unwrap_err();
+        assert_eq!(missing.code(), Code::PermissionDenied);
+    }
+
+    #[tokio::test]
+    async fn test_unknown_user_is_not_found() {
+        let auth = MyAuth::new();
+        let prover = register(&auth, "alice").await;
+        let login_nonce = login_nonce(&auth, "alice").await;
+
+        // Reported before the nonce is looked at, so the nonce stays usable for its own user
+        let request = AuthenticationChallengeRequest { user: "mallory".to_string(), login_nonce: login_nonce.clone(), ..Default::default() };
+        let unknown = auth.create_authentication_challenge(Request::new(request)).await.unwrap_err();
+        assert_eq!(unknown.code(), Code::NotFound);
+        let request = async_request(&prover, "mallory", &login_nonce);
+        let unknown = auth.verify_authentication_async(Request::new(request)).await.unwrap_err();
+        assert_eq!(unknown.code(), Code::NotFound);
+
+        let request = async_request(&prover, "alice", &login_nonce);
+        assert!(auth.verify_authentication_async(Request::new(request)).await.is_ok());
+    }
+
+    #[tokio::test]
+    async fn test_login_nonces_expire_and_are_capped() {
+        let auth = MyAuth::new();
+        let prover = register(&auth, "alice").await;
+        register(&auth, "bob").await;
+
+        // An expired nonce is refused, even by a proof made with it
+        let stale = login_nonce(&auth, "alice").await;
+        auth.state.login_nonces.lock().unwrap().entries.get_mut(&stale).unwrap().issued -= PENDING_TTL;
+        let request = async_request(&prover, "alice", &stale);
+        let expired = auth.verify_authentication_async(Request::new(request)).await.unwrap_err();
+        assert_eq!(expired.code(), Code::PermissionDenied);
+
+        // Flooding alice only replaces her own oldest nonces, and bob still gets one
+        let mut flood = Vec::new();
+        for _ in 0..=MAX_PENDING_PER_OWNER {
+            flood.push(login_nonce(&auth, "alice").await);
+        }
+        let request = async_request(&prover, "alice", &flood[0]);
+        let evicted = auth.verify_authentication_async(Request::new(request)).await.unwrap_err();
+        assert_eq!(evicted.code(), Code::PermissionDenied);
+        login_nonce(&auth, "bob").await;
+        assert_eq!(auth.state.login_nonces.lock().unwrap().entries.len(), MAX_PENDING_PER_OWNER + 1);
+        let request = async_request(&prover, "alice", &flood[MAX_PENDING_PER_OWNER]);
+        assert!(auth.verify_authentication_async(Request::new(request)).await.is_ok());
+
+        // Only the live nonces of many owners reach the global cap, and only until they expire
+        let mut pending = PendingMap::new();
+        for i in 0..MAX_PENDING {
+            let owner = SessionOwner::User((i % (MAX_PENDING / MAX_PENDING_PER_OWNER)).to_string());
+            pending.insert(owner, i.to_string(), ()).unwrap();
+        }
+        *auth.state.login_nonces.lock().unwrap() = pending;
+        let full = auth.create_login_nonce(Request::new(LoginNonceRequest { user: "bob".to_string() })).await.unwrap_err();
+        assert_eq!(full.code(), Code::ResourceExhausted);
+        {
+            let mut pending = auth.state.login_nonces.lock().unwrap();
+            pending.entries.values_mut().for_each(|p| p.issued -= PENDING_TTL);
+            pending.swept -= PENDING_TTL;
+        }
+        login_nonce(&auth, "bob").await;
+        let pending = auth.state.login_nonces.lock().unwrap();
+        assert_eq!((pending.entries.len(), pending.by_owner.len()), (1, 1));
+    }
+
+    #[tokio::test]
+    async fn test_user_group_authentication_rejects_replay() {
+        let auth = MyAuth::new();
+        let prover = register(&auth, "alice").await;
+        register(&auth, "bob").await;
+        let users = vec!["bob".to_string(), "alice".to_string()];
+        auth.register_user_group(Request::new(RegisterUserGroupRequest { name: "team".to_string(), users })).await.unwrap();
+
+        let user_group = auth.get_user_group(Request::new(UserGroupRequest { name: "team".to_string() })).await.unwrap().into_inner();
+        let g = prover.group();
+        let statement = user_group
+            .members
+            .iter()
+            .map(|m| (g.decode_element(&m.y1).unwrap(), g.decode_element(&m.y2).unwrap()))
+            .collect();
+        let or_prover = OrProver::with_secret(g.clone(), prover.kp.secret().clone(), statement).unwrap();
+        let proof = or_prover.prove(&utils::user_group_login_context("team", &user_group.login_nonce), &mut OsRng);
+        let request = UserGroupAuthenticationRequest {
+            name: "team".to_string(),
+            branches: proof
+                .branches
+                .iter()
+                .map(|b| ProofBranch {
+                    r1: g.encode_element(&b.r1),
+                    r2: g.encode_element(&b.r2),
+                    c: g.encode_scalar(&b.c),
+                    s: g.encode_scalar(&b.s),
+                })
+                .collect(),
+            login_nonce: user_group.login_nonce,
+        };
+
+        assert!(auth.verify_user_group_authentication(Request::new(request.clone())).await.is_ok());
+        let replayed = auth.verify_user_group_authentication(Request::new(request)).await.unwrap_err();
+        assert_eq!(replayed.code(), Code::PermissionDenied);
+    }
+}
@@ -1,9 +1,27 @@
-use rand::Rng;
+use rand::{CryptoRng, Rng, RngCore};
 
-// Generate a random string of length
-pub fn generate_random_string(length: usize) -> String {
+use crate::zkrypto::generator::ValidationParams;
+
+// Names of the groups a user can register in
+pub const DEFAULT_GROUP: &str = "default";
+pub const RISTRETTO_GROUP: &str = "ristretto255";
+
+// Built-in toy groups, far below the size floors, that are accepted by name for demos and tests.
+// The names resolve to fixed constants, so no received parameters can claim the exception.
+pub const TOY_GROUPS: [&str; 3] = ["", DEFAULT_GROUP, "debug"];
+
+// Checks run on the finite-field group of the given name when it is loaded: the full checks of
+// ValidationParams::default(), with the size floors lifted only for the toy groups
+pub fn group_validation(name: &str) -> ValidationParams {
+    match TOY_GROUPS.contains(&name) {
+        true => ValidationParams { min_p_bits: 0, min_q_bits: 0, ..ValidationParams::default() },
+        false => ValidationParams::default(),
+    }
+}
+
+// Generate a random string of length, drawing characters from rng
+pub fn generate_random_string<R: RngCore + CryptoRng + ?Sized>(length: usize, rng: &mut R) -> String {
     let charset: &[u8] = b"abcdefghijklmnopqrstuvwxyzABCDEFGHIJKLMNOPQRSTUVWXYZ0123456789";
-    let mut rng = rand::thread_rng();
     let random_string: String = (0..length)
         .map(|_| {
             let random_index = rng.gen_range(0..charset.len());
@@ -12,4 +30,40 @@ pub fn generate_random_string(length: usize) -> String {
         .collect();
 
     random_string
-}
\ No newline at end of file
+}
+
+// Context bound into the nonces of interactive login commitments. As the server accepts each login
+// nonce once, the context is unique per login and a hedged nonce cannot repeat even if the RNG does.
+pub fn interactive_login_context(user: &str, login_nonce: &str) -> Vec<u8> {
+    [
+        b"zkp_auth/create-authentication-challenge/".as_slice(),
+        &(user.len() as u64).to_be_bytes(),
+        user.as_bytes(),
+        login_nonce.as_bytes(),
+    ]
+    .concat()
+}
+
+// Context bound into non-interactive login proofs, so a proof for one user cannot be replayed for
+// another and, as the server accepts each login nonce once, not for the same user either
+pub fn async_login_context(user: &str, login_nonce: &str) -> Vec<u8> {
+    [
+        b"zkp_auth/verify-authentication-async/".as_slice(),
+        &(user.len() as u64).to_be_bytes(),
+        user.as_bytes(),
+        login_nonce.as_bytes(),
+    ]
+    .concat()
+}
+
+// Context bound into user group login proofs, so a proof for one user group cannot be replayed for
+// another and, as the server accepts each login nonce once, not for the same user group either
+pub fn user_group_login_context(name: &str, login_nonce: &str) -> Vec<u8> {
+    [
+        b"zkp_auth/verify-user-group-authentication/".as_slice(),
+        &(name.len() as u64).to_be_bytes(),
+        name.as_bytes(),
+        login_nonce.as_bytes(),
+    ]
+    .concat()
+}
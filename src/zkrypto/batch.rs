@@ -0,0 +1,197 @@
+use log::debug;
+use num_bigint::BigInt;
+use rand::{CryptoRng, RngCore};
+
+use crate::zkrypto::chaum_pedersen::{fiat_shamir_challenge, ChaumPedersenProof, ChaumPedersenVerifier};
+use crate::zkrypto::generator::{Generator, get_default};
+use crate::zkrypto::group::Group;
+
+// A queued proof together with the public keys and context it is checked against
+struct Entry<G: Group> {
+    y1: G::Element,
+    y2: G::Element,
+    proof: ChaumPedersenProof<G>,
+    context: Vec<u8>,
+}
+
+// Verifies many non-interactive Chaum-Pedersen proofs in one group at once.
+//
+// Each proof i passes if r1_i = g^s_i * y1_i^c_i and r2_i = h^s_i * y2_i^c_i. Raising both
+// equations to random 128-bit weights a_i and b_i and multiplying them over all proofs gives
+//
+//   prod r1_i^a_i * r2_i^b_i = g^(sum a_i s_i) * h^(sum b_i s_i) * prod y1_i^(a_i c_i) * y2_i^(b_i c_i)
+//
+// which holds when every proof passes, and otherwise fails except with probability about
+// 2^-128 (or 1/q in smaller groups). Both sides are multi-exponentiations, and the left one
+// only has short exponents. A failing batch is split in halves to locate the invalid proofs.
+pub struct ChaumPedersenBatchVerifier<G: Group = Generator> {
+    g: G,                   // Group instance
+    entries: Vec<Entry<G>>, // Queued proofs, in the order they were pushed
+}
+
+impl ChaumPedersenBatchVerifier {
+    // Creates an empty batch over the default group
+    pub fn new() -> ChaumPedersenBatchVerifier {
+        ChaumPedersenBatchVerifier::with_group(get_default())
+    }
+}
+
+impl Default for ChaumPedersenBatchVerifier {
+    fn default() -> ChaumPedersenBatchVerifier {
+        ChaumPedersenBatchVerifier::new()
+    }
+}
+
+impl<G: Group> ChaumPedersenBatchVerifier<G> {
+    // Creates an empty batch over the given group
+    pub fn with_group(g: G) -> ChaumPedersenBatchVerifier<G> {
+        ChaumPedersenBatchVerifier { g, entries: Vec::new() }
+    }
+
+    // Queues a proof for the given context by the owner of the verifier's keys, which were
+    // checked when the verifier was created. Returns the index of the proof in the batch.
+    pub fn push(&mut self, verifier: &ChaumPedersenVerifier<G>, proof: ChaumPedersenProof<G>, context: &[u8]) -> usize {
+        self.entries.push(Entry {
+            y1: verifier.y1().clone(),
+            y2: verifier.y2().clone(),
+            proof,
+            context: context.to_vec(),
+        });
+        self.entries.len() - 1
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    // Checks every queued proof, returning the indices of the invalid ones in increasing order
+    pub fn verify<R: RngCore + CryptoRng + ?Sized>(&self, rng: &mut R) -> Result<(), Vec<usize>> {
+        debug!("[V] VERIFY BATCH of {}", self.entries.len());
+        // Membership and the challenge hash are cheap, so they are checked proof by proof
+        let (candidates, mut failures): (Vec<usize>, Vec<usize>) = (0..self.entries.len()).partition(|&i| {
+            let e = &self.entries[i];
+            self.g.check_element(&e.proof.r1).is_ok()
+                && self.g.check_element(&e.proof.r2).is_ok()
+                && fiat_shamir_challenge(&self.g, &e.y1, &e.y2, &e.proof.r1, &e.proof.r2, &e.context) == e.proof.c
+        });
+        self.bisect(&candidates, rng, &mut failures);
+        failures.sort_unstable();
+        debug!("[V] END VERIFY BATCH with {} failures", failures.len());
+        match failures.is_empty() {
+            true => Ok(()),
+            false => Err(failures),
+        }
+    }
+
+    // Collects the failing proofs among indices, checking halves of a failing set recursively
+    fn bisect<R: RngCore + CryptoRng + ?Sized>(&self, indices: &[usize], rng: &mut R, failures: &mut Vec<usize>) {
+        if indices.is_empty() || self.check(indices, rng) {
+            return;
+        }
+        if indices.len() == 1 {
+            failures.push(indices[0]);
+            return;
+        }
+        let (left, right) = indices.split_at(indices.len() / 2);
+        self.bisect(left, rng, failures);
+        self.bisect(right, rng, failures);
+    }
+
+    // Draws a nonzero weight below 2^128
+    fn weight<R: RngCore + CryptoRng + ?Sized>(&self, rng: &mut R) -> G::Scalar {
+        loop {
+            let w = (u128::from(rng.next_u64()) << 64) | u128::from(rng.next_u64());
+            let w = self.g.scalar_from_bigint(&BigInt::from(w));
+            if w != G::Scalar::default() {
+                return w;
+            }
+        }
+    }
+
+    // Checks the randomly weighted product of the verification equations of the given proofs
+    fn check<R: RngCore + CryptoRng + ?Sized>(&self, indices: &[usize], rng: &mut R) -> bool {
+        let g = &self.g;
+        // Commitments with their weights on the left, generators and public keys on the right
+        let (mut commitments, mut weights) = (Vec::new(), Vec::new());
+        let (mut bases, mut exponents) = (vec![g.g(), g.h()], vec![G::Scalar::default(), G::Scalar::default()]);
+        for &i in indices {
+            let Entry { y1, y2, proof, .. } = &self.entries[i];
+            let (a, b) = (self.weight(rng), self.weight(rng));
+            exponents[0] = g.scalar_add(&exponents[0], &g.scalar_mul(&a, &proof.s));
+            exponents[1] = g.scalar_add(&exponents[1], &g.scalar_mul(&b, &proof.s));
+            bases.extend([y1.clone(), y2.clone()]);
+            exponents.extend([g.scalar_mul(&a, &proof.c), g.scalar_mul(&b, &proof.c)]);
+            commitments.extend([proof.r1.clone(), proof.r2.clone()]);
+            weights.extend([a, b]);
+        }
+        g.multi_exp(&commitments, &weights) == g.multi_exp(&bases, &exponents)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use rand::rngs::StdRng;
+    use rand::SeedableRng;
+
+    use crate::zkrypto::chaum_pedersen::{ChaumPedersenProof, ChaumPedersenProver, ChaumPedersenVerifier};
+    use crate::zkrypto::generator::get_named;
+    use crate::zkrypto::group::Group;
+    use crate::zkrypto::ristretto::RistrettoGroup;
+    use super::ChaumPedersenBatchVerifier;
+
+    // Fills a batch with n valid proofs by distinct provers, each for its own context
+    fn valid_batch<G: Group>(g: G, n: usize, rng: &mut StdRng) -> ChaumPedersenBatchVerifier<G> {
+        let mut batch = ChaumPedersenBatchVerifier::with_group(g.clone());
+        for i in 0..n {
+            let prover = ChaumPedersenProver::with_group(g.clone(), rng);
+            let context = format!("login {}", i);
+            let proof = prover.prove(context.as_bytes(), rng);
+            let verifier = ChaumPedersenVerifier::with_group(g.clone(), prover.kp.y1.clone(), prover.kp.y2.clone()).unwrap();
+            batch.push(&verifier, proof, context.as_bytes());
+        }
+        batch
+    }
+
+    #[test]
+    fn test_accepts_valid_batches() {
+        let mut rng = StdRng::seed_from_u64(19);
+        assert_eq!(ChaumPedersenBatchVerifier::new().verify(&mut rng), Ok(()));
+        assert_eq!(valid_batch(get_named("modp2048-256").unwrap(), 8, &mut rng).verify(&mut rng), Ok(()));
+        assert_eq!(valid_batch(RistrettoGroup::new(), 40, &mut rng).verify(&mut rng), Ok(()));
+    }
+
+    #[test]
+    fn test_reports_failing_indices() {
+        let mut rng = StdRng::seed_from_u64(20);
+        let g = RistrettoGroup::new();
+        let mut batch = valid_batch(g.clone(), 32, &mut rng);
+
+        // A response off by one, a proof replayed under another context, and a proof for other keys
+        let e = &mut batch.entries[3];
+        e.proof.s = g.scalar_add(&e.proof.s, &g.scalar_from_bigint(&1u32.into()));
+        batch.entries[17].context = b"login 18".to_vec();
+        let other = ChaumPedersenProver::with_group(g.clone(), &mut rng).prove(b"login 30", &mut rng);
+        batch.entries[30].proof = other;
+        assert_eq!(batch.verify(&mut rng), Err(vec![3, 17, 30]));
+    }
+
+    #[test]
+    fn test_rejects_forgeries_that_cancel_out() {
+        let mut rng = StdRng::seed_from_u64(21);
+        let g = get_named("modp2048-256").unwrap();
+        let mut batch = valid_batch(g.clone(), 4, &mut rng);
+
+        // Adding d to one response and subtracting it from another leaves the unweighted product
+        // of all equations intact, which the random weights must still catch
+        let d = g.scalar_from_bigint(&7u32.into());
+        let s0 = g.scalar_add(&batch.entries[0].proof.s, &d);
+        let s1 = g.scalar_sub(&batch.entries[1].proof.s, &d);
+        batch.entries[0].proof = ChaumPedersenProof { s: s0, ..batch.entries[0].proof.clone() };
+        batch.entries[1].proof = ChaumPedersenProof { s: s1, ..batch.entries[1].proof.clone() };
+        assert_eq!(batch.verify(&mut rng), Err(vec![0, 1]));
+    }
+}
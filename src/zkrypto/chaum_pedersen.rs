@@ -1,164 +1,649 @@
-use num_bigint::{BigInt, RandBigInt};
-use num_traits::{Zero, One};
+use num_bigint::BigInt;
 use log::{debug};
+use rand::{CryptoRng, RngCore};
+use sha2::{Digest, Sha256};
 use std::fmt::Debug;
 
 use crate::zkrypto::generator::{Generator, get_default};
+use crate::zkrypto::group::{ElementError, Group};
+use crate::zkrypto::nonce::{self, NonceMode};
+use crate::zkrypto::scalar::Secret;
+
+// Domain separation tag for the Fiat-Shamir challenge hash
+const FIAT_SHAMIR_DOMAIN: &[u8] = b"zkp_auth/chaum-pedersen/fiat-shamir/v1";
 
 // Represents a key pair containing private and public components
-#[derive(Default)]
-pub struct KeyPair{
-    x: BigInt,      // Private key
-    pub y1: BigInt, // Public key component 1
-    pub y2: BigInt  // Public key component 2
+pub struct KeyPair<G: Group = Generator> {
+    x: Secret<G::Scalar>, // Private key, wiped on drop
+    pub y1: G::Element, // Public key component 1
+    pub y2: G::Element  // Public key component 2
 }
 
-impl KeyPair {
-    // Generates a new key pair using provided generator and parameters
-    fn new(g: &BigInt, h: &BigInt, p: &BigInt) -> KeyPair {
-        let mut rng = rand::thread_rng();
-        let x = rng.gen_bigint_range(&BigInt::one(), p);
-        let y1 = g.modpow(&x, p);
-        let y2 = h.modpow(&x, p);
-        return KeyPair {
-            x: x,
-            y1: y1,
-            y2: y2
-        };
+impl<G: Group> KeyPair<G> {
+    // Generates a new key pair over the generators of the provided group
+    fn new<R: RngCore + CryptoRng + ?Sized>(group: &G, rng: &mut R) -> KeyPair<G> {
+        KeyPair::from_secret(group, Secret::new(group.random_nonzero_scalar(rng)))
+    }
+
+    // Builds the key pair of an existing nonzero secret, e.g. one derived from a password
+    pub fn from_secret(group: &G, x: Secret<G::Scalar>) -> KeyPair<G> {
+        let y1 = group.exp_secret(&group.g(), x.expose());
+        let y2 = group.exp_secret(&group.h(), x.expose());
+        KeyPair {
+            x,
+            y1,
+            y2
+        }
+    }
+
+    // Borrows the private key, e.g. to write it to a key file
+    pub fn secret(&self) -> &Secret<G::Scalar> {
+        &self.x
     }
 }
 
+// Non-interactive Chaum-Pedersen proof obtained through the Fiat-Shamir transform
+#[derive(Clone, Debug, PartialEq)]
+pub struct ChaumPedersenProof<G: Group = Generator> {
+    pub r1: G::Element, // Commitment g^k
+    pub r2: G::Element, // Commitment h^k
+    pub c: G::Scalar,   // Challenge derived from the transcript hash
+    pub s: G::Scalar,   // Response k - c * x mod q
+}
+
+// Absorbs a length-prefixed byte string so that adjacent fields cannot be confused
+pub fn absorb(hasher: &mut Sha256, bytes: &[u8]) {
+    hasher.update((bytes.len() as u64).to_be_bytes());
+    hasher.update(bytes);
+}
+
+// Computes the Fiat-Shamir challenge H(domain, group, y1, y2, r1, r2, context) mod q
+pub fn fiat_shamir_challenge<G: Group>(
+    group: &G,
+    y1: &G::Element,
+    y2: &G::Element,
+    r1: &G::Element,
+    r2: &G::Element,
+    context: &[u8],
+) -> G::Scalar {
+    let mut hasher = Sha256::new();
+    absorb(&mut hasher, FIAT_SHAMIR_DOMAIN);
+    absorb(&mut hasher, &group.encode_parameters());
+    for x in [y1, y2, r1, r2] {
+        absorb(&mut hasher, &group.encode_element(x));
+    }
+    absorb(&mut hasher, context);
+    group.scalar_from_hash(&hasher.finalize())
+}
+
 // Represents a prover for the Chaum-Pedersen protocol
-#[derive(Default)]
-pub struct ChaumPedersenProver {
-    g: Generator,      // Generator instance
-    pub kp: KeyPair,   // Key pair
-    k: BigInt,         // Random integer k
-    r1: BigInt,        // First response value
-    r2: BigInt,        // Second response value
-    c: BigInt,         // Challenge value
-    s: BigInt,         // Solution value
-}
-
-impl Debug for ChaumPedersenProver {
+pub struct ChaumPedersenProver<G: Group = Generator> {
+    g: G,                   // Group instance
+    pub kp: KeyPair<G>,     // Key pair
+    nonce_mode: NonceMode,  // How nonces are obtained
+}
+
+// Commitment of a single interactive attempt, holding the nonce it was made with.
+// It is neither Clone nor Copy and answering consumes it, so a nonce can only be used once.
+pub struct Commitment<'a, G: Group = Generator> {
+    prover: &'a ChaumPedersenProver<G>, // Prover whose key answers the challenge
+    k: Secret<G::Scalar>,               // Random nonce k, wiped on drop
+    r1: G::Element,                     // First commitment g^k
+    r2: G::Element,                     // Second commitment h^k
+}
+
+impl<G: Group> Debug for Commitment<'_, G> {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(f, "Commitment <-")
+    }
+}
+
+impl<G: Group> Debug for ChaumPedersenProver<G> {
     fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
         write!(f, "ChaumPedersenProver <-")
     }
 }
 
 impl ChaumPedersenProver {
-    // Creates a new instance of ChaumPedersenProver
-    pub fn new() -> ChaumPedersenProver {
-        let g = get_default();
-        let kp = KeyPair::new(&g.g, &g.h, &g.p);
-
-        return ChaumPedersenProver {
-            g: g,
-            kp: kp,
-            k: BigInt::zero(),
-            r1: BigInt::zero(),
-            r2: BigInt::zero(),
-            c: BigInt::zero(),
-            s: BigInt::zero(),
+    // Creates a new instance of ChaumPedersenProver over the default group
+    pub fn new<R: RngCore + CryptoRng + ?Sized>(rng: &mut R) -> ChaumPedersenProver {
+        ChaumPedersenProver::with_group(get_default(), rng)
+    }
+}
+
+impl<G: Group> ChaumPedersenProver<G> {
+    // Creates a new instance of ChaumPedersenProver with a fresh key pair in the given group
+    pub fn with_group<R: RngCore + CryptoRng + ?Sized>(g: G, rng: &mut R) -> ChaumPedersenProver<G> {
+        let kp = KeyPair::new(&g, rng);
+
+        ChaumPedersenProver {
+            g,
+            kp,
+            nonce_mode: NonceMode::default(),
         }
     }
 
-    // Proves part A of the protocol synchronously
-    pub fn prove_sync_a(&mut self) -> (BigInt, BigInt) {
-        debug!("[P] PROVE SYNC A");
-        let mut rng = rand::thread_rng();
+    // Creates a new instance of ChaumPedersenProver for a known secret x in the given group
+    pub fn with_secret(g: G, x: Secret<G::Scalar>) -> ChaumPedersenProver<G> {
+        let kp = KeyPair::from_secret(&g, x);
+        ChaumPedersenProver::with_keypair(g, kp)
+    }
+
+    // Creates a new instance of ChaumPedersenProver for a key pair in the given group, e.g. one loaded from a key file
+    pub fn with_keypair(g: G, kp: KeyPair<G>) -> ChaumPedersenProver<G> {
+        ChaumPedersenProver {
+            g,
+            kp,
+            nonce_mode: NonceMode::default(),
+        }
+    }
 
-        self.k =  rng.gen_bigint_range(&BigInt::one(), &(self.g.p.clone() - 2));
-        self.r1 = self.g.g.clone().modpow(&self.k, &self.g.p);
-        self.r2 = self.g.h.clone().modpow(&self.k, &self.g.p);
+    // Selects how nonces are obtained; see NonceMode
+    pub fn with_nonce_mode(mut self, mode: NonceMode) -> ChaumPedersenProver<G> {
+        self.nonce_mode = mode;
+        self
+    }
+
+    // Obtains the nonce for a proof, binding it to the kind of proof and its context
+    fn nonce<R: RngCore + CryptoRng + ?Sized>(&self, mode: NonceMode, kind: &[u8], context: &[u8], rng: &mut R) -> Secret<G::Scalar> {
+        let context = [kind, context].concat();
+        nonce::nonce(mode, &self.g, &self.kp.x, &[&self.kp.y1, &self.kp.y2], &context, rng)
+    }
+
+    // Returns the group used by this prover
+    pub fn group(&self) -> &G {
+        &self.g
+    }
+
+    // Proves part A of the protocol synchronously, committing to a fresh nonce.
+    // The deterministic mode is hedged here; see NonceMode::interactive.
+    pub fn prove_sync_a<R: RngCore + CryptoRng + ?Sized>(&self, rng: &mut R) -> Commitment<'_, G> {
+        self.commit(&[], rng)
+    }
+
+    // Proves part A of the protocol for a login identified by context, such as a server-issued
+    // session nonce, which is bound into the nonce alongside fresh randomness
+    pub fn prove_sync_a_with_context<R: RngCore + CryptoRng + ?Sized>(&self, context: &[u8], rng: &mut R) -> Commitment<'_, G> {
+        self.commit(context, rng)
+    }
 
-        debug!("[P] y1: {}, y2:{}", &self.kp.y1, &self.kp.y2);
-        debug!("[P] r1: {}, r2:{}", &self.r1, &self.r2);
+    fn commit<R: RngCore + CryptoRng + ?Sized>(&self, context: &[u8], rng: &mut R) -> Commitment<'_, G> {
+        debug!("[P] PROVE SYNC A");
+        let k = self.nonce(self.nonce_mode.interactive(), b"interactive/", context, rng);
+        let r1 = self.g.exp_secret(&self.g.g(), k.expose());
+        let r2 = self.g.exp_secret(&self.g.h(), k.expose());
+
+        // Only public values are logged; x and k never are
+        debug!("[P] y1: {:?}, y2:{:?}", &self.kp.y1, &self.kp.y2);
+        debug!("[P] r1: {:?}, r2:{:?}", &r1, &r2);
         debug!("[P] END PROVE SYNC A");
-        return (self.r1.clone(), self.r2.clone());
+        Commitment { prover: self, k, r1, r2 }
+    }
+
+    // Produces a non-interactive proof bound to the caller-supplied context
+    pub fn prove<R: RngCore + CryptoRng + ?Sized>(&self, context: &[u8], rng: &mut R) -> ChaumPedersenProof<G> {
+        debug!("[P] PROVE");
+        let k = self.nonce(self.nonce_mode, b"fiat-shamir/", context, rng);
+        let r1 = self.g.exp_secret(&self.g.g(), k.expose());
+        let r2 = self.g.exp_secret(&self.g.h(), k.expose());
+        let c = fiat_shamir_challenge(&self.g, &self.kp.y1, &self.kp.y2, &r1, &r2, context);
+        let xc = Secret::new(self.g.scalar_mul(self.kp.x.expose(), &c));
+        let s = self.g.scalar_sub(k.expose(), xc.expose());
+
+        debug!("[P] END PROVE");
+        ChaumPedersenProof { r1, r2, c, s }
+    }
+}
+
+impl<G: Group> Commitment<'_, G> {
+    // First commitment g^k, sent to the verifier
+    pub fn r1(&self) -> &G::Element {
+        &self.r1
     }
 
-    // Proves part B of the protocol synchronously
-    pub fn prove_sync_b(&mut self, c: &BigInt) -> BigInt {
+    // Second commitment h^k, sent to the verifier
+    pub fn r2(&self) -> &G::Element {
+        &self.r2
+    }
+
+    // Proves part B of the protocol synchronously, consuming the commitment and its nonce
+    pub fn prove_sync_b(self, c: &G::Scalar) -> G::Scalar {
         debug!("[P] PROVE SYNC B");
-        self.c = c.clone();
-        self.s = (self.k.clone() - ((self.kp.x.clone() * self.c.clone()) % self.g.q.clone())).modpow(&BigInt::one(), &self.g.q);
-        debug!("[P] c: {}, s:{}", &self.c, &self.s);
+        let g = &self.prover.g;
+        let xc = Secret::new(g.scalar_mul(self.prover.kp.x.expose(), c));
+        let s = g.scalar_sub(self.k.expose(), xc.expose());
         debug!("[P] END PROVE SYNC B");
-        return self.s.clone();
+        s
     }
 }
 
-// Represents a verifier for the Chaum-Pedersen protocol
-#[derive(Default)]
-pub struct ChaumPedersenVerifier {
-    g : Generator,      // Generator instance
-    y1: BigInt,         // Public key component 1
-    y2: BigInt,         // Public key component 2
-    r1: BigInt,         // First response value
-    r2: BigInt,         // Second response value
-    c: BigInt,          // Challenge value
-    s: BigInt,          // Solution value
+// Challenge state of a single interactive authentication attempt. Only the verifier can
+// create it and checking an answer consumes it, so every challenge is answered at most once.
+#[derive(Debug)]
+pub struct ChaumPedersenChallenge<G: Group = Generator> {
+    r1: G::Element, // First commitment received from the prover
+    r2: G::Element, // Second commitment received from the prover
+    c: G::Scalar,   // Challenge value sent back to the prover
+}
+
+impl<G: Group> ChaumPedersenChallenge<G> {
+    // Challenge value to send back to the prover
+    pub fn c(&self) -> &G::Scalar {
+        &self.c
+    }
 }
 
-impl Debug for ChaumPedersenVerifier {
+// Represents a verifier for the Chaum-Pedersen protocol, holding only the prover's public keys
+pub struct ChaumPedersenVerifier<G: Group = Generator> {
+    g : G,              // Group instance
+    y1: G::Element,     // Public key component 1
+    y2: G::Element,     // Public key component 2
+}
+
+impl<G: Group> Debug for ChaumPedersenVerifier<G> {
     fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
         write!(f, "ChaumPedersenVerifier <-")
     }
 }
 
 impl ChaumPedersenVerifier {
-    // Creates a new instance of ChaumPedersenVerifier
-    pub fn new(y1: BigInt, y2: BigInt) -> ChaumPedersenVerifier {
-        let g = get_default();
-        return ChaumPedersenVerifier {
-            g: g,
-            y1: y1.clone(),
-            y2: y2.clone(),
-            r1: BigInt::zero(),
-            r2: BigInt::zero(),
-            c: BigInt::zero(),
-            s: BigInt::zero(),
-        }
+    // Creates a new instance of ChaumPedersenVerifier over the default group
+    pub fn new(y1: BigInt, y2: BigInt) -> Result<ChaumPedersenVerifier, ElementError> {
+        ChaumPedersenVerifier::with_group(get_default(), y1, y2)
     }
+}
 
-    // Verifies part A of the protocol synchronously
-    pub fn verify_sync_a(&mut self, r1: BigInt, r2: BigInt) -> BigInt {
-        debug!("[V] VERIFY SYNC A");
-        let mut rng = rand::thread_rng();
+impl<G: Group> ChaumPedersenVerifier<G> {
+    // Creates a new instance of ChaumPedersenVerifier for public keys in the given group,
+    // rejecting keys outside its prime-order subgroup
+    pub fn with_group(g: G, y1: G::Element, y2: G::Element) -> Result<ChaumPedersenVerifier<G>, ElementError> {
+        g.check_named("y1", &y1)?;
+        g.check_named("y2", &y2)?;
+        Ok(ChaumPedersenVerifier {
+            g,
+            y1,
+            y2,
+        })
+    }
+
+    // Returns the group used by this verifier
+    pub fn group(&self) -> &G {
+        &self.g
+    }
+
+    // Public key component 1, g^x
+    pub fn y1(&self) -> &G::Element {
+        &self.y1
+    }
 
-        self.c = rng.gen_bigint_range(&BigInt::one(), &(self.g.p.clone()));
-        self.r1 = r1;
-        self.r2 = r2;
-        debug!("[V] y1: {}, y2:{}", &self.y1, &self.y2);
-        debug!("[V] r1: {}, r2:{}", &self.r1, &self.r2);
+    // Public key component 2, h^x
+    pub fn y2(&self) -> &G::Element {
+        &self.y2
+    }
+
+    // Verifies part A of the protocol synchronously, returning the state of this attempt.
+    // Commitments outside the prime-order subgroup are rejected before a challenge is drawn.
+    pub fn verify_sync_a<R: RngCore + CryptoRng + ?Sized>(
+        &self,
+        r1: G::Element,
+        r2: G::Element,
+        rng: &mut R,
+    ) -> Result<ChaumPedersenChallenge<G>, ElementError> {
+        debug!("[V] VERIFY SYNC A");
+        self.g.check_named("r1", &r1)?;
+        self.g.check_named("r2", &r2)?;
+        let c = self.g.random_scalar(rng);
+        debug!("[V] y1: {:?}, y2:{:?}", &self.y1, &self.y2);
+        debug!("[V] r1: {:?}, r2:{:?}", &r1, &r2);
         debug!("[V] END VERIFY SYNC A");
-        return self.c.clone();
+        Ok(ChaumPedersenChallenge { r1, r2, c })
     }
 
-    // Verifies part B of the protocol synchronously
-    pub fn verify_sync_b(&mut self, s: BigInt) -> bool {
+    // Verifies part B of the protocol synchronously, consuming the state of the same attempt
+    pub fn verify_sync_b(&self, challenge: ChaumPedersenChallenge<G>, s: G::Scalar) -> bool {
         debug!("[V] VERIFY SYNC B");
-        self.s = s;
-        let r1_prime: BigInt = (
-            self.g.g.modpow(&self.s, &self.g.p) * 
-            self.y1.modpow(&self.c, &self.g.p)
-        ) % self.g.p.clone();
-        let r2_prime: BigInt = (
-            self.g.h.modpow(&self.s, &self.g.p) * 
-            self.y2.modpow(&self.c, &self.g.p)
-        ) % self.g.p.clone();
-
+        let result = self.check(&challenge.r1, &challenge.r2, &challenge.c, &s);
         debug!("[V] END VERIFY SYNC B");
-        return r1_prime == self.r1 && r2_prime == self.r2;
+        result
+    }
+
+    // Verifies a non-interactive proof against the context it was produced for
+    pub fn verify(&self, proof: &ChaumPedersenProof<G>, context: &[u8]) -> bool {
+        debug!("[V] VERIFY");
+        let c = fiat_shamir_challenge(&self.g, &self.y1, &self.y2, &proof.r1, &proof.r2, context);
+        let members = self.g.check_element(&proof.r1).is_ok() && self.g.check_element(&proof.r2).is_ok();
+        let result = members && c == proof.c && self.check(&proof.r1, &proof.r2, &proof.c, &proof.s);
+        debug!("[V] END VERIFY");
+        result
+    }
+
+    // Checks an interactive transcript (r1, r2, c, s) as verify_sync_b would, for a challenge this
+    // verifier did not draw, such as one of a simulated or captured transcript
+    #[cfg(any(test, feature = "test-utils"))]
+    pub fn accepts(&self, transcript: &ChaumPedersenProof<G>) -> bool {
+        self.g.check_element(&transcript.r1).is_ok()
+            && self.g.check_element(&transcript.r2).is_ok()
+            && self.check(&transcript.r1, &transcript.r2, &transcript.c, &transcript.s)
+    }
+
+    // Checks r1 = g^s * y1^c and r2 = h^s * y2^c, each as one multi-exponentiation
+    fn check(&self, r1: &G::Element, r2: &G::Element, c: &G::Scalar, s: &G::Scalar) -> bool {
+        let exponents = [s.clone(), c.clone()];
+        let r1_prime = self.g.multi_exp(&[self.g.g(), self.y1.clone()], &exponents);
+        let r2_prime = self.g.multi_exp(&[self.g.h(), self.y2.clone()], &exponents);
+
+        &r1_prime == r1 && &r2_prime == r2
+    }
+}
+
+
+// Domain separation tag for the Fiat-Shamir challenge hash of generalized DLEQ proofs
+const DLEQ_FIAT_SHAMIR_DOMAIN: &[u8] = b"zkp_auth/dleq/fiat-shamir/v1";
+
+// Errors raised when a statement over several public values, such as a generalized DLEQ or an
+// OR-composition, or the commitments sent for it are malformed
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum StatementError {
+    EmptyStatement,                                   // The statement has no entries
+    CountMismatch { expected: usize, actual: usize }, // There is not one commitment per entry
+    Element { index: usize, error: ElementError },    // An element of the entry at index is rejected
+}
+
+impl std::fmt::Display for StatementError {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            StatementError::EmptyStatement => write!(f, "statement is empty"),
+            StatementError::CountMismatch { expected, actual } => write!(f, "expected {} commitments, got {}", expected, actual),
+            StatementError::Element { index, error } => write!(f, "entry {}: {}", index, error),
+        }
+    }
+}
+
+impl std::error::Error for StatementError {}
+
+// Checks that every element is a non-trivial group member, naming the offending one
+pub fn check_all<G: Group>(group: &G, name: &'static str, elements: &[G::Element]) -> Result<(), StatementError> {
+    for (index, e) in elements.iter().enumerate() {
+        group.check_named(name, e).map_err(|error| StatementError::Element { index, error })?;
+    }
+    Ok(())
+}
+
+// Non-interactive proof that log_{b_i}(y_i) is the same x for every pair (b_i, y_i) of a statement
+#[derive(Clone, Debug, PartialEq)]
+pub struct DleqProof<G: Group = Generator> {
+    pub r: Vec<G::Element>, // Commitments b_i^k, one per pair
+    pub c: G::Scalar,       // Challenge derived from the transcript hash
+    pub s: G::Scalar,       // Response k - c * x mod q
+}
+
+// Computes the Fiat-Shamir challenge H(domain, group, n, b_1, y_1, ..., b_n, y_n, r_1, ..., r_n, context) mod q
+pub fn dleq_challenge<G: Group>(
+    group: &G,
+    bases: &[G::Element],
+    public: &[G::Element],
+    r: &[G::Element],
+    context: &[u8],
+) -> G::Scalar {
+    let mut hasher = Sha256::new();
+    absorb(&mut hasher, DLEQ_FIAT_SHAMIR_DOMAIN);
+    absorb(&mut hasher, &group.encode_parameters());
+    absorb(&mut hasher, &(bases.len() as u64).to_be_bytes());
+    for (b, y) in bases.iter().zip(public) {
+        absorb(&mut hasher, &group.encode_element(b));
+        absorb(&mut hasher, &group.encode_element(y));
+    }
+    for x in r {
+        absorb(&mut hasher, &group.encode_element(x));
+    }
+    absorb(&mut hasher, context);
+    group.scalar_from_hash(&hasher.finalize())
+}
+
+// Represents a prover of discrete-log equality over any number of bases, binding one secret x
+// to the public values y_i = b_i^x. Chaum-Pedersen is the case of the two bases g and h.
+pub struct DleqProver<G: Group = Generator> {
+    g: G,                    // Group instance
+    x: Secret<G::Scalar>,    // Private key, wiped on drop
+    bases: Vec<G::Element>,  // Bases b_i
+    public: Vec<G::Element>, // Public values b_i^x
+    nonce_mode: NonceMode,   // How nonces are obtained
+}
+
+// Commitment of a single interactive DLEQ attempt, consumed when the challenge is answered
+pub struct DleqCommitment<'a, G: Group = Generator> {
+    prover: &'a DleqProver<G>, // Prover whose key answers the challenge
+    k: Secret<G::Scalar>,      // Random nonce k, wiped on drop
+    r: Vec<G::Element>,        // Commitments b_i^k
+}
+
+impl<G: Group> Debug for DleqCommitment<'_, G> {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(f, "DleqCommitment <-")
+    }
+}
+
+impl<G: Group> Debug for DleqProver<G> {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(f, "DleqProver <-")
+    }
+}
+
+impl<G: Group> DleqProver<G> {
+    // Creates a new instance of DleqProver with a fresh secret over the given bases
+    pub fn with_group<R: RngCore + CryptoRng + ?Sized>(g: G, bases: Vec<G::Element>, rng: &mut R) -> Result<DleqProver<G>, StatementError> {
+        let x = Secret::new(g.random_nonzero_scalar(rng));
+        DleqProver::with_secret(g, x, bases)
+    }
+
+    // Creates a new instance of DleqProver for a known secret x over the given bases, which
+    // must be non-trivial group members, e.g. g, h and per-device or per-service tags
+    pub fn with_secret(g: G, x: Secret<G::Scalar>, bases: Vec<G::Element>) -> Result<DleqProver<G>, StatementError> {
+        if bases.is_empty() {
+            return Err(StatementError::EmptyStatement);
+        }
+        check_all(&g, "base", &bases)?;
+        let public = bases.iter().map(|b| g.exp_secret(b, x.expose())).collect();
+        Ok(DleqProver {
+            g,
+            x,
+            bases,
+            public,
+            nonce_mode: NonceMode::default(),
+        })
+    }
+
+    // Selects how nonces are obtained; see NonceMode
+    pub fn with_nonce_mode(mut self, mode: NonceMode) -> DleqProver<G> {
+        self.nonce_mode = mode;
+        self
+    }
+
+    // Returns the group used by this prover
+    pub fn group(&self) -> &G {
+        &self.g
+    }
+
+    // Bases b_i of the statement
+    pub fn bases(&self) -> &[G::Element] {
+        &self.bases
+    }
+
+    // Public values y_i = b_i^x, in the order of the bases
+    pub fn public(&self) -> &[G::Element] {
+        &self.public
+    }
+
+    // Obtains the nonce for a proof, binding it to every base and public value of the statement
+    fn nonce<R: RngCore + CryptoRng + ?Sized>(&self, mode: NonceMode, kind: &[u8], context: &[u8], rng: &mut R) -> Secret<G::Scalar> {
+        let context = [b"dleq/".as_slice(), kind, context].concat();
+        let public: Vec<&G::Element> = self.bases.iter().chain(&self.public).collect();
+        nonce::nonce(mode, &self.g, &self.x, &public, &context, rng)
+    }
+
+    // Computes the commitments b_i^k
+    fn commitments(&self, k: &Secret<G::Scalar>) -> Vec<G::Element> {
+        self.bases.iter().map(|b| self.g.exp_secret(b, k.expose())).collect()
+    }
+
+    // Proves part A of the protocol synchronously, committing to a fresh nonce.
+    // As for Chaum-Pedersen, the deterministic mode is hedged here.
+    pub fn prove_sync_a<R: RngCore + CryptoRng + ?Sized>(&self, rng: &mut R) -> DleqCommitment<'_, G> {
+        self.commit(&[], rng)
+    }
+
+    // Proves part A of the protocol for a login identified by context, which is bound into the
+    // nonce alongside fresh randomness
+    pub fn prove_sync_a_with_context<R: RngCore + CryptoRng + ?Sized>(&self, context: &[u8], rng: &mut R) -> DleqCommitment<'_, G> {
+        self.commit(context, rng)
+    }
+
+    fn commit<R: RngCore + CryptoRng + ?Sized>(&self, context: &[u8], rng: &mut R) -> DleqCommitment<'_, G> {
+        debug!("[P] DLEQ PROVE SYNC A over {} bases", self.bases.len());
+        let k = self.nonce(self.nonce_mode.interactive(), b"interactive/", context, rng);
+        let r = self.commitments(&k);
+        debug!("[P] END DLEQ PROVE SYNC A");
+        DleqCommitment { prover: self, k, r }
+    }
+
+    // Produces a non-interactive proof bound to the caller-supplied context
+    pub fn prove<R: RngCore + CryptoRng + ?Sized>(&self, context: &[u8], rng: &mut R) -> DleqProof<G> {
+        debug!("[P] DLEQ PROVE over {} bases", self.bases.len());
+        let k = self.nonce(self.nonce_mode, b"fiat-shamir/", context, rng);
+        let r = self.commitments(&k);
+        let c = dleq_challenge(&self.g, &self.bases, &self.public, &r, context);
+        let xc = Secret::new(self.g.scalar_mul(self.x.expose(), &c));
+        let s = self.g.scalar_sub(k.expose(), xc.expose());
+
+        debug!("[P] END DLEQ PROVE");
+        DleqProof { r, c, s }
+    }
+}
+
+impl<G: Group> DleqCommitment<'_, G> {
+    // Commitments b_i^k, sent to the verifier
+    pub fn r(&self) -> &[G::Element] {
+        &self.r
+    }
+
+    // Proves part B of the protocol synchronously, consuming the commitment and its nonce
+    pub fn prove_sync_b(self, c: &G::Scalar) -> G::Scalar {
+        let g = &self.prover.g;
+        let xc = Secret::new(g.scalar_mul(self.prover.x.expose(), c));
+        g.scalar_sub(self.k.expose(), xc.expose())
+    }
+}
+
+// Challenge state of a single interactive DLEQ attempt, consumed when its answer is checked
+#[derive(Debug)]
+pub struct DleqChallenge<G: Group = Generator> {
+    r: Vec<G::Element>, // Commitments received from the prover
+    c: G::Scalar,       // Challenge value sent back to the prover
+}
+
+impl<G: Group> DleqChallenge<G> {
+    // Challenge value to send back to the prover
+    pub fn c(&self) -> &G::Scalar {
+        &self.c
+    }
+}
+
+// Represents a verifier of discrete-log equality over the (base, public value) pairs of a statement
+pub struct DleqVerifier<G: Group = Generator> {
+    g: G,                    // Group instance
+    bases: Vec<G::Element>,  // Bases b_i
+    public: Vec<G::Element>, // Public values y_i
+}
+
+impl<G: Group> Debug for DleqVerifier<G> {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(f, "DleqVerifier <-")
+    }
+}
+
+impl<G: Group> DleqVerifier<G> {
+    // Creates a new instance of DleqVerifier for the given pairs, rejecting empty statements
+    // and bases or public values outside the prime-order subgroup
+    pub fn with_group(g: G, pairs: Vec<(G::Element, G::Element)>) -> Result<DleqVerifier<G>, StatementError> {
+        if pairs.is_empty() {
+            return Err(StatementError::EmptyStatement);
+        }
+        let (bases, public): (Vec<G::Element>, Vec<G::Element>) = pairs.into_iter().unzip();
+        check_all(&g, "base", &bases)?;
+        check_all(&g, "y", &public)?;
+        Ok(DleqVerifier { g, bases, public })
+    }
+
+    // Returns the group used by this verifier
+    pub fn group(&self) -> &G {
+        &self.g
+    }
+
+    // Number of pairs in the statement
+    pub fn len(&self) -> usize {
+        self.bases.len()
+    }
+
+    // Never true, as empty statements are rejected
+    pub fn is_empty(&self) -> bool {
+        self.bases.is_empty()
+    }
+
+    // Checks that there is one commitment per pair and that all are group members
+    fn check_commitments(&self, r: &[G::Element]) -> Result<(), StatementError> {
+        if r.len() != self.bases.len() {
+            return Err(StatementError::CountMismatch { expected: self.bases.len(), actual: r.len() });
+        }
+        check_all(&self.g, "r", r)
+    }
+
+    // Verifies part A of the protocol synchronously, returning the state of this attempt
+    pub fn verify_sync_a<R: RngCore + CryptoRng + ?Sized>(&self, r: Vec<G::Element>, rng: &mut R) -> Result<DleqChallenge<G>, StatementError> {
+        debug!("[V] DLEQ VERIFY SYNC A over {} bases", self.bases.len());
+        self.check_commitments(&r)?;
+        let c = self.g.random_scalar(rng);
+        debug!("[V] END DLEQ VERIFY SYNC A");
+        Ok(DleqChallenge { r, c })
+    }
+
+    // Verifies part B of the protocol synchronously, consuming the state of the same attempt
+    pub fn verify_sync_b(&self, challenge: DleqChallenge<G>, s: G::Scalar) -> bool {
+        self.check(&challenge.r, &challenge.c, &s)
+    }
+
+    // Verifies a non-interactive proof against the context it was produced for
+    pub fn verify(&self, proof: &DleqProof<G>, context: &[u8]) -> bool {
+        debug!("[V] DLEQ VERIFY over {} bases", self.bases.len());
+        let result = self.check_commitments(&proof.r).is_ok()
+            && dleq_challenge(&self.g, &self.bases, &self.public, &proof.r, context) == proof.c
+            && self.check(&proof.r, &proof.c, &proof.s);
+        debug!("[V] END DLEQ VERIFY");
+        result
+    }
+
+    // Checks r_i = b_i^s * y_i^c for every pair, each as one multi-exponentiation
+    fn check(&self, r: &[G::Element], c: &G::Scalar, s: &G::Scalar) -> bool {
+        let exponents = [s.clone(), c.clone()];
+        r.len() == self.bases.len()
+            && self.bases.iter().zip(&self.public).zip(r).all(|((b, y), r)| {
+                &self.g.multi_exp(&[b.clone(), y.clone()], &exponents) == r
+            })
     }
 }
 
 
 #[cfg(test)]
 mod test{
-    use crate::zkrypto::generator::get_default;
+    use crate::zkrypto::generator::{get_debug, get_default};
     use crate::zkrypto::chaum_pedersen::{KeyPair, ChaumPedersenProver, ChaumPedersenVerifier};
+    use crate::zkrypto::chaum_pedersen::{StatementError, DleqProver, DleqVerifier};
+    use crate::zkrypto::group::{ElementError, Group, MembershipError};
+    use crate::zkrypto::ristretto::RistrettoGroup;
+    use crate::zkrypto::nonce::NonceMode;
+    use crate::zkrypto::scalar::{Secret, Zq};
+    use num_bigint::BigInt;
+    use rand::rngs::StdRng;
+    use rand::SeedableRng;
 
     #[test]
     fn test_keypair_creation() {
@@ -166,10 +651,10 @@ mod test{
         let h = get_default().h;
         let p = get_default().p;
 
-        let keypair = KeyPair::new(&g, &h, &p);
+        let keypair: KeyPair = KeyPair::new(&get_default(), &mut rand::thread_rng());
 
-        let y1_expected = g.modpow(&keypair.x, &p);
-        let y2_expected = h.modpow(&keypair.x, &p);
+        let y1_expected = g.modpow(keypair.x.expose().value(), &p);
+        let y2_expected = h.modpow(keypair.x.expose().value(), &p);
 
         assert_eq!(keypair.y1, y1_expected);
         assert_eq!(keypair.y2, y2_expected);
@@ -177,15 +662,336 @@ mod test{
 
     #[test]
     fn test_chaum_pedersen_protocol() {
-        let mut prover = ChaumPedersenProver::new();
-        let mut verifier = ChaumPedersenVerifier::new(prover.kp.y1.clone(), prover.kp.y2.clone());
+        let mut rng = rand::thread_rng();
+        let prover = ChaumPedersenProver::new(&mut rng);
+        let verifier = ChaumPedersenVerifier::new(prover.kp.y1.clone(), prover.kp.y2.clone()).unwrap();
 
         // Prover's side
-        let (r1, r2) = prover.prove_sync_a();
-        let c = verifier.verify_sync_a(r1.clone(), r2.clone());
-        let s = prover.prove_sync_b(&c);
-        let result = verifier.verify_sync_b(s);
+        let commitment = prover.prove_sync_a(&mut rng);
+        let challenge = verifier.verify_sync_a(commitment.r1().clone(), commitment.r2().clone(), &mut rng).unwrap();
+        let s = commitment.prove_sync_b(challenge.c());
+        let result = verifier.verify_sync_b(challenge, s);
+
+        assert!(result);
+    }
+
+    #[test]
+    fn test_concurrent_attempts() {
+        let mut rng = rand::thread_rng();
+        // Two devices holding the same key authenticate against one verifier at the same time
+        let device_a = ChaumPedersenProver::new(&mut rng);
+        let mut device_b = ChaumPedersenProver::new(&mut rng);
+        device_b.kp = KeyPair { x: device_a.kp.x.clone(), y1: device_a.kp.y1.clone(), y2: device_a.kp.y2.clone() };
+        let verifier = ChaumPedersenVerifier::new(device_a.kp.y1.clone(), device_a.kp.y2.clone()).unwrap();
+
+        let commitment_a = device_a.prove_sync_a(&mut rng);
+        let challenge_a = verifier.verify_sync_a(commitment_a.r1().clone(), commitment_a.r2().clone(), &mut rng).unwrap();
+        let commitment_b = device_b.prove_sync_a(&mut rng);
+        let challenge_b = verifier.verify_sync_a(commitment_b.r1().clone(), commitment_b.r2().clone(), &mut rng).unwrap();
+        let commitment_c = device_b.prove_sync_a(&mut rng);
+        let challenge_c = verifier.verify_sync_a(commitment_c.r1().clone(), commitment_c.r2().clone(), &mut rng).unwrap();
+
+        let s_a = commitment_a.prove_sync_b(challenge_a.c());
+        let s_b = commitment_b.prove_sync_b(challenge_b.c());
+        assert!(verifier.verify_sync_b(challenge_a, s_a.clone()));
+        assert!(verifier.verify_sync_b(challenge_b, s_b));
+        // The answer to one attempt does not satisfy another
+        assert!(!verifier.verify_sync_b(challenge_c, s_a));
+    }
+
+    #[test]
+    fn test_fiat_shamir_protocol() {
+        let mut rng = rand::thread_rng();
+        let prover = ChaumPedersenProver::new(&mut rng);
+        let verifier = ChaumPedersenVerifier::new(prover.kp.y1.clone(), prover.kp.y2.clone()).unwrap();
+
+        let proof = prover.prove(b"login", &mut rng);
+        assert!(verifier.verify(&proof, b"login"));
+        assert!(!verifier.verify(&proof, b"other context"));
+    }
+
+    #[test]
+    fn test_fiat_shamir_rejects_tampered_proof() {
+        let mut rng = rand::thread_rng();
+        let prover = ChaumPedersenProver::new(&mut rng);
+        let verifier = ChaumPedersenVerifier::new(prover.kp.y1.clone(), prover.kp.y2.clone()).unwrap();
+        let proof = prover.prove(b"login", &mut rng);
+
+        let mut bad_s = proof.clone();
+        bad_s.s = Zq::new(&(bad_s.s.value() + 1u32), &verifier.group().q);
+        assert!(!verifier.verify(&bad_s, b"login"));
+
+        let mut bad_c = proof.clone();
+        bad_c.c = Zq::new(&(bad_c.c.value() + 1u32), &verifier.group().q);
+        assert!(!verifier.verify(&bad_c, b"login"));
+
+        let mut bad_r = proof;
+        bad_r.r1 = (&bad_r.r1 * &verifier.group().g) % &verifier.group().p;
+        assert!(!verifier.verify(&bad_r, b"login"));
+
+        let other = ChaumPedersenProver::new(&mut rng);
+        assert!(!verifier.verify(&other.prove(b"login", &mut rng), b"login"));
+    }
+
+    #[test]
+    fn test_protocol_over_other_group() {
+        let mut rng = rand::thread_rng();
+        let prover = ChaumPedersenProver::with_group(get_debug(), &mut rng);
+        let verifier = ChaumPedersenVerifier::with_group(get_debug(), prover.kp.y1.clone(), prover.kp.y2.clone()).unwrap();
+
+        let commitment = prover.prove_sync_a(&mut rng);
+        let challenge = verifier.verify_sync_a(commitment.r1().clone(), commitment.r2().clone(), &mut rng).unwrap();
+        let s = commitment.prove_sync_b(challenge.c());
+        assert!(verifier.verify_sync_b(challenge, s));
+
+        let proof = prover.prove(b"debug", &mut rng);
+        assert!(verifier.verify(&proof, b"debug"));
+    }
+
+    #[test]
+    fn test_rejects_non_members() {
+        let mut rng = rand::thread_rng();
+        let error = |name, error| Err(ElementError { name, error });
+        let prover = ChaumPedersenProver::with_group(get_debug(), &mut rng);
+        let (y1, y2) = (prover.kp.y1.clone(), prover.kp.y2.clone());
+
+        // 0, 1 and p are out of range; p - 1 has order 2 and 2 generates all of Z_367^*
+        for (bad, e) in [
+            (0, MembershipError::OutOfRange),
+            (1, MembershipError::OutOfRange),
+            (367, MembershipError::OutOfRange),
+            (366, MembershipError::NotInSubgroup),
+            (2, MembershipError::NotInSubgroup),
+        ] {
+            let bad = BigInt::from(bad);
+            assert_eq!(
+                ChaumPedersenVerifier::with_group(get_debug(), bad.clone(), y2.clone()).map(|_| ()),
+                error("y1", e.clone())
+            );
+            assert_eq!(
+                ChaumPedersenVerifier::with_group(get_debug(), y1.clone(), bad.clone()).map(|_| ()),
+                error("y2", e.clone())
+            );
+
+            let verifier = ChaumPedersenVerifier::with_group(get_debug(), y1.clone(), y2.clone()).unwrap();
+            assert_eq!(verifier.verify_sync_a(bad.clone(), y2.clone(), &mut rng).map(|_| ()), error("r1", e.clone()));
+            assert_eq!(verifier.verify_sync_a(y1.clone(), bad.clone(), &mut rng).map(|_| ()), error("r2", e));
+        }
+    }
+
+    #[test]
+    fn test_verify_rejects_non_member_commitments() {
+        let mut rng = rand::thread_rng();
+        let prover = ChaumPedersenProver::with_group(get_debug(), &mut rng);
+        let verifier = ChaumPedersenVerifier::with_group(get_debug(), prover.kp.y1.clone(), prover.kp.y2.clone()).unwrap();
+        let mut proof = prover.prove(b"login", &mut rng);
+        assert!(verifier.verify(&proof, b"login"));
+        proof.r1 = BigInt::from(366);
+        assert!(!verifier.verify(&proof, b"login"));
+    }
+
+    // Pearson's chi-squared statistic of observed counts against the uniform distribution
+    fn chi_squared(counts: &[u64]) -> f64 {
+        let expected = counts.iter().sum::<u64>() as f64 / counts.len() as f64;
+        counts.iter().map(|&n| (n as f64 - expected).powi(2) / expected).sum()
+    }
+
+    // Critical value of chi-squared with 60 degrees of freedom at p = 1e-6
+    const CHI_SQUARED_60: f64 = 130.0;
+
+    #[test]
+    fn test_responses_are_uniform() {
+        // Seeded, so the statistical check gives the same result on every run
+        let mut rng = StdRng::seed_from_u64(11);
+        // q = 61, so every residue can be counted; 300 samples expected per bucket
+        let prover = ChaumPedersenProver::with_group(get_debug(), &mut rng);
+        let verifier = ChaumPedersenVerifier::with_group(get_debug(), prover.kp.y1.clone(), prover.kp.y2.clone()).unwrap();
+        let q = verifier.group().q.clone();
+        let bucket = |x: &Zq| usize::try_from(x.value()).unwrap();
+
+        let mut challenges = [0u64; 61];
+        let mut responses = [0u64; 61];
+        let mut fs_responses = [0u64; 61];
+        for i in 0u32..61 * 300 {
+            let commitment = prover.prove_sync_a(&mut rng);
+            let challenge = verifier.verify_sync_a(commitment.r1().clone(), commitment.r2().clone(), &mut rng).unwrap();
+            challenges[bucket(challenge.c())] += 1;
+            let s = commitment.prove_sync_b(challenge.c());
+            assert!(s.value() < &q);
+            responses[bucket(&s)] += 1;
+            // In such a small group the challenge is only fresh if the context changes
+            fs_responses[bucket(&prover.prove(&i.to_be_bytes(), &mut rng).s)] += 1;
+        }
+        assert!(chi_squared(&challenges) < CHI_SQUARED_60, "challenges not uniform: {:?}", challenges);
+        assert!(chi_squared(&responses) < CHI_SQUARED_60, "responses not uniform: {:?}", responses);
+        assert!(chi_squared(&fs_responses) < CHI_SQUARED_60, "proofs not uniform: {:?}", fs_responses);
+    }
+
+    #[test]
+    fn test_chi_squared_detects_bias() {
+        // Sampling the response from [1, q) would never produce 0
+        let mut counts = [300u64; 61];
+        counts[0] = 0;
+        counts[1] = 600;
+        assert!(chi_squared(&counts) > CHI_SQUARED_60);
+    }
+
+    #[test]
+    fn test_secret_and_nonces_in_zq() {
+        let mut rng = rand::thread_rng();
+        let group = get_debug();
+        for _ in 0..1000 {
+            let keypair: KeyPair = KeyPair::new(&group, &mut rng);
+            assert!(!keypair.x.expose().is_zero() && keypair.x.expose().value() < &group.q);
+        }
+        let prover = ChaumPedersenProver::with_group(get_debug(), &mut rng);
+        for _ in 0..1000 {
+            assert_ne!(prover.prove_sync_a(&mut rng).r1(), &BigInt::from(1), "nonce must not be zero");
+        }
+    }
+
+    #[test]
+    fn test_deterministic_nonces() {
+        let mut rng = rand::thread_rng();
+        let prover = ChaumPedersenProver::new(&mut rng).with_nonce_mode(NonceMode::Deterministic);
+        let verifier = ChaumPedersenVerifier::new(prover.kp.y1.clone(), prover.kp.y2.clone()).unwrap();
+
+        // The same context yields the same proof, a different one a different nonce
+        let proof = prover.prove(b"login 1", &mut rng);
+        assert!(verifier.verify(&proof, b"login 1"));
+        assert_eq!(prover.prove(b"login 1", &mut rng), proof);
+        assert_ne!(prover.prove(b"login 2", &mut rng).r1, proof.r1);
+
+        // Interactive commitments are hedged, so they do not repeat even when the context does
+        let a = prover.prove_sync_a_with_context(b"session 1", &mut rng);
+        let b = prover.prove_sync_a_with_context(b"session 1", &mut rng);
+        assert_ne!(a.r1(), b.r1());
+        assert_ne!(a.r1(), &proof.r1);
+
+        // Nor without a context
+        let commitments: std::collections::HashSet<_> = (0..10).map(|_| prover.prove_sync_a(&mut rng).r1().clone()).collect();
+        assert!(commitments.len() > 1);
+
+        let challenge = verifier.verify_sync_a(a.r1().clone(), a.r2().clone(), &mut rng).unwrap();
+        let s = a.prove_sync_b(challenge.c());
+        assert!(verifier.verify_sync_b(challenge, s));
+    }
 
-        assert_eq!(result, true);
+    #[test]
+    fn test_hedged_nonces() {
+        let mut rng = rand::thread_rng();
+        let prover = ChaumPedersenProver::new(&mut rng).with_nonce_mode(NonceMode::Hedged);
+        let verifier = ChaumPedersenVerifier::new(prover.kp.y1.clone(), prover.kp.y2.clone()).unwrap();
+        let proof = prover.prove(b"login", &mut rng);
+        assert!(verifier.verify(&proof, b"login"));
+        assert_ne!(prover.prove(b"login", &mut rng).r1, proof.r1);
+    }
+
+    #[test]
+    fn test_seeded_rng_reproduces_transcripts() {
+        let run = |seed: u64| {
+            let mut rng = StdRng::seed_from_u64(seed);
+            let prover = ChaumPedersenProver::with_group(get_debug(), &mut rng);
+            let commitment = prover.prove_sync_a(&mut rng);
+            let (r1, r2) = (commitment.r1().clone(), commitment.r2().clone());
+            let proof = prover.prove(b"login", &mut rng);
+            (prover.kp.y1.clone(), r1, r2, proof.r1, proof.s)
+        };
+        assert_eq!(run(1), run(1));
+        assert_ne!(run(1), run(2));
+    }
+
+    // Extra bases standing in for per-device and per-service tags
+    fn tags<G: Group>(g: &G, n: usize) -> Vec<G::Element> {
+        let mut rng = rand::thread_rng();
+        (0..n).map(|_| g.exp(&g.g(), &g.random_nonzero_scalar(&mut rng))).collect()
+    }
+
+    #[test]
+    fn test_dleq_protocol() {
+        let mut rng = rand::thread_rng();
+        let g = get_default();
+        let bases = [vec![g.g(), g.h()], tags(&g, 3)].concat();
+        let prover = DleqProver::with_group(g.clone(), bases.clone(), &mut rng).unwrap();
+        let pairs: Vec<_> = bases.into_iter().zip(prover.public().iter().cloned()).collect();
+        let verifier = DleqVerifier::with_group(g, pairs).unwrap();
+        assert_eq!(verifier.len(), 5);
+
+        let commitment = prover.prove_sync_a(&mut rng);
+        let challenge = verifier.verify_sync_a(commitment.r().to_vec(), &mut rng).unwrap();
+        let s = commitment.prove_sync_b(challenge.c());
+        assert!(verifier.verify_sync_b(challenge, s));
+
+        let proof = prover.prove(b"login", &mut rng);
+        assert!(verifier.verify(&proof, b"login"));
+        assert!(!verifier.verify(&proof, b"other context"));
+        let mut shuffled = proof.clone();
+        shuffled.r.swap(1, 2);
+        assert!(!verifier.verify(&shuffled, b"login"));
+        let mut truncated = proof;
+        truncated.r.pop();
+        assert!(!verifier.verify(&truncated, b"login"));
+    }
+
+    #[test]
+    fn test_dleq_generalizes_chaum_pedersen() {
+        // Over the bases g and h the statement is the Chaum-Pedersen one, in every group
+        let mut rng = rand::thread_rng();
+        let cp = ChaumPedersenProver::with_group(RistrettoGroup::new(), &mut rng);
+        let g = cp.group().clone();
+        let prover = DleqProver::with_secret(g.clone(), cp.kp.secret().clone(), vec![g.g(), g.h()]).unwrap();
+        assert_eq!(prover.public(), &[cp.kp.y1, cp.kp.y2]);
+        let verifier = DleqVerifier::with_group(g.clone(), vec![(g.g(), cp.kp.y1), (g.h(), cp.kp.y2)]).unwrap();
+        assert!(verifier.verify(&prover.prove(b"login", &mut rng), b"login"));
+
+        // A single pair is a Schnorr statement
+        let one = DleqProver::with_secret(g.clone(), cp.kp.secret().clone(), vec![g.g()]).unwrap();
+        let verifier = DleqVerifier::with_group(g.clone(), vec![(g.g(), cp.kp.y1)]).unwrap();
+        assert!(verifier.verify(&one.prove(b"login", &mut rng), b"login"));
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn test_dleq_rejects_unequal_logs() {
+        let mut rng = rand::thread_rng();
+        let g = get_default();
+        let bases = tags(&g, 3);
+        let prover = DleqProver::with_group(g.clone(), bases.clone(), &mut rng).unwrap();
+        let other = DleqProver::with_group(g.clone(), bases.clone(), &mut rng).unwrap();
+
+        // The last value is bound to a different secret
+        let mut public = prover.public().to_vec();
+        public[2] = other.public()[2].clone();
+        let verifier = DleqVerifier::with_group(g.clone(), bases.iter().cloned().zip(public).collect()).unwrap();
+        assert!(!verifier.verify(&prover.prove(b"login", &mut rng), b"login"));
+        assert!(!verifier.verify(&other.prove(b"login", &mut rng), b"login"));
+        let commitment = prover.prove_sync_a(&mut rng);
+        let challenge = verifier.verify_sync_a(commitment.r().to_vec(), &mut rng).unwrap();
+        let s = commitment.prove_sync_b(challenge.c());
+        assert!(!verifier.verify_sync_b(challenge, s));
+    }
+
+    #[test]
+    fn test_dleq_rejects_malformed_statements() {
+        let mut rng = rand::thread_rng();
+        let g = get_debug();
+        let x = || Secret::new(g.random_nonzero_scalar(&mut rand::thread_rng()));
+        assert_eq!(DleqProver::with_secret(g.clone(), x(), vec![]).map(|_| ()), Err(StatementError::EmptyStatement));
+        assert_eq!(DleqVerifier::with_group(g.clone(), vec![]).map(|_| ()), Err(StatementError::EmptyStatement));
+
+        // 366 = p - 1 has order 2 in Z_367^*
+        let bad = BigInt::from(366);
+        let error = |index, name| Err(StatementError::Element { index, error: ElementError { name, error: MembershipError::NotInSubgroup } });
+        assert_eq!(DleqProver::with_secret(g.clone(), x(), vec![g.g(), bad.clone()]).map(|_| ()), error(1, "base"));
+        let prover = DleqProver::with_secret(g.clone(), x(), vec![g.g(), g.h()]).unwrap();
+        let (y1, y2) = (prover.public()[0].clone(), prover.public()[1].clone());
+        assert_eq!(DleqVerifier::with_group(g.clone(), vec![(g.g(), y1.clone()), (g.h(), bad.clone())]).map(|_| ()), error(1, "y"));
+
+        let verifier = DleqVerifier::with_group(g.clone(), vec![(g.g(), y1), (g.h(), y2)]).unwrap();
+        let r = prover.prove_sync_a(&mut rng).r().to_vec();
+        assert_eq!(verifier.verify_sync_a(vec![bad, r[1].clone()], &mut rng).map(|_| ()), error(0, "r"));
+        assert_eq!(
+            verifier.verify_sync_a(r[..1].to_vec(), &mut rng).map(|_| ()),
+            Err(StatementError::CountMismatch { expected: 2, actual: 1 })
+        );
+    }
+}
@@ -0,0 +1,840 @@
+use log::{debug};
+use rand::{CryptoRng, RngCore};
+use sha2::{Digest, Sha256};
+use std::fmt::Debug;
+
+use crate::zkrypto::chaum_pedersen::{absorb, check_all, ChaumPedersenProof, KeyPair, StatementError};
+use crate::zkrypto::generator::Generator;
+use crate::zkrypto::group::Group;
+use crate::zkrypto::nonce::{self, NonceMode};
+use crate::zkrypto::scalar::Secret;
+
+// Domain separation tag for the Fiat-Shamir challenge hash of OR proofs
+const OR_FIAT_SHAMIR_DOMAIN: &[u8] = b"zkp_auth/or/fiat-shamir/v1";
+
+// Non-interactive proof of knowledge of the secret of one of N Chaum-Pedersen key pairs
+// (y1_i, y2_i), without revealing which. Branch i is a Chaum-Pedersen transcript for key
+// pair i, and the branch challenges add up to the Fiat-Shamir challenge of all commitments.
+#[derive(Clone, Debug, PartialEq)]
+pub struct OrProof<G: Group = Generator> {
+    pub branches: Vec<ChaumPedersenProof<G>>, // One transcript per key pair, in statement order
+}
+
+// Computes the Fiat-Shamir challenge H(domain, group, n, y1_i, y2_i, ..., r1_i, r2_i, ..., context) mod q
+pub fn or_challenge<G: Group>(
+    group: &G,
+    statement: &[(G::Element, G::Element)],
+    r1: &[G::Element],
+    r2: &[G::Element],
+    context: &[u8],
+) -> G::Scalar {
+    let mut hasher = Sha256::new();
+    absorb(&mut hasher, OR_FIAT_SHAMIR_DOMAIN);
+    absorb(&mut hasher, &group.encode_parameters());
+    absorb(&mut hasher, &(statement.len() as u64).to_be_bytes());
+    for (y1, y2) in statement {
+        absorb(&mut hasher, &group.encode_element(y1));
+        absorb(&mut hasher, &group.encode_element(y2));
+    }
+    for (r1, r2) in r1.iter().zip(r2) {
+        absorb(&mut hasher, &group.encode_element(r1));
+        absorb(&mut hasher, &group.encode_element(r2));
+    }
+    absorb(&mut hasher, context);
+    group.scalar_from_hash(&hasher.finalize())
+}
+
+// Checks the Chaum-Pedersen equations r1 = g^s * y1^c and r2 = h^s * y2^c of one branch
+fn check_branch<G: Group>(g: &G, (y1, y2): &(G::Element, G::Element), r1: &G::Element, r2: &G::Element, c: &G::Scalar, s: &G::Scalar) -> bool {
+    let exponents = [s.clone(), c.clone()];
+    &g.multi_exp(&[g.g(), y1.clone()], &exponents) == r1 && &g.multi_exp(&[g.h(), y2.clone()], &exponents) == r2
+}
+
+// Represents a prover of the CDS OR-composition of Chaum-Pedersen statements: it knows the
+// secret of one key pair of the statement and simulates transcripts for all the others
+pub struct OrProver<G: Group = Generator> {
+    g: G,                                     // Group instance
+    kp: KeyPair<G>,                           // Key pair whose secret is known
+    statement: Vec<(G::Element, G::Element)>, // Public key pairs (y1_i, y2_i) of all members
+    index: usize,                             // Position of kp in the statement, never revealed
+}
+
+// Commitments of a single interactive attempt, holding the nonce of the real branch and the
+// challenges and responses chosen for the simulated ones. Answering consumes it.
+pub struct OrCommitment<'a, G: Group = Generator> {
+    prover: &'a OrProver<G>, // Prover whose key answers the real branch
+    k: Secret<G::Scalar>,    // Nonce of the real branch, wiped on drop
+    r1: Vec<G::Element>,     // First commitments, one per branch
+    r2: Vec<G::Element>,     // Second commitments, one per branch
+    c: Vec<G::Scalar>,       // Challenges of the simulated branches; the real entry is unused
+    s: Vec<G::Scalar>,       // Responses of the simulated branches; the real entry is unused
+}
+
+impl<G: Group> Debug for OrCommitment<'_, G> {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(f, "OrCommitment <-")
+    }
+}
+
+impl<G: Group> Debug for OrProver<G> {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(f, "OrProver <-")
+    }
+}
+
+impl<G: Group> OrProver<G> {
+    // Creates a new instance of OrProver for a key pair and the public key pairs of all members,
+    // or None when the key pair is not one of them
+    pub fn with_keypair(g: G, kp: KeyPair<G>, statement: Vec<(G::Element, G::Element)>) -> Option<OrProver<G>> {
+        let index = statement.iter().position(|(y1, y2)| y1 == &kp.y1 && y2 == &kp.y2)?;
+        Some(OrProver { g, kp, statement, index })
+    }
+
+    // Creates a new instance of OrProver for a known secret x, or None when its key pair is not a member
+    pub fn with_secret(g: G, x: Secret<G::Scalar>, statement: Vec<(G::Element, G::Element)>) -> Option<OrProver<G>> {
+        let kp = KeyPair::from_secret(&g, x);
+        OrProver::with_keypair(g, kp, statement)
+    }
+
+    // Returns the group used by this prover
+    pub fn group(&self) -> &G {
+        &self.g
+    }
+
+    // Public key pairs of all members, in the order of the branches
+    pub fn statement(&self) -> &[(G::Element, G::Element)] {
+        &self.statement
+    }
+
+    // Proves part A of the protocol synchronously. Nonces, simulated challenges and simulated
+    // responses are all drawn from rng, as a deterministic nonce would need every one of them.
+    pub fn prove_sync_a<R: RngCore + CryptoRng + ?Sized>(&self, rng: &mut R) -> OrCommitment<'_, G> {
+        debug!("[P] OR PROVE SYNC A over {} key pairs", self.statement.len());
+        let g = &self.g;
+        let k = Secret::new(g.random_nonzero_scalar(rng));
+        let n = self.statement.len();
+        let (mut r1, mut r2, mut c, mut s) = (Vec::with_capacity(n), Vec::with_capacity(n), Vec::with_capacity(n), Vec::with_capacity(n));
+        for (i, (y1, y2)) in self.statement.iter().enumerate() {
+            // A simulated branch picks its challenge and response first and solves for the
+            // commitments g^s * y1^c and h^s * y2^c. The real branch commits to g^k * y1^0 and
+            // h^k * y2^0, so every branch costs the same secret-exponent operations. Like a real
+            // commitment with k != 0, a simulated one is redrawn if it is the identity.
+            let (r1i, r2i, ci, si) = loop {
+                let (ci, si) = (g.random_scalar(rng), g.random_scalar(rng));
+                let (a, b) = match i == self.index {
+                    true => (k.expose(), &G::Scalar::default()),
+                    false => (&si, &ci),
+                };
+                let r1i = g.mul(&g.exp_secret(&g.g(), a), &g.exp_secret(y1, b));
+                let r2i = g.mul(&g.exp_secret(&g.h(), a), &g.exp_secret(y2, b));
+                if r1i != g.identity() {
+                    break (r1i, r2i, ci, si);
+                }
+            };
+            r1.push(r1i);
+            r2.push(r2i);
+            c.push(ci);
+            s.push(si);
+        }
+        debug!("[P] END OR PROVE SYNC A");
+        OrCommitment { prover: self, k, r1, r2, c, s }
+    }
+
+    // Produces a non-interactive proof bound to the caller-supplied context
+    pub fn prove<R: RngCore + CryptoRng + ?Sized>(&self, context: &[u8], rng: &mut R) -> OrProof<G> {
+        debug!("[P] OR PROVE");
+        let commitment = self.prove_sync_a(rng);
+        let c = or_challenge(&self.g, &self.statement, &commitment.r1, &commitment.r2, context);
+        let (r1, r2) = (commitment.r1.clone(), commitment.r2.clone());
+        let branches = r1
+            .into_iter()
+            .zip(r2)
+            .zip(commitment.prove_sync_b(&c))
+            .map(|((r1, r2), (c, s))| ChaumPedersenProof { r1, r2, c, s })
+            .collect();
+        debug!("[P] END OR PROVE");
+        OrProof { branches }
+    }
+}
+
+impl<G: Group> OrCommitment<'_, G> {
+    // First commitments, sent to the verifier
+    pub fn r1(&self) -> &[G::Element] {
+        &self.r1
+    }
+
+    // Second commitments, sent to the verifier
+    pub fn r2(&self) -> &[G::Element] {
+        &self.r2
+    }
+
+    // Proves part B of the protocol synchronously, consuming the commitment and its nonce.
+    // The real branch takes c minus the simulated challenges, so all of them add up to c.
+    // Returns the challenge and response (c_i, s_i) of every branch.
+    pub fn prove_sync_b(self, c: &G::Scalar) -> Vec<(G::Scalar, G::Scalar)> {
+        let OrCommitment { prover, k, c: mut challenges, s: mut responses, .. } = self;
+        let g = &prover.g;
+        let j = prover.index;
+        let simulated = challenges
+            .iter()
+            .enumerate()
+            .filter(|&(i, _)| i != j)
+            .fold(G::Scalar::default(), |acc, (_, ci)| g.scalar_add(&acc, ci));
+        challenges[j] = g.scalar_sub(c, &simulated);
+        let xc = Secret::new(g.scalar_mul(prover.kp.secret().expose(), &challenges[j]));
+        responses[j] = g.scalar_sub(k.expose(), xc.expose());
+        challenges.into_iter().zip(responses).collect()
+    }
+}
+
+// Challenge state of a single interactive OR attempt, consumed when its answer is checked
+#[derive(Debug)]
+pub struct OrChallenge<G: Group = Generator> {
+    r1: Vec<G::Element>, // First commitments received from the prover
+    r2: Vec<G::Element>, // Second commitments received from the prover
+    c: G::Scalar,        // Challenge value sent back to the prover
+}
+
+impl<G: Group> OrChallenge<G> {
+    // Challenge value to send back to the prover
+    pub fn c(&self) -> &G::Scalar {
+        &self.c
+    }
+}
+
+// Represents a verifier of the OR-composition, holding the public key pairs of all members
+pub struct OrVerifier<G: Group = Generator> {
+    g: G,                                     // Group instance
+    statement: Vec<(G::Element, G::Element)>, // Public key pairs (y1_i, y2_i)
+}
+
+impl<G: Group> Debug for OrVerifier<G> {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(f, "OrVerifier <-")
+    }
+}
+
+impl<G: Group> OrVerifier<G> {
+    // Creates a new instance of OrVerifier for the given key pairs, rejecting empty statements
+    // and keys outside the prime-order subgroup
+    pub fn with_group(g: G, statement: Vec<(G::Element, G::Element)>) -> Result<OrVerifier<G>, StatementError> {
+        if statement.is_empty() {
+            return Err(StatementError::EmptyStatement);
+        }
+        for (index, (y1, y2)) in statement.iter().enumerate() {
+            g.check_named("y1", y1).and_then(|_| g.check_named("y2", y2)).map_err(|error| StatementError::Element { index, error })?;
+        }
+        Ok(OrVerifier { g, statement })
+    }
+
+    // Returns the group used by this verifier
+    pub fn group(&self) -> &G {
+        &self.g
+    }
+
+    // Public key pairs of all members, in the order of the branches
+    pub fn statement(&self) -> &[(G::Element, G::Element)] {
+        &self.statement
+    }
+
+    // Checks that there is one pair of commitments per key pair and that all are group members
+    fn check_commitments(&self, r1: &[G::Element], r2: &[G::Element]) -> Result<(), StatementError> {
+        for r in [r1, r2] {
+            if r.len() != self.statement.len() {
+                return Err(StatementError::CountMismatch { expected: self.statement.len(), actual: r.len() });
+            }
+        }
+        check_all(&self.g, "r1", r1)?;
+        check_all(&self.g, "r2", r2)
+    }
+
+    // Checks that the branch challenges add up to c and that every branch passes
+    fn check(&self, r1: &[G::Element], r2: &[G::Element], c: &G::Scalar, branches: &[(&G::Scalar, &G::Scalar)]) -> bool {
+        let g = &self.g;
+        let sum = branches.iter().fold(G::Scalar::default(), |acc, (ci, _)| g.scalar_add(&acc, ci));
+        branches.len() == self.statement.len()
+            && &sum == c
+            && self.statement.iter().zip(r1.iter().zip(r2)).zip(branches).all(|((y, (r1, r2)), (ci, si))| check_branch(g, y, r1, r2, ci, si))
+    }
+
+    // Verifies part A of the protocol synchronously, returning the state of this attempt
+    pub fn verify_sync_a<R: RngCore + CryptoRng + ?Sized>(
+        &self,
+        r1: Vec<G::Element>,
+        r2: Vec<G::Element>,
+        rng: &mut R,
+    ) -> Result<OrChallenge<G>, StatementError> {
+        debug!("[V] OR VERIFY SYNC A over {} key pairs", self.statement.len());
+        self.check_commitments(&r1, &r2)?;
+        let c = self.g.random_scalar(rng);
+        debug!("[V] END OR VERIFY SYNC A");
+        Ok(OrChallenge { r1, r2, c })
+    }
+
+    // Verifies part B of the protocol synchronously, given the (c_i, s_i) of every branch
+    pub fn verify_sync_b(&self, challenge: OrChallenge<G>, answer: &[(G::Scalar, G::Scalar)]) -> bool {
+        let branches: Vec<_> = answer.iter().map(|(c, s)| (c, s)).collect();
+        self.check(&challenge.r1, &challenge.r2, &challenge.c, &branches)
+    }
+
+    // Verifies a non-interactive proof against the context it was produced for
+    pub fn verify(&self, proof: &OrProof<G>, context: &[u8]) -> bool {
+        debug!("[V] OR VERIFY over {} key pairs", self.statement.len());
+        let r1: Vec<G::Element> = proof.branches.iter().map(|b| b.r1.clone()).collect();
+        let r2: Vec<G::Element> = proof.branches.iter().map(|b| b.r2.clone()).collect();
+        let branches: Vec<_> = proof.branches.iter().map(|b| (&b.c, &b.s)).collect();
+        let result = self.check_commitments(&r1, &r2).is_ok()
+            && self.check(&r1, &r2, &or_challenge(&self.g, &self.statement, &r1, &r2, context), &branches);
+        debug!("[V] END OR VERIFY");
+        result
+    }
+}
+
+// Domain separation tag for the Fiat-Shamir challenge hash of AND proofs
+const AND_FIAT_SHAMIR_DOMAIN: &[u8] = b"zkp_auth/and/fiat-shamir/v1";
+
+// Proof system each factor of an AND statement is proven with
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum FactorKind {
+    ChaumPedersen, // Two public keys y1 = g^x and y2 = h^x
+    Schnorr,       // One public key y = g^x
+}
+
+// One factor of an AND statement, such as a device key or a recovery key, with its public keys
+#[derive(Clone, Debug, PartialEq)]
+pub enum Factor<G: Group = Generator> {
+    ChaumPedersen { y1: G::Element, y2: G::Element }, // log_g(y1) = log_h(y2) is known
+    Schnorr { y: G::Element },                         // log_g(y) is known
+}
+
+impl<G: Group> Factor<G> {
+    // Public keys of a key pair for the given kind of factor
+    pub fn from_keypair(kind: FactorKind, kp: &KeyPair<G>) -> Factor<G> {
+        match kind {
+            FactorKind::ChaumPedersen => Factor::ChaumPedersen { y1: kp.y1.clone(), y2: kp.y2.clone() },
+            FactorKind::Schnorr => Factor::Schnorr { y: kp.y1.clone() },
+        }
+    }
+
+    pub fn kind(&self) -> FactorKind {
+        match self {
+            Factor::ChaumPedersen { .. } => FactorKind::ChaumPedersen,
+            Factor::Schnorr { .. } => FactorKind::Schnorr,
+        }
+    }
+
+    // Pairs of a base and the public key it yields, each answered with one commitment
+    fn pairs(&self, g: &G) -> Vec<(G::Element, &G::Element)> {
+        match self {
+            Factor::ChaumPedersen { y1, y2 } => vec![(g.g(), y1), (g.h(), y2)],
+            Factor::Schnorr { y } => vec![(g.g(), y)],
+        }
+    }
+}
+
+// Number of commitments a statement takes: two per Chaum-Pedersen factor and one per Schnorr factor
+fn commitment_count<G: Group>(g: &G, factors: &[Factor<G>]) -> usize {
+    factors.iter().map(|f| f.pairs(g).len()).sum()
+}
+
+// Non-interactive proof that the secrets of every factor of a statement are known. All factors
+// share one challenge, so no factor can be proven on its own and replayed with another.
+#[derive(Clone, Debug, PartialEq)]
+pub struct AndProof<G: Group = Generator> {
+    pub r: Vec<G::Element>, // Commitments in factor order, two per Chaum-Pedersen and one per Schnorr factor
+    pub c: G::Scalar,       // Challenge derived from the transcript hash
+    pub s: Vec<G::Scalar>,  // Responses k_i - c * x_i mod q, one per factor
+}
+
+// Computes the Fiat-Shamir challenge H(domain, group, n, factors, r_1, ..., r_m, context) mod q,
+// where each factor is absorbed as its kind followed by its public keys
+pub fn and_challenge<G: Group>(group: &G, factors: &[Factor<G>], r: &[G::Element], context: &[u8]) -> G::Scalar {
+    let mut hasher = Sha256::new();
+    absorb(&mut hasher, AND_FIAT_SHAMIR_DOMAIN);
+    absorb(&mut hasher, &group.encode_parameters());
+    absorb(&mut hasher, &(factors.len() as u64).to_be_bytes());
+    for factor in factors {
+        match factor {
+            Factor::ChaumPedersen { y1, y2 } => {
+                absorb(&mut hasher, b"chaum-pedersen");
+                absorb(&mut hasher, &group.encode_element(y1));
+                absorb(&mut hasher, &group.encode_element(y2));
+            }
+            Factor::Schnorr { y } => {
+                absorb(&mut hasher, b"schnorr");
+                absorb(&mut hasher, &group.encode_element(y));
+            }
+        }
+    }
+    for x in r {
+        absorb(&mut hasher, &group.encode_element(x));
+    }
+    absorb(&mut hasher, context);
+    group.scalar_from_hash(&hasher.finalize())
+}
+
+// Represents a prover of the AND-composition of Chaum-Pedersen and Schnorr statements, holding
+// an independent key pair for every factor
+pub struct AndProver<G: Group = Generator> {
+    g: G,                    // Group instance
+    keys: Vec<KeyPair<G>>,   // Key pair of every factor
+    factors: Vec<Factor<G>>, // Public keys of every factor, in the same order
+    nonce_mode: NonceMode,   // How nonces are obtained
+}
+
+// Commitments of a single interactive attempt, holding one nonce per factor. Answering consumes it.
+pub struct AndCommitment<'a, G: Group = Generator> {
+    prover: &'a AndProver<G>,  // Prover whose keys answer the challenge
+    k: Vec<Secret<G::Scalar>>, // Nonce of every factor, wiped on drop
+    r: Vec<G::Element>,        // Commitments in factor order
+}
+
+impl<G: Group> Debug for AndCommitment<'_, G> {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(f, "AndCommitment <-")
+    }
+}
+
+impl<G: Group> Debug for AndProver<G> {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(f, "AndProver <-")
+    }
+}
+
+impl<G: Group> AndProver<G> {
+    // Creates a new instance of AndProver proving every key pair with the given kind of factor
+    pub fn with_keypairs(g: G, keys: Vec<(FactorKind, KeyPair<G>)>) -> Result<AndProver<G>, StatementError> {
+        if keys.is_empty() {
+            return Err(StatementError::EmptyStatement);
+        }
+        let factors = keys.iter().map(|(kind, kp)| Factor::from_keypair(*kind, kp)).collect();
+        let keys = keys.into_iter().map(|(_, kp)| kp).collect();
+        Ok(AndProver { g, keys, factors, nonce_mode: NonceMode::default() })
+    }
+
+    // Selects how nonces are obtained, random by default
+    pub fn with_nonce_mode(mut self, mode: NonceMode) -> AndProver<G> {
+        self.nonce_mode = mode;
+        self
+    }
+
+    // Returns the group used by this prover
+    pub fn group(&self) -> &G {
+        &self.g
+    }
+
+    // Public keys of every factor, as registered with the verifier
+    pub fn factors(&self) -> &[Factor<G>] {
+        &self.factors
+    }
+
+    // Nonce of every factor for the given mode, each bound to the index and public keys of its factor
+    fn nonces<R: RngCore + CryptoRng + ?Sized>(&self, mode: NonceMode, kind: &[u8], context: &[u8], rng: &mut R) -> Vec<Secret<G::Scalar>> {
+        let g = &self.g;
+        self.keys
+            .iter()
+            .zip(&self.factors)
+            .enumerate()
+            .map(|(i, (kp, factor))| {
+                let context = [b"and/".as_slice(), &(i as u64).to_be_bytes(), kind, context].concat();
+                let public: Vec<&G::Element> = factor.pairs(g).into_iter().map(|(_, y)| y).collect();
+                nonce::nonce(mode, g, kp.secret(), &public, &context, rng)
+            })
+            .collect()
+    }
+
+    // Commitments of every factor to its nonce, in factor order
+    fn commit(&self, k: Vec<Secret<G::Scalar>>) -> AndCommitment<'_, G> {
+        let g = &self.g;
+        let r = self
+            .factors
+            .iter()
+            .zip(&k)
+            .flat_map(|(factor, k)| factor.pairs(g).into_iter().map(move |(base, _)| g.exp_secret(&base, k.expose())))
+            .collect();
+        AndCommitment { prover: self, k, r }
+    }
+
+    // Proves part A of the protocol synchronously, committing to one nonce per factor.
+    // The deterministic mode is hedged here; see NonceMode::interactive.
+    pub fn prove_sync_a<R: RngCore + CryptoRng + ?Sized>(&self, rng: &mut R) -> AndCommitment<'_, G> {
+        self.prove_sync_a_with_context(&[], rng)
+    }
+
+    // Proves part A of the protocol for a login identified by context, such as a server-issued
+    // login nonce, which is bound into the nonce of every factor alongside fresh randomness
+    pub fn prove_sync_a_with_context<R: RngCore + CryptoRng + ?Sized>(&self, context: &[u8], rng: &mut R) -> AndCommitment<'_, G> {
+        debug!("[P] AND PROVE SYNC A over {} factors", self.factors.len());
+        let k = self.nonces(self.nonce_mode.interactive(), b"interactive/", context, rng);
+        let commitment = self.commit(k);
+        debug!("[P] END AND PROVE SYNC A");
+        commitment
+    }
+
+    // Produces a non-interactive proof bound to the caller-supplied context
+    pub fn prove<R: RngCore + CryptoRng + ?Sized>(&self, context: &[u8], rng: &mut R) -> AndProof<G> {
+        debug!("[P] AND PROVE");
+        let commitment = self.commit(self.nonces(self.nonce_mode, b"fiat-shamir/", context, rng));
+        let r = commitment.r.clone();
+        let c = and_challenge(&self.g, &self.factors, &r, context);
+        let s = commitment.prove_sync_b(&c);
+        debug!("[P] END AND PROVE");
+        AndProof { r, c, s }
+    }
+}
+
+impl<G: Group> AndCommitment<'_, G> {
+    // Commitments in factor order, sent to the verifier
+    pub fn r(&self) -> &[G::Element] {
+        &self.r
+    }
+
+    // Proves part B of the protocol synchronously, answering the shared challenge for every
+    // factor and consuming the nonces
+    pub fn prove_sync_b(self, c: &G::Scalar) -> Vec<G::Scalar> {
+        let g = &self.prover.g;
+        self.prover
+            .keys
+            .iter()
+            .zip(&self.k)
+            .map(|(kp, k)| {
+                let xc = Secret::new(g.scalar_mul(kp.secret().expose(), c));
+                g.scalar_sub(k.expose(), xc.expose())
+            })
+            .collect()
+    }
+}
+
+// Challenge state of a single interactive AND attempt, consumed when its answer is checked
+#[derive(Debug)]
+pub struct AndChallenge<G: Group = Generator> {
+    r: Vec<G::Element>, // Commitments received from the prover
+    c: G::Scalar,       // Challenge value sent back to the prover
+}
+
+impl<G: Group> AndChallenge<G> {
+    // Challenge value to send back to the prover
+    pub fn c(&self) -> &G::Scalar {
+        &self.c
+    }
+}
+
+// Represents a verifier of the AND-composition, holding the public keys of every factor
+pub struct AndVerifier<G: Group = Generator> {
+    g: G,                    // Group instance
+    factors: Vec<Factor<G>>, // Public keys of every factor
+}
+
+impl<G: Group> Debug for AndVerifier<G> {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(f, "AndVerifier <-")
+    }
+}
+
+impl<G: Group> AndVerifier<G> {
+    // Creates a new instance of AndVerifier for the given factors, rejecting empty statements
+    // and keys outside the prime-order subgroup
+    pub fn with_group(g: G, factors: Vec<Factor<G>>) -> Result<AndVerifier<G>, StatementError> {
+        if factors.is_empty() {
+            return Err(StatementError::EmptyStatement);
+        }
+        for (index, factor) in factors.iter().enumerate() {
+            let checked = match factor {
+                Factor::ChaumPedersen { y1, y2 } => g.check_named("y1", y1).and_then(|_| g.check_named("y2", y2)),
+                Factor::Schnorr { y } => g.check_named("y", y),
+            };
+            checked.map_err(|error| StatementError::Element { index, error })?;
+        }
+        Ok(AndVerifier { g, factors })
+    }
+
+    // Returns the group used by this verifier
+    pub fn group(&self) -> &G {
+        &self.g
+    }
+
+    // Public keys of every factor
+    pub fn factors(&self) -> &[Factor<G>] {
+        &self.factors
+    }
+
+    // Checks that there is one commitment per base of every factor and that all are group members
+    fn check_commitments(&self, r: &[G::Element]) -> Result<(), StatementError> {
+        let expected = commitment_count(&self.g, &self.factors);
+        if r.len() != expected {
+            return Err(StatementError::CountMismatch { expected, actual: r.len() });
+        }
+        check_all(&self.g, "r", r)
+    }
+
+    // Checks r = base^s_i * y^c for every base of every factor i, under the shared challenge c
+    fn check(&self, r: &[G::Element], c: &G::Scalar, s: &[G::Scalar]) -> bool {
+        let g = &self.g;
+        let pairs: Vec<_> = self
+            .factors
+            .iter()
+            .zip(s)
+            .flat_map(|(factor, s)| factor.pairs(g).into_iter().map(move |(base, y)| (base, y, s)))
+            .collect();
+        s.len() == self.factors.len()
+            && pairs.len() == r.len()
+            && pairs.into_iter().zip(r).all(|((base, y, s), r)| &g.multi_exp(&[base, y.clone()], &[s.clone(), c.clone()]) == r)
+    }
+
+    // Verifies part A of the protocol synchronously, returning the state of this attempt
+    pub fn verify_sync_a<R: RngCore + CryptoRng + ?Sized>(&self, r: Vec<G::Element>, rng: &mut R) -> Result<AndChallenge<G>, StatementError> {
+        debug!("[V] AND VERIFY SYNC A over {} factors", self.factors.len());
+        self.check_commitments(&r)?;
+        let c = self.g.random_scalar(rng);
+        debug!("[V] END AND VERIFY SYNC A");
+        Ok(AndChallenge { r, c })
+    }
+
+    // Verifies part B of the protocol synchronously, given the response of every factor
+    pub fn verify_sync_b(&self, challenge: AndChallenge<G>, s: &[G::Scalar]) -> bool {
+        self.check(&challenge.r, &challenge.c, s)
+    }
+
+    // Verifies a non-interactive proof against the context it was produced for
+    pub fn verify(&self, proof: &AndProof<G>, context: &[u8]) -> bool {
+        debug!("[V] AND VERIFY over {} factors", self.factors.len());
+        let result = self.check_commitments(&proof.r).is_ok()
+            && and_challenge(&self.g, &self.factors, &proof.r, context) == proof.c
+            && self.check(&proof.r, &proof.c, &proof.s);
+        debug!("[V] END AND VERIFY");
+        result
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use num_bigint::BigInt;
+
+    use crate::zkrypto::chaum_pedersen::{ChaumPedersenProver, KeyPair, StatementError};
+    use crate::zkrypto::generator::{get_debug, get_default};
+    use crate::zkrypto::group::{ElementError, Group, MembershipError};
+    use crate::zkrypto::nonce::NonceMode;
+    use crate::zkrypto::ristretto::RistrettoGroup;
+    use crate::zkrypto::scalar::Secret;
+    use super::{AndProver, AndVerifier, Factor, FactorKind, OrProver, OrVerifier};
+
+    // Key pairs of n members in the given group
+    fn members<G: Group>(g: &G, n: usize) -> Vec<ChaumPedersenProver<G>> {
+        (0..n).map(|_| ChaumPedersenProver::with_group(g.clone(), &mut rand::thread_rng())).collect()
+    }
+
+    fn statement<G: Group>(members: &[ChaumPedersenProver<G>]) -> Vec<(G::Element, G::Element)> {
+        members.iter().map(|m| (m.kp.y1.clone(), m.kp.y2.clone())).collect()
+    }
+
+    #[test]
+    fn test_any_member_proves_membership() {
+        let mut rng = rand::thread_rng();
+        let g = get_default();
+        let members = members(&g, 4);
+        let verifier = OrVerifier::with_group(g.clone(), statement(&members)).unwrap();
+        for member in &members {
+            let prover = OrProver::with_secret(g.clone(), member.kp.secret().clone(), statement(&members)).unwrap();
+
+            let commitment = prover.prove_sync_a(&mut rng);
+            let challenge = verifier.verify_sync_a(commitment.r1().to_vec(), commitment.r2().to_vec(), &mut rng).unwrap();
+            let answer = commitment.prove_sync_b(challenge.c());
+            assert!(verifier.verify_sync_b(challenge, &answer));
+
+            let proof = prover.prove(b"team login", &mut rng);
+            assert!(verifier.verify(&proof, b"team login"));
+            assert!(!verifier.verify(&proof, b"other context"));
+        }
+    }
+
+    #[test]
+    fn test_single_member_and_ristretto() {
+        let mut rng = rand::thread_rng();
+        let g = RistrettoGroup::new();
+        let members = members(&g, 3);
+        let verifier = OrVerifier::with_group(g.clone(), statement(&members)).unwrap();
+        let prover = OrProver::with_secret(g.clone(), members[2].kp.secret().clone(), statement(&members)).unwrap();
+        assert!(verifier.verify(&prover.prove(b"team", &mut rng), b"team"));
+
+        let alone = OrProver::with_secret(g.clone(), members[0].kp.secret().clone(), statement(&members[..1])).unwrap();
+        let verifier = OrVerifier::with_group(g, statement(&members[..1])).unwrap();
+        assert!(verifier.verify(&alone.prove(b"team", &mut rng), b"team"));
+    }
+
+    #[test]
+    fn test_outsiders_cannot_prove() {
+        let mut rng = rand::thread_rng();
+        let g = get_default();
+        let members = members(&g, 3);
+        let outsider = ChaumPedersenProver::with_group(g.clone(), &mut rng);
+        assert!(OrProver::with_secret(g.clone(), outsider.kp.secret().clone(), statement(&members)).is_none());
+
+        // An outsider proving for a statement that includes its key does not convince the real verifier
+        let verifier = OrVerifier::with_group(g.clone(), statement(&members)).unwrap();
+        let mut forged = statement(&members);
+        forged[1] = (outsider.kp.y1.clone(), outsider.kp.y2.clone());
+        let prover = OrProver::with_secret(g.clone(), outsider.kp.secret().clone(), forged).unwrap();
+        assert!(!verifier.verify(&prover.prove(b"team", &mut rng), b"team"));
+
+        // Branch challenges must add up to the challenge
+        let member = OrProver::with_secret(g.clone(), members[0].kp.secret().clone(), statement(&members)).unwrap();
+        let mut proof = member.prove(b"team", &mut rng);
+        assert!(verifier.verify(&proof, b"team"));
+        proof.branches[2].c = g.scalar_add(&proof.branches[2].c, &g.scalar_from_bigint(&BigInt::from(1)));
+        assert!(!verifier.verify(&proof, b"team"));
+        proof.branches.pop();
+        assert!(!verifier.verify(&proof, b"team"));
+    }
+
+    #[test]
+    fn test_branches_do_not_reveal_the_member() {
+        // With q = 61 every branch challenge can be counted; each one is uniform whoever proves
+        let mut rng = rand::thread_rng();
+        let g = get_debug();
+        let members = members(&g, 2);
+        let mut counts = [[0u32; 61]; 2];
+        for i in 0u32..61 * 100 {
+            let member = &members[(i % 2) as usize];
+            let prover = OrProver::with_secret(g.clone(), member.kp.secret().clone(), statement(&members)).unwrap();
+            let proof = prover.prove(&i.to_be_bytes(), &mut rng);
+            for (branch, count) in proof.branches.iter().zip(&mut counts) {
+                count[usize::try_from(branch.c.value()).unwrap()] += 1;
+            }
+        }
+        // 100 samples expected per residue; a branch challenge fixed or skewed by the real member would stand out
+        for count in counts {
+            assert!(count.iter().all(|&n| n > 40 && n < 180), "branch challenges not uniform: {:?}", count);
+        }
+    }
+
+    #[test]
+    fn test_rejects_malformed_statements() {
+        let mut rng = rand::thread_rng();
+        let g = get_debug();
+        let members = members(&g, 2);
+        assert_eq!(OrVerifier::with_group(g.clone(), vec![]).map(|_| ()), Err(StatementError::EmptyStatement));
+        let mut bad = statement(&members);
+        bad[1].1 = BigInt::from(366);
+        assert_eq!(
+            OrVerifier::with_group(g.clone(), bad).map(|_| ()),
+            Err(StatementError::Element { index: 1, error: ElementError { name: "y2", error: MembershipError::NotInSubgroup } })
+        );
+
+        let verifier = OrVerifier::with_group(g.clone(), statement(&members)).unwrap();
+        let prover = OrProver::with_secret(g.clone(), members[0].kp.secret().clone(), statement(&members)).unwrap();
+        let commitment = prover.prove_sync_a(&mut rng);
+        assert_eq!(
+            verifier.verify_sync_a(commitment.r1().to_vec(), commitment.r2()[..1].to_vec(), &mut rng).map(|_| ()),
+            Err(StatementError::CountMismatch { expected: 2, actual: 1 })
+        );
+    }
+
+    // A device key proven with Chaum-Pedersen and a recovery key proven with Schnorr
+    fn two_factors<G: Group>(g: &G) -> Vec<(FactorKind, KeyPair<G>)> {
+        let mut rng = rand::thread_rng();
+        vec![
+            (FactorKind::ChaumPedersen, KeyPair::from_secret(g, Secret::new(g.random_nonzero_scalar(&mut rng)))),
+            (FactorKind::Schnorr, KeyPair::from_secret(g, Secret::new(g.random_nonzero_scalar(&mut rng)))),
+        ]
+    }
+
+    #[test]
+    fn test_and_composition() {
+        let mut rng = rand::thread_rng();
+        let g = get_default();
+        let prover = AndProver::with_keypairs(g.clone(), two_factors(&g)).unwrap();
+        let verifier = AndVerifier::with_group(g.clone(), prover.factors().to_vec()).unwrap();
+
+        let commitment = prover.prove_sync_a(&mut rng);
+        assert_eq!(commitment.r().len(), 3);
+        let challenge = verifier.verify_sync_a(commitment.r().to_vec(), &mut rng).unwrap();
+        let s = commitment.prove_sync_b(challenge.c());
+        assert!(verifier.verify_sync_b(challenge, &s));
+
+        let proof = prover.prove(b"login", &mut rng);
+        assert!(verifier.verify(&proof, b"login"));
+        assert!(!verifier.verify(&proof, b"other context"));
+
+        let g = RistrettoGroup::new();
+        let prover = AndProver::with_keypairs(g.clone(), two_factors(&g)).unwrap();
+        let verifier = AndVerifier::with_group(g, prover.factors().to_vec()).unwrap();
+        assert!(verifier.verify(&prover.prove(b"login", &mut rng), b"login"));
+    }
+
+    #[test]
+    fn test_and_nonce_modes() {
+        let mut rng = rand::thread_rng();
+        let g = get_default();
+        let prover = AndProver::with_keypairs(g.clone(), two_factors(&g)).unwrap().with_nonce_mode(NonceMode::Deterministic);
+        let verifier = AndVerifier::with_group(g, prover.factors().to_vec()).unwrap();
+
+        // The same context yields the same proof, a different one different nonces
+        let proof = prover.prove(b"login 1", &mut rng);
+        assert!(verifier.verify(&proof, b"login 1"));
+        assert_eq!(prover.prove(b"login 1", &mut rng).r, proof.r);
+        assert_ne!(prover.prove(b"login 2", &mut rng).r, proof.r);
+
+        // Interactive commitments are hedged, so they do not repeat even when the context does
+        let a = prover.prove_sync_a_with_context(b"session 1", &mut rng);
+        let b = prover.prove_sync_a_with_context(b"session 1", &mut rng);
+        assert_ne!(a.r(), b.r());
+
+        let challenge = verifier.verify_sync_a(a.r().to_vec(), &mut rng).unwrap();
+        let s = a.prove_sync_b(challenge.c());
+        assert!(verifier.verify_sync_b(challenge, &s));
+    }
+
+    #[test]
+    fn test_and_requires_every_factor() {
+        let mut rng = rand::thread_rng();
+        let g = get_default();
+        let prover = AndProver::with_keypairs(g.clone(), two_factors(&g)).unwrap();
+        let verifier = AndVerifier::with_group(g.clone(), prover.factors().to_vec()).unwrap();
+
+        // Holding the device key and some other recovery key is not enough
+        let mut stolen = two_factors(&g);
+        let device = prover.keys[0].secret().clone();
+        stolen[0].1 = KeyPair::from_secret(&g, device);
+        let thief = AndProver::with_keypairs(g.clone(), stolen).unwrap();
+        assert!(!verifier.verify(&thief.prove(b"login", &mut rng), b"login"));
+        let commitment = thief.prove_sync_a(&mut rng);
+        let challenge = verifier.verify_sync_a(commitment.r().to_vec(), &mut rng).unwrap();
+        let s = commitment.prove_sync_b(challenge.c());
+        assert!(!verifier.verify_sync_b(challenge, &s));
+
+        // Responses are bound to their factors, and none can be left out
+        let mut proof = prover.prove(b"login", &mut rng);
+        proof.s.swap(0, 1);
+        assert!(!verifier.verify(&proof, b"login"));
+        proof.s.pop();
+        assert!(!verifier.verify(&proof, b"login"));
+
+        // A proof of the device factor alone does not satisfy the policy
+        let device = AndVerifier::with_group(g.clone(), prover.factors()[..1].to_vec()).unwrap();
+        let single = AndProver::with_keypairs(g.clone(), vec![(FactorKind::ChaumPedersen, KeyPair::from_secret(&g, prover.keys[0].secret().clone()))]).unwrap();
+        let proof = single.prove(b"login", &mut rng);
+        assert!(device.verify(&proof, b"login"));
+        assert!(!verifier.verify(&proof, b"login"));
+    }
+
+    #[test]
+    fn test_and_rejects_malformed_statements() {
+        let mut rng = rand::thread_rng();
+        let g = get_debug();
+        assert_eq!(AndProver::with_keypairs(g.clone(), vec![]).map(|_| ()), Err(StatementError::EmptyStatement));
+        assert_eq!(AndVerifier::with_group(g.clone(), vec![]).map(|_| ()), Err(StatementError::EmptyStatement));
+        assert_eq!(
+            AndVerifier::with_group(g.clone(), vec![Factor::Schnorr { y: g.g() }, Factor::Schnorr { y: BigInt::from(366) }]).map(|_| ()),
+            Err(StatementError::Element { index: 1, error: ElementError { name: "y", error: MembershipError::NotInSubgroup } })
+        );
+
+        let prover = AndProver::with_keypairs(g.clone(), two_factors(&g)).unwrap();
+        let verifier = AndVerifier::with_group(g.clone(), prover.factors().to_vec()).unwrap();
+        assert_eq!(prover.factors()[1].kind(), FactorKind::Schnorr);
+        let commitment = prover.prove_sync_a(&mut rng);
+        assert_eq!(
+            verifier.verify_sync_a(commitment.r()[..2].to_vec(), &mut rng).map(|_| ()),
+            Err(StatementError::CountMismatch { expected: 3, actual: 2 })
+        );
+    }
+}
@@ -0,0 +1,109 @@
+use std::fmt;
+
+use num_bigint::{BigInt, Sign};
+use num_traits::{One, Signed, Zero};
+
+// Errors raised when converting between BigInt and its fixed-length wire encoding
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum EncodingError {
+    Negative,   // Value is below zero
+    OutOfRange, // Value is not below the modulus
+    InvalidLength { expected: usize, actual: usize }, // Byte string has a non-canonical length
+    InvalidPoint, // Byte string is not the canonical encoding of a curve point
+}
+
+impl fmt::Display for EncodingError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            EncodingError::Negative => write!(f, "negative value cannot be encoded"),
+            EncodingError::OutOfRange => write!(f, "value is not reduced modulo the group modulus"),
+            EncodingError::InvalidLength { expected, actual } => {
+                write!(f, "expected {} bytes, got {}", expected, actual)
+            }
+            EncodingError::InvalidPoint => write!(f, "not a canonical point encoding"),
+        }
+    }
+}
+
+impl std::error::Error for EncodingError {}
+
+// Number of bytes needed to encode any integer in [0, modulus)
+pub fn byte_len(modulus: &BigInt) -> usize {
+    let max: BigInt = modulus - BigInt::one();
+    std::cmp::max(1, max.bits().div_ceil(8) as usize)
+}
+
+// Encodes n in [0, modulus) as a big-endian byte string of exactly byte_len(modulus) bytes
+pub fn encode(n: &BigInt, modulus: &BigInt) -> Result<Vec<u8>, EncodingError> {
+    if n.is_negative() {
+        return Err(EncodingError::Negative);
+    }
+    if n >= modulus {
+        return Err(EncodingError::OutOfRange);
+    }
+    let len = byte_len(modulus);
+    let (_, digits) = n.to_bytes_be();
+    let mut bytes = vec![0u8; len];
+    if !n.is_zero() {
+        bytes[len - digits.len()..].copy_from_slice(&digits);
+    }
+    Ok(bytes)
+}
+
+// Decodes a big-endian byte string produced by encode, rejecting non-canonical inputs
+pub fn decode(bytes: &[u8], modulus: &BigInt) -> Result<BigInt, EncodingError> {
+    let len = byte_len(modulus);
+    if bytes.len() != len {
+        return Err(EncodingError::InvalidLength { expected: len, actual: bytes.len() });
+    }
+    let n = BigInt::from_bytes_be(Sign::Plus, bytes);
+    if &n >= modulus {
+        return Err(EncodingError::OutOfRange);
+    }
+    Ok(n)
+}
+
+#[cfg(test)]
+mod test {
+    use num_bigint::{BigInt, RandBigInt};
+    use num_traits::One;
+
+    use super::{byte_len, decode, encode, EncodingError};
+
+    #[test]
+    fn test_byte_len() {
+        assert_eq!(byte_len(&BigInt::from(2)), 1);
+        assert_eq!(byte_len(&BigInt::from(256)), 1);
+        assert_eq!(byte_len(&BigInt::from(257)), 2);
+        assert_eq!(byte_len(&(BigInt::one() << 2048)), 256);
+    }
+
+    #[test]
+    fn test_roundtrip() {
+        let mut rng = rand::thread_rng();
+        let modulus: BigInt = (BigInt::one() << 2048) - 159;
+        for _ in 0..16 {
+            let n = rng.gen_bigint_range(&BigInt::from(0), &modulus);
+            let bytes = encode(&n, &modulus).unwrap();
+            assert_eq!(bytes.len(), 256);
+            assert_eq!(decode(&bytes, &modulus).unwrap(), n);
+        }
+        assert_eq!(encode(&BigInt::from(0), &modulus).unwrap(), vec![0u8; 256]);
+    }
+
+    #[test]
+    fn test_rejects_non_canonical() {
+        let modulus = BigInt::from(367);
+        assert_eq!(encode(&BigInt::from(-1), &modulus), Err(EncodingError::Negative));
+        assert_eq!(encode(&BigInt::from(367), &modulus), Err(EncodingError::OutOfRange));
+        assert_eq!(decode(&[0x01, 0x6f], &modulus), Err(EncodingError::OutOfRange));
+        assert_eq!(
+            decode(&[0x01], &modulus),
+            Err(EncodingError::InvalidLength { expected: 2, actual: 1 })
+        );
+        assert_eq!(
+            decode(&[0x00, 0x00, 0x01], &modulus),
+            Err(EncodingError::InvalidLength { expected: 2, actual: 3 })
+        );
+    }
+}
@@ -1,14 +1,23 @@
 
-use num_bigint::{BigInt, RandBigInt};
+use std::fmt;
+
+use num_bigint::{BigInt, RandBigInt, Sign};
 use num_traits::{Zero, One};
+use rand::{CryptoRng, RngCore};
+use sha2::{Digest, Sha256};
 
+use crate::zkrypto::encoding::{self, EncodingError};
+use crate::zkrypto::group::{Group, MembershipError};
+use crate::zkrypto::montgomery::Montgomery;
+use crate::zkrypto::multiexp;
 use crate::zkrypto::prime::{miller_rabin_primality_test, n_bit_prime};
+use crate::zkrypto::scalar::Zq;
 
 
 //use prime::{miller_rabin_primality_test, n_bit_prime};
 
 
-#[derive(Clone, Default)]
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
 pub struct Generator {
     pub p: BigInt,
     pub q: BigInt,
@@ -16,34 +25,452 @@ pub struct Generator {
     pub h: BigInt
 }
 
+// Reduces x into [0, m), following mod_floor semantics for negative values
+fn reduce(x: &BigInt, m: &BigInt) -> BigInt {
+    x.modpow(&BigInt::one(), m)
+}
+
+// The order-q subgroup of Z_p^* generated by g, with h a second generator of it
+impl Group for Generator {
+    type Element = BigInt;
+    type Scalar = Zq;
+
+    fn g(&self) -> BigInt {
+        self.g.clone()
+    }
+
+    fn h(&self) -> BigInt {
+        self.h.clone()
+    }
+
+    fn identity(&self) -> BigInt {
+        BigInt::one()
+    }
+
+    fn order(&self) -> BigInt {
+        self.q.clone()
+    }
+
+    // Rejects x outside [2, p - 1] and x with x^q != 1 mod p
+    fn check_element(&self, x: &BigInt) -> Result<(), MembershipError> {
+        if x < &BigInt::from(2) || x >= &self.p {
+            return Err(MembershipError::OutOfRange);
+        }
+        if x.modpow(&self.q, &self.p) != BigInt::one() {
+            return Err(MembershipError::NotInSubgroup);
+        }
+        Ok(())
+    }
+
+    fn exp(&self, base: &BigInt, e: &Zq) -> BigInt {
+        base.modpow(e.value(), &self.p)
+    }
+
+    // Fixed-window Montgomery exponentiation over as many bits as q has, whatever the value of e
+    fn exp_secret(&self, base: &BigInt, e: &Zq) -> BigInt {
+        Montgomery::new(&self.p).pow(base, e.value(), self.q.bits())
+    }
+
+    fn mul(&self, a: &BigInt, b: &BigInt) -> BigInt {
+        (a * b) % &self.p
+    }
+
+    fn multi_exp(&self, bases: &[BigInt], exponents: &[Zq]) -> BigInt {
+        let exponents: Vec<BigInt> = exponents.iter().map(|e| e.value().clone()).collect();
+        multiexp::multi_exp(bases, &exponents, &self.p)
+    }
+
+    fn random_scalar<R: RngCore + CryptoRng + ?Sized>(&self, rng: &mut R) -> Zq {
+        Zq::random(&self.q, rng)
+    }
+
+    fn random_nonzero_scalar<R: RngCore + CryptoRng + ?Sized>(&self, rng: &mut R) -> Zq {
+        Zq::random_nonzero(&self.q, rng)
+    }
+
+    fn scalar_add(&self, a: &Zq, b: &Zq) -> Zq {
+        a.add(b, &self.q)
+    }
+
+    fn scalar_sub(&self, a: &Zq, b: &Zq) -> Zq {
+        a.sub(b, &self.q)
+    }
+
+    fn scalar_mul(&self, a: &Zq, b: &Zq) -> Zq {
+        a.mul(b, &self.q)
+    }
+
+    fn scalar_inv(&self, a: &Zq) -> Option<Zq> {
+        a.inv(&self.q)
+    }
+
+    fn scalar_from_bigint(&self, x: &BigInt) -> Zq {
+        Zq::new(x, &self.q)
+    }
+
+    fn scalar_from_hash(&self, digest: &[u8]) -> Zq {
+        Zq::from_hash(digest, &self.q)
+    }
+
+    // Canonical byte length of a group element (an integer mod p)
+    fn element_len(&self) -> usize {
+        encoding::byte_len(&self.p)
+    }
+
+    // Canonical byte length of a scalar (an integer mod q)
+    fn scalar_len(&self) -> usize {
+        encoding::byte_len(&self.q)
+    }
+
+    fn encode_element(&self, e: &BigInt) -> Vec<u8> {
+        encoding::encode(&reduce(e, &self.p), &self.p).expect("reduced elements are encodable")
+    }
+
+    fn decode_element(&self, bytes: &[u8]) -> Result<BigInt, EncodingError> {
+        encoding::decode(bytes, &self.p)
+    }
+
+    fn encode_scalar(&self, s: &Zq) -> Vec<u8> {
+        encoding::encode(s.value(), &self.q).expect("reduced scalars are encodable")
+    }
+
+    fn decode_scalar(&self, bytes: &[u8]) -> Result<Zq, EncodingError> {
+        encoding::decode(bytes, &self.q).map(|s| Zq::new(&s, &self.q))
+    }
+
+    // Length-prefixed big-endian p, q, g and h
+    fn encode_parameters(&self) -> Vec<u8> {
+        let mut out = b"modp".to_vec();
+        for x in [&self.p, &self.q, &self.g, &self.h] {
+            let (_, bytes) = x.to_bytes_be();
+            out.extend_from_slice(&(bytes.len() as u32).to_be_bytes());
+            out.extend_from_slice(&bytes);
+        }
+        out
+    }
+}
+
+// Miller-Rabin rounds used when validating parameters, giving an error bound of 4^-20
+pub const DEFAULT_MR_ROUNDS: i64 = 20;
+
+// Thresholds applied by Generator::validate
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ValidationParams {
+    pub rounds: i64,      // Miller-Rabin rounds for the primality of p and q
+    pub min_p_bits: u64,  // Minimum bit length of p
+    pub min_q_bits: u64,  // Minimum bit length of q
+}
+
+impl Default for ValidationParams {
+    // NIST SP 800-57 floor for finite-field groups
+    fn default() -> ValidationParams {
+        ValidationParams { rounds: DEFAULT_MR_ROUNDS, min_p_bits: 2048, min_q_bits: 224 }
+    }
+}
+
+// A single check failed by Generator::validate
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum ValidationFailure {
+    PTooSmall { bits: u64, min: u64 }, // p is shorter than min_p_bits
+    QTooSmall { bits: u64, min: u64 }, // q is shorter than min_q_bits
+    PNotPrime,                         // p is composite
+    QNotPrime,                         // q is composite
+    QDoesNotDivideP,                   // q does not divide p - 1
+    GWrongOrder,                       // g is outside [2, p - 1] or g^q != 1 mod p
+    HWrongOrder,                       // h is outside [2, p - 1] or h^q != 1 mod p
+    GEqualsH,                          // g and h are the same element
+}
+
+impl fmt::Display for ValidationFailure {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ValidationFailure::PTooSmall { bits, min } => write!(f, "p has {} bits, at least {} required", bits, min),
+            ValidationFailure::QTooSmall { bits, min } => write!(f, "q has {} bits, at least {} required", bits, min),
+            ValidationFailure::PNotPrime => write!(f, "p is not prime"),
+            ValidationFailure::QNotPrime => write!(f, "q is not prime"),
+            ValidationFailure::QDoesNotDivideP => write!(f, "q does not divide p - 1"),
+            ValidationFailure::GWrongOrder => write!(f, "g does not have order q"),
+            ValidationFailure::HWrongOrder => write!(f, "h does not have order q"),
+            ValidationFailure::GEqualsH => write!(f, "g and h are equal"),
+        }
+    }
+}
+
+// Every check failed by Generator::validate, in the order they were run
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ValidationError {
+    pub failures: Vec<ValidationFailure>,
+}
+
+impl fmt::Display for ValidationError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let failures: Vec<String> = self.failures.iter().map(|e| e.to_string()).collect();
+        write!(f, "invalid group parameters: {}", failures.join(", "))
+    }
+}
+
+impl std::error::Error for ValidationError {}
+
+// Checks that x lies in [2, p - 1] and x^q = 1 mod p, i.e. that x has order q when q is prime
+fn has_order(x: &BigInt, p: &BigInt, q: &BigInt) -> bool {
+    x >= &BigInt::from(2) && x < p && x.modpow(q, p) == BigInt::one()
+}
+
+impl Generator {
+    // Runs every parameter check and reports all that failed; rng picks the Miller-Rabin witnesses
+    pub fn validate<R: RngCore + CryptoRng + ?Sized>(&self, params: &ValidationParams, rng: &mut R) -> Result<(), ValidationError> {
+        let mut failures = vec![];
+        if self.p.bits() < params.min_p_bits {
+            failures.push(ValidationFailure::PTooSmall { bits: self.p.bits(), min: params.min_p_bits });
+        }
+        if self.q.bits() < params.min_q_bits {
+            failures.push(ValidationFailure::QTooSmall { bits: self.q.bits(), min: params.min_q_bits });
+        }
+        if !miller_rabin_primality_test(&self.p, params.rounds, rng) {
+            failures.push(ValidationFailure::PNotPrime);
+        }
+        if !miller_rabin_primality_test(&self.q, params.rounds, rng) {
+            failures.push(ValidationFailure::QNotPrime);
+        }
+        if self.q.is_zero() || !((&self.p - 1u32) % &self.q).is_zero() {
+            failures.push(ValidationFailure::QDoesNotDivideP);
+        }
+        if !has_order(&self.g, &self.p, &self.q) {
+            failures.push(ValidationFailure::GWrongOrder);
+        }
+        if !has_order(&self.h, &self.p, &self.q) {
+            failures.push(ValidationFailure::HWrongOrder);
+        }
+        if self.g == self.h {
+            failures.push(ValidationFailure::GEqualsH);
+        }
+        match failures.is_empty() {
+            true => Ok(()),
+            false => Err(ValidationError { failures }),
+        }
+    }
+}
+
+// Index passed to derive_generator for g when it is derived from a seed
+pub const INDEX_G: u8 = 1;
+
+// Index passed to derive_generator for h
+pub const INDEX_H: u8 = 2;
+
+// Public seed from which the h of a named group is derived
+pub fn group_seed(name: &str) -> Vec<u8> {
+    [b"zkp_auth/".as_slice(), name.as_bytes()].concat()
+}
+
+// Default params; h is derive_generator(p, q, group_seed("default"), INDEX_H). It replaced the
+// unexplained h = 322482758, so keys registered under that value no longer verify.
 pub fn get_default() -> Generator {
-    return Generator{
-        p: BigInt::from(421360559 as i64), 
-        q: BigInt::from(455033 as i64), 
-        g: BigInt::from(103117051 as i64), 
-        h: BigInt::from(322482758 as i64)
-    };// Default params
+    Generator{
+        p: BigInt::from(421360559), 
+        q: BigInt::from(455033), 
+        g: BigInt::from(103117051), 
+        h: BigInt::from(339533085)
+    }
 }
 
+// Debug params; h is derive_generator(p, q, group_seed("debug"), INDEX_H), replacing h = 199
 pub fn get_debug() -> Generator {
-    return Generator{
+    Generator{
         p: BigInt::from(367), 
         q: BigInt::from(61), 
         g: BigInt::from(137), 
-        h: BigInt::from(199)
-    };// Default params
+        h: BigInt::from(151)
+    }
+}
+
+// RFC 3526 2048-bit MODP group (group 14)
+const MODP_2048_P: &str = concat!(
+    "FFFFFFFFFFFFFFFFC90FDAA22168C234C4C6628B80DC1CD129024E088A67CC74",
+    "020BBEA63B139B22514A08798E3404DDEF9519B3CD3A431B302B0A6DF25F1437",
+    "4FE1356D6D51C245E485B576625E7EC6F44C42E9A637ED6B0BFF5CB6F406B7ED",
+    "EE386BFB5A899FA5AE9F24117C4B1FE649286651ECE45B3DC2007CB8A163BF05",
+    "98DA48361C55D39A69163FA8FD24CF5F83655D23DCA3AD961C62F356208552BB",
+    "9ED529077096966D670C354E4ABC9804F1746C08CA18217C32905E462E36CE3B",
+    "E39E772C180E86039B2783A2EC07A28FB5C55DF06F4C52C9DE2BCBF695581718",
+    "3995497CEA956AE515D2261898FA051015728E5A8AACAA68FFFFFFFFFFFFFFFF",
+);
+
+// RFC 7919 ffdhe2048
+const FFDHE_2048_P: &str = concat!(
+    "FFFFFFFFFFFFFFFFADF85458A2BB4A9AAFDC5620273D3CF1D8B9C583CE2D3695",
+    "A9E13641146433FBCC939DCE249B3EF97D2FE363630C75D8F681B202AEC4617A",
+    "D3DF1ED5D5FD65612433F51F5F066ED0856365553DED1AF3B557135E7F57C935",
+    "984F0C70E0E68B77E2A689DAF3EFE8721DF158A136ADE73530ACCA4F483A797A",
+    "BC0AB182B324FB61D108A94BB2C8E3FBB96ADAB760D7F4681D4F42A3DE394DF4",
+    "AE56EDE76372BB190B07A7C8EE0A6D709E02FCE1CDF7E2ECC03404CD28342F61",
+    "9172FE9CE98583FF8E4F1232EEF28183C3FE3B1B4C6FAD733BB5FCBC2EC22005",
+    "C58EF1837D1683B2C6F34A26C1B2EFFA886B423861285C97FFFFFFFFFFFFFFFF",
+);
+
+// RFC 7919 ffdhe3072
+const FFDHE_3072_P: &str = concat!(
+    "FFFFFFFFFFFFFFFFADF85458A2BB4A9AAFDC5620273D3CF1D8B9C583CE2D3695",
+    "A9E13641146433FBCC939DCE249B3EF97D2FE363630C75D8F681B202AEC4617A",
+    "D3DF1ED5D5FD65612433F51F5F066ED0856365553DED1AF3B557135E7F57C935",
+    "984F0C70E0E68B77E2A689DAF3EFE8721DF158A136ADE73530ACCA4F483A797A",
+    "BC0AB182B324FB61D108A94BB2C8E3FBB96ADAB760D7F4681D4F42A3DE394DF4",
+    "AE56EDE76372BB190B07A7C8EE0A6D709E02FCE1CDF7E2ECC03404CD28342F61",
+    "9172FE9CE98583FF8E4F1232EEF28183C3FE3B1B4C6FAD733BB5FCBC2EC22005",
+    "C58EF1837D1683B2C6F34A26C1B2EFFA886B4238611FCFDCDE355B3B6519035B",
+    "BC34F4DEF99C023861B46FC9D6E6C9077AD91D2691F7F7EE598CB0FAC186D91C",
+    "AEFE130985139270B4130C93BC437944F4FD4452E2D74DD364F2E21E71F54BFF",
+    "5CAE82AB9C9DF69EE86D2BC522363A0DABC521979B0DEADA1DBF9A42D5C4484E",
+    "0ABCD06BFA53DDEF3C1B20EE3FD59D7C25E41D2B66C62E37FFFFFFFFFFFFFFFF",
+);
+
+// RFC 7919 ffdhe4096
+const FFDHE_4096_P: &str = concat!(
+    "FFFFFFFFFFFFFFFFADF85458A2BB4A9AAFDC5620273D3CF1D8B9C583CE2D3695",
+    "A9E13641146433FBCC939DCE249B3EF97D2FE363630C75D8F681B202AEC4617A",
+    "D3DF1ED5D5FD65612433F51F5F066ED0856365553DED1AF3B557135E7F57C935",
+    "984F0C70E0E68B77E2A689DAF3EFE8721DF158A136ADE73530ACCA4F483A797A",
+    "BC0AB182B324FB61D108A94BB2C8E3FBB96ADAB760D7F4681D4F42A3DE394DF4",
+    "AE56EDE76372BB190B07A7C8EE0A6D709E02FCE1CDF7E2ECC03404CD28342F61",
+    "9172FE9CE98583FF8E4F1232EEF28183C3FE3B1B4C6FAD733BB5FCBC2EC22005",
+    "C58EF1837D1683B2C6F34A26C1B2EFFA886B4238611FCFDCDE355B3B6519035B",
+    "BC34F4DEF99C023861B46FC9D6E6C9077AD91D2691F7F7EE598CB0FAC186D91C",
+    "AEFE130985139270B4130C93BC437944F4FD4452E2D74DD364F2E21E71F54BFF",
+    "5CAE82AB9C9DF69EE86D2BC522363A0DABC521979B0DEADA1DBF9A42D5C4484E",
+    "0ABCD06BFA53DDEF3C1B20EE3FD59D7C25E41D2B669E1EF16E6F52C3164DF4FB",
+    "7930E9E4E58857B6AC7D5F42D69F6D187763CF1D5503400487F55BA57E31CC7A",
+    "7135C886EFB4318AED6A1E012D9E6832A907600A918130C46DC778F971AD0038",
+    "092999A333CB8B7A1A1DB93D7140003C2A4ECEA9F98D0ACC0A8291CDCEC97DCF",
+    "8EC9B55A7F88A46B4DB5A851F44182E1C68A007E5E655F6AFFFFFFFFFFFFFFFF",
+);
+
+// RFC 5114 2048-bit MODP group with 256-bit prime order subgroup
+const MODP_2048_256_P: &str = concat!(
+    "87A8E61DB4B6663CFFBBD19C651959998CEEF608660DD0F25D2CEED4435E3B00",
+    "E00DF8F1D61957D4FAF7DF4561B2AA3016C3D91134096FAA3BF4296D830E9A7C",
+    "209E0C6497517ABD5A8A9D306BCF67ED91F9E6725B4758C022E0B1EF4275BF7B",
+    "6C5BFC11D45F9088B941F54EB1E59BB8BC39A0BF12307F5C4FDB70C581B23F76",
+    "B63ACAE1CAA6B7902D52526735488A0EF13C6D9A51BFA4AB3AD8347796524D8E",
+    "F6A167B5A41825D967E144E5140564251CCACB83E6B486F6B3CA3F7971506026",
+    "C0B857F689962856DED4010ABD0BE621C3A3960A54E710C375F26375D7014103",
+    "A4B54330C198AF126116D2276E11715F693877FAD7EF09CADB094AE91E1A1597",
+);
+const MODP_2048_256_Q: &str = "8CF83642A709A097B447997640129DA299B1A47D1EB3750BA308B0FE64F5FBD3";
+const MODP_2048_256_G: &str = concat!(
+    "3FB32C9B73134D0B2E77506660EDBD484CA7B18F21EF205407F4793A1A0BA125",
+    "10DBC15077BE463FFF4FED4AAC0BB555BE3A6C1B0C6B47B1BC3773BF7E8C6F62",
+    "901228F8C28CBB18A55AE31341000A650196F931C77A57F2DDF463E5E9EC144B",
+    "777DE62AAAB8A8628AC376D282D6ED3864E67982428EBC831D14348F6F2F9193",
+    "B5045AF2767164E1DFC967C1FB3F2E55A4BD1BFFE83B9C80D052B985D182EA0A",
+    "DB2A3B7313D3FE14C8484B1E052588B9B7D2BBD2DF016199ECD06E1557CD0915",
+    "B3353BBB64E0EC377FD028370DF92B52C7891428CDC67EB6184B523D1DB246C3",
+    "2F63078490F00EF8D647D148D47954515E2327CFEF98C582664B4C0F6CC41659",
+);
+
+// Names accepted by get_named
+pub const NAMED_GROUPS: [&str; 7] = [
+    "default",
+    "debug",
+    "modp2048",
+    "modp2048-256",
+    "ffdhe2048",
+    "ffdhe3072",
+    "ffdhe4096",
+];
+
+// Parses a hexadecimal constant
+fn from_hex(hex: &str) -> BigInt {
+    BigInt::parse_bytes(hex.as_bytes(), 16).expect("valid hexadecimal constant")
+}
+
+// Derives a generator of the order-q subgroup of Z_p^* from a public seed, following
+// FIPS 186-4 A.2.3: g = SHA-256(seed || "ggen" || index || count)^((p - 1) / q) mod p.
+// Different indexes yield independent generators whose mutual discrete logs nobody knows.
+// Returns None if the 16-bit counter is exhausted, which only happens for degenerate (p, q).
+pub fn derive_generator(p: &BigInt, q: &BigInt, seed: &[u8], index: u8) -> Option<BigInt> {
+    let e: BigInt = (p - 1) / q;
+    for count in 1..=u16::MAX {
+        let w = Sha256::new()
+            .chain_update(seed)
+            .chain_update(b"ggen")
+            .chain_update([index])
+            .chain_update(count.to_be_bytes())
+            .finalize();
+        let g = BigInt::from_bytes_be(Sign::Plus, &w).modpow(&e, p);
+        if g > BigInt::one() {
+            return Some(g);
+        }
+    }
+    None
+}
+
+// Checks that candidate was derived from seed and index, following FIPS 186-4 A.2.4:
+// 2 <= candidate <= p - 1, candidate^q = 1 mod p, and re-derivation yields the same value
+pub fn verify_generator(p: &BigInt, q: &BigInt, seed: &[u8], index: u8, candidate: &BigInt) -> bool {
+    if candidate < &BigInt::from(2) || candidate >= p {
+        return false;
+    }
+    if candidate.modpow(q, p) != BigInt::one() {
+        return false;
+    }
+    derive_generator(p, q, seed, index).as_ref() == Some(candidate)
+}
+
+// Builds a group in which both g and h are derived from a public seed
+pub fn generator_from_seed(p: BigInt, q: BigInt, seed: &[u8]) -> Option<Generator> {
+    let g = derive_generator(&p, &q, seed, INDEX_G)?;
+    let h = derive_generator(&p, &q, seed, INDEX_H)?;
+    Some(Generator { p, q, g, h })
+}
+
+// Checks that h (and g too, if derive_g is set) of a group were derived from seed
+pub fn verify_seeded(generator: &Generator, seed: &[u8], derive_g: bool) -> bool {
+    let (p, q) = (&generator.p, &generator.q);
+    (!derive_g || verify_generator(p, q, seed, INDEX_G, &generator.g))
+        && verify_generator(p, q, seed, INDEX_H, &generator.h)
 }
 
-pub fn get_generator_prime(bits: u64, num: i32) -> (BigInt, BigInt, Vec<BigInt>) {
+// Builds a standard group from its modulus, subgroup order and first generator,
+// deriving h from the group name so that nobody knows log_g(h)
+fn named_group(name: &str, p: BigInt, q: BigInt, g: BigInt) -> Generator {
+    let h = derive_generator(&p, &q, &group_seed(name), INDEX_H).expect("standard groups yield a generator");
+    Generator { p, q, g, h }
+}
+
+// Builds a standard safe-prime group: q = (p - 1) / 2 and g = 2 generates the quadratic residues
+fn safe_prime_group(name: &str, p_hex: &str) -> Generator {
+    let p = from_hex(p_hex);
+    let q: BigInt = (&p - 1) / 2;
+    named_group(name, p, q, BigInt::from(2))
+}
+
+// Looks up a parameter set by name (see NAMED_GROUPS)
+pub fn get_named(name: &str) -> Option<Generator> {
+    match name {
+        "default" => Some(get_default()),
+        "debug" => Some(get_debug()),
+        "modp2048" => Some(safe_prime_group(name, MODP_2048_P)),
+        "modp2048-256" => Some(named_group(
+            name,
+            from_hex(MODP_2048_256_P),
+            from_hex(MODP_2048_256_Q),
+            from_hex(MODP_2048_256_G),
+        )),
+        "ffdhe2048" => Some(safe_prime_group(name, FFDHE_2048_P)),
+        "ffdhe3072" => Some(safe_prime_group(name, FFDHE_3072_P)),
+        "ffdhe4096" => Some(safe_prime_group(name, FFDHE_4096_P)),
+        _ => None,
+    }
+}
+
+pub fn get_generator_prime<R: RngCore + CryptoRng + ?Sized>(bits: u64, num: i32, rng: &mut R) -> (BigInt, BigInt, Vec<BigInt>) {
     let mut g_list: Vec<BigInt> = vec!(); // Initialize an empty vector to store generator values
     let n: BigInt = BigInt::one() << bits; // Calculate 2 raised to the power of bits
-    let q: BigInt = n_bit_prime(bits - 1); // Generate a prime number q with (bits - 1) bits
+    let q: BigInt = n_bit_prime(bits - 1, rng); // Generate a prime number q with (bits - 1) bits
     
     let mut p: BigInt = BigInt::zero(); // Initialize p with zero
-    let mut rng = rand::thread_rng(); // Initialize a random number generator
 
     // Generate a prime number p such that p = k * q + 1 using a loop
-    while !miller_rabin_primality_test(&p, 5) {
+    while !miller_rabin_primality_test(&p, 5, rng) {
         let k = rng.gen_bigint_range(&BigInt::one(), &n); // Generate a random integer k in the range [1, n)
         p = k * &q + 1; // Calculate p = k * q + 1
     }
@@ -66,13 +493,18 @@ pub fn get_generator_prime(bits: u64, num: i32) -> (BigInt, BigInt, Vec<BigInt>)
 #[cfg(test)]
 mod tests{
     use num_bigint::BigInt;
-    use crate::zkrypto::generator::{get_default, get_debug, get_generator_prime}; // Adjust the paths accordingly
+    use crate::zkrypto::generator::{
+        get_default, get_debug, get_generator_prime, get_named, NAMED_GROUPS,
+        derive_generator, verify_generator, generator_from_seed, verify_seeded, group_seed, INDEX_G, INDEX_H,
+        Generator, ValidationFailure, ValidationParams,
+    }; // Adjust the paths accordingly
+    use crate::zkrypto::chaum_pedersen::{ChaumPedersenProver, ChaumPedersenVerifier};
     use crate::zkrypto::prime::miller_rabin_primality_test;
     
     #[test]
     fn test_get_default() {
         let generator = get_default();
-        assert_eq!(generator.p, BigInt::from(421360559 as i64));
+        assert_eq!(generator.p, BigInt::from(421360559));
         // Add assertions for other fields
     }
     
@@ -85,14 +517,113 @@ mod tests{
     
     #[test]
     fn test_get_generator_prime() {
+        let mut rng = rand::thread_rng();
         let bits = 64;
         let num = 3;
-        let (p, q, g_list) = get_generator_prime(bits, num);
+        let (p, q, g_list) = get_generator_prime(bits, num, &mut rng);
         
         // Add assertions to validate the generated values
-        assert!(miller_rabin_primality_test(&p, 5));
-        assert!(miller_rabin_primality_test(&q, 5));
+        assert!(miller_rabin_primality_test(&p, 5, &mut rng));
+        assert!(miller_rabin_primality_test(&q, 5, &mut rng));
         assert_eq!(g_list.len(), num as usize);
     }
-    
-}
\ No newline at end of file
+
+    #[test]
+    fn test_named_groups() {
+        let mut rng = rand::thread_rng();
+        for name in NAMED_GROUPS {
+            let group = get_named(name).unwrap();
+            let params = ValidationParams { rounds: 2, min_p_bits: 0, min_q_bits: 0 };
+            assert_eq!(group.validate(&params, &mut rng), Ok(()), "{}", name);
+        }
+        assert_eq!(get_named("ffdhe2048").unwrap().p.bits(), 2048);
+        assert_eq!(get_named("ffdhe4096").unwrap().p.bits(), 4096);
+        assert_eq!(get_named("modp2048-256").unwrap().q.bits(), 256);
+        assert!(get_named("ffdhe1024").is_none());
+    }
+
+    #[test]
+    fn test_named_group_derivation_is_deterministic() {
+        assert_eq!(get_named("ffdhe2048"), get_named("ffdhe2048"));
+        assert_ne!(get_named("ffdhe2048").unwrap().h, get_named("modp2048").unwrap().h);
+    }
+
+    #[test]
+    fn test_protocol_over_named_group() {
+        let mut rng = rand::thread_rng();
+        let group = get_named("modp2048-256").unwrap();
+        let prover = ChaumPedersenProver::with_group(group.clone(), &mut rng);
+        let verifier = ChaumPedersenVerifier::with_group(group, prover.kp.y1.clone(), prover.kp.y2.clone()).unwrap();
+
+        let commitment = prover.prove_sync_a(&mut rng);
+        let challenge = verifier.verify_sync_a(commitment.r1().clone(), commitment.r2().clone(), &mut rng).unwrap();
+        let s = commitment.prove_sync_b(challenge.c());
+        assert!(verifier.verify_sync_b(challenge, s));
+    }
+
+    #[test]
+    fn test_named_groups_h_is_verifiable() {
+        for name in NAMED_GROUPS {
+            let group = get_named(name).unwrap();
+            assert!(verify_seeded(&group, &group_seed(name), false), "{}: h not derived from seed", name);
+            assert!(!verify_seeded(&group, &group_seed("other"), false), "{}: h verified for wrong seed", name);
+        }
+    }
+
+    #[test]
+    fn test_generator_from_seed() {
+        let mut rng = rand::thread_rng();
+        let (p, q, _) = get_generator_prime(64, 1, &mut rng);
+        let group = generator_from_seed(p.clone(), q.clone(), b"seed").unwrap();
+        assert_ne!(group.g, group.h);
+        assert!(verify_seeded(&group, b"seed", true));
+        assert!(!verify_seeded(&group, b"other seed", true));
+
+        // Swapping g and h or tampering with h breaks verification
+        let swapped = generator_from_seed(p.clone(), q.clone(), b"seed").map(|mut g| {
+            std::mem::swap(&mut g.g, &mut g.h);
+            g
+        }).unwrap();
+        assert!(!verify_seeded(&swapped, b"seed", true));
+        assert_eq!(derive_generator(&p, &q, b"seed", INDEX_H), Some(group.h.clone()));
+        assert!(!verify_generator(&p, &q, b"seed", INDEX_G, &group.h));
+        assert!(!verify_generator(&p, &q, b"seed", INDEX_H, &BigInt::from(1)));
+        assert!(!verify_generator(&p, &q, b"seed", INDEX_H, &(&group.h + &p)));
+    }
+
+    #[test]
+    fn test_validate_reports_failures() {
+        let mut rng = rand::thread_rng();
+        // The toy groups only fail the size floor
+        let err = get_default().validate(&ValidationParams::default(), &mut rng).unwrap_err();
+        assert_eq!(err.failures, vec![
+            ValidationFailure::PTooSmall { bits: 29, min: 2048 },
+            ValidationFailure::QTooSmall { bits: 19, min: 224 },
+        ]);
+
+        let small = ValidationParams { rounds: 5, min_p_bits: 0, min_q_bits: 0 };
+        let debug = get_debug();
+        assert_eq!(debug.validate(&small, &mut rng), Ok(()));
+
+        // Composite p, so q cannot divide p - 1 either
+        let bad_p = Generator { p: BigInt::from(369), ..debug.clone() };
+        let err = bad_p.validate(&small, &mut rng).unwrap_err();
+        assert!(err.failures.contains(&ValidationFailure::PNotPrime));
+        assert!(err.failures.contains(&ValidationFailure::QDoesNotDivideP));
+
+        // 2 generates the whole of Z_367^*, not the order-61 subgroup
+        let bad_g = Generator { g: BigInt::from(2), h: debug.g.clone(), ..debug.clone() };
+        assert_eq!(bad_g.validate(&small, &mut rng).unwrap_err().failures, vec![ValidationFailure::GWrongOrder]);
+
+        let bad_h = Generator { h: BigInt::from(1), ..debug.clone() };
+        assert_eq!(bad_h.validate(&small, &mut rng).unwrap_err().failures, vec![ValidationFailure::HWrongOrder]);
+
+        let same = Generator { h: debug.g.clone(), ..debug.clone() };
+        assert_eq!(same.validate(&small, &mut rng).unwrap_err().failures, vec![ValidationFailure::GEqualsH]);
+
+        let bad_q = Generator { q: BigInt::from(60), ..debug };
+        let err = bad_q.validate(&small, &mut rng).unwrap_err();
+        assert!(err.failures.contains(&ValidationFailure::QNotPrime));
+        assert!(err.to_string().starts_with("invalid group parameters: q is not prime"));
+    }
+}
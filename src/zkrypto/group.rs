@@ -0,0 +1,133 @@
+use std::fmt::{self, Debug};
+
+use num_bigint::BigInt;
+use rand::{CryptoRng, RngCore};
+use zeroize::Zeroize;
+
+use crate::zkrypto::encoding::EncodingError;
+
+// Reasons a received element is rejected by Group::check_element
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum MembershipError {
+    OutOfRange,    // Element is the identity, or an integer outside [2, p - 1]
+    NotInSubgroup, // Element lies outside the prime-order subgroup
+}
+
+impl fmt::Display for MembershipError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            MembershipError::OutOfRange => write!(f, "degenerate or out of range element"),
+            MembershipError::NotInSubgroup => write!(f, "element not in the prime-order subgroup"),
+        }
+    }
+}
+
+impl std::error::Error for MembershipError {}
+
+// A MembershipError together with the name of the offending value
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ElementError {
+    pub name: &'static str,
+    pub error: MembershipError,
+}
+
+impl fmt::Display for ElementError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}: {}", self.name, self.error)
+    }
+}
+
+impl std::error::Error for ElementError {}
+
+// Prime-order group the Chaum-Pedersen protocol runs in, together with the two
+// generators g and h over which the statement log_g(y1) = log_h(y2) is proven.
+// Elements are written multiplicatively; scalars are integers modulo the group order.
+pub trait Group: Clone {
+    type Element: Clone + PartialEq + Debug;
+    type Scalar: Clone + PartialEq + Debug + Default + Zeroize;
+
+    // First generator g
+    fn g(&self) -> Self::Element;
+
+    // Second generator h, whose discrete log with respect to g must be unknown
+    fn h(&self) -> Self::Element;
+
+    // Neutral element of the group
+    fn identity(&self) -> Self::Element;
+
+    // Order q of the group (and modulus of the scalars)
+    fn order(&self) -> BigInt;
+
+    // Checks that a received element is a non-trivial member of the prime-order group,
+    // ruling out small-subgroup and degenerate-key attacks
+    fn check_element(&self, e: &Self::Element) -> Result<(), MembershipError>;
+
+    // Like check_element, naming the checked value in the error
+    fn check_named(&self, name: &'static str, e: &Self::Element) -> Result<(), ElementError> {
+        self.check_element(e).map_err(|error| ElementError { name, error })
+    }
+
+    // Computes base^e, possibly in variable time; only for public exponents
+    fn exp(&self, base: &Self::Element, e: &Self::Scalar) -> Self::Element;
+
+    // Computes base^e in constant time, for secret exponents such as keys and nonces.
+    // Backends whose exp is already constant time keep this default.
+    fn exp_secret(&self, base: &Self::Element, e: &Self::Scalar) -> Self::Element {
+        self.exp(base, e)
+    }
+
+    // Computes a * b
+    fn mul(&self, a: &Self::Element, b: &Self::Element) -> Self::Element;
+
+    // Computes the product of bases[i]^exponents[i], possibly in variable time; only for public
+    // exponents. The default multiplies separate exponentiations.
+    fn multi_exp(&self, bases: &[Self::Element], exponents: &[Self::Scalar]) -> Self::Element {
+        assert_eq!(bases.len(), exponents.len(), "multi_exp needs one exponent per base");
+        bases.iter().zip(exponents).fold(self.identity(), |acc, (b, e)| self.mul(&acc, &self.exp(b, e)))
+    }
+
+    // Samples a scalar uniformly from Z_q, as used for challenges
+    fn random_scalar<R: RngCore + CryptoRng + ?Sized>(&self, rng: &mut R) -> Self::Scalar;
+
+    // Samples a scalar uniformly from Z_q minus zero, as used for secret keys and nonces
+    fn random_nonzero_scalar<R: RngCore + CryptoRng + ?Sized>(&self, rng: &mut R) -> Self::Scalar;
+
+    // Computes a + b mod q
+    fn scalar_add(&self, a: &Self::Scalar, b: &Self::Scalar) -> Self::Scalar;
+
+    // Computes a - b mod q
+    fn scalar_sub(&self, a: &Self::Scalar, b: &Self::Scalar) -> Self::Scalar;
+
+    // Computes a * b mod q
+    fn scalar_mul(&self, a: &Self::Scalar, b: &Self::Scalar) -> Self::Scalar;
+
+    // Computes a^-1 mod q, or None for zero
+    fn scalar_inv(&self, a: &Self::Scalar) -> Option<Self::Scalar>;
+
+    // Reduces a non-negative integer modulo q into a scalar
+    fn scalar_from_bigint(&self, x: &BigInt) -> Self::Scalar;
+
+    // Maps a hash digest to a scalar statistically close to uniform in Z_q
+    fn scalar_from_hash(&self, digest: &[u8]) -> Self::Scalar;
+
+    // Canonical byte length of an encoded element
+    fn element_len(&self) -> usize;
+
+    // Canonical byte length of an encoded scalar
+    fn scalar_len(&self) -> usize;
+
+    // Encodes an element into exactly element_len() bytes
+    fn encode_element(&self, e: &Self::Element) -> Vec<u8>;
+
+    // Decodes an element, rejecting non-canonical encodings
+    fn decode_element(&self, bytes: &[u8]) -> Result<Self::Element, EncodingError>;
+
+    // Encodes a scalar into exactly scalar_len() bytes
+    fn encode_scalar(&self, s: &Self::Scalar) -> Vec<u8>;
+
+    // Decodes a scalar, rejecting non-canonical encodings
+    fn decode_scalar(&self, bytes: &[u8]) -> Result<Self::Scalar, EncodingError>;
+
+    // Unambiguous encoding of the group parameters, bound into Fiat-Shamir challenges
+    fn encode_parameters(&self) -> Vec<u8>;
+}
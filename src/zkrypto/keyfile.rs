@@ -0,0 +1,492 @@
+use std::fmt;
+use std::fs;
+use std::io::{self, Write};
+use std::path::Path;
+
+use chacha20poly1305::aead::{Aead, KeyInit, Payload};
+use chacha20poly1305::{XChaCha20Poly1305, XNonce};
+use rand::{CryptoRng, RngCore};
+use sha2::{Digest, Sha256};
+use zeroize::Zeroize;
+
+use crate::zkrypto::chaum_pedersen::KeyPair;
+use crate::zkrypto::encoding::EncodingError;
+use crate::zkrypto::group::{ElementError, Group};
+use crate::zkrypto::password::{self, KdfError, KdfParams};
+use crate::zkrypto::scalar::Secret;
+
+// Leading bytes of every key file
+const MAGIC: &[u8] = b"ZKPK";
+
+// Version written by this implementation; files with any other version are rejected
+pub const VERSION: u8 = 1;
+
+// Lengths of the XChaCha20-Poly1305 key and nonce protecting encrypted secrets
+const KEY_LEN: usize = 32;
+const NONCE_LEN: usize = 24;
+
+// Key file layout, version 1. Variable-length fields are prefixed with their length as a
+// big-endian u32, and the Argon2id costs are big-endian u32s.
+//
+//   "ZKPK" | version | kind | group name | SHA-256(group parameters) | y1 | y2
+//   kind 0: nothing else, the file only holds public keys
+//   kind 1: x
+//   kind 2: salt | memory_kib | iterations | parallelism | nonce | XChaCha20-Poly1305(x)
+//
+// The encryption key is the Argon2id hash of the password, and everything before the
+// ciphertext is authenticated as associated data, so the public part cannot be swapped.
+
+// What a key file holds besides the public keys
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum KeyKind {
+    Public = 0,          // Public keys only
+    Secret = 1,          // The secret in the clear
+    EncryptedSecret = 2, // The secret encrypted under a password
+}
+
+// Public keys of an identity, as read from a key file
+#[derive(Clone, Debug, PartialEq)]
+pub struct PublicKey<G: Group> {
+    pub y1: G::Element,
+    pub y2: G::Element,
+}
+
+// Errors raised while reading or writing a key file
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum KeyFileError {
+    Truncated,                          // The file ends in the middle of a field
+    BadMagic,                           // The file does not start with the key file magic
+    UnsupportedVersion(u8),             // The file was written in an unknown version of the format
+    UnknownKind(u8),                    // The kind byte is not a KeyKind
+    TrailingBytes,                      // Bytes follow the last field
+    GroupMismatch,                      // The file was written for different group parameters
+    Field(&'static str, EncodingError), // A key could not be decoded
+    Element(ElementError),              // A public key is degenerate or outside the subgroup
+    NoSecret,                           // The file only holds public keys
+    PasswordRequired,                   // The secret is encrypted and no password was given
+    Decryption,                         // Wrong password, or the file was modified
+    KeyMismatch,                        // The secret does not match the public keys
+    Kdf(KdfError),                      // The stored Argon2id parameters are unusable or exceed the ceiling
+}
+
+impl fmt::Display for KeyFileError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            KeyFileError::Truncated => write!(f, "key file is truncated"),
+            KeyFileError::BadMagic => write!(f, "not a key file"),
+            KeyFileError::UnsupportedVersion(v) => write!(f, "unsupported key file version {}", v),
+            KeyFileError::UnknownKind(k) => write!(f, "unknown key file kind {}", k),
+            KeyFileError::TrailingBytes => write!(f, "unexpected bytes after the last field"),
+            KeyFileError::GroupMismatch => write!(f, "key file was written for different group parameters"),
+            KeyFileError::Field(field, e) => write!(f, "invalid {}: {}", field, e),
+            KeyFileError::Element(e) => write!(f, "invalid {}", e),
+            KeyFileError::NoSecret => write!(f, "key file holds no secret key"),
+            KeyFileError::PasswordRequired => write!(f, "secret key is encrypted and needs a password"),
+            KeyFileError::Decryption => write!(f, "wrong password or corrupted key file"),
+            KeyFileError::KeyMismatch => write!(f, "secret key does not match the public keys"),
+            KeyFileError::Kdf(e) => write!(f, "{}", e),
+        }
+    }
+}
+
+impl std::error::Error for KeyFileError {}
+
+impl From<KdfError> for KeyFileError {
+    fn from(e: KdfError) -> KeyFileError {
+        KeyFileError::Kdf(e)
+    }
+}
+
+// Hash identifying the group parameters a key belongs to
+fn fingerprint<G: Group>(group: &G) -> Vec<u8> {
+    Sha256::digest(group.encode_parameters()).to_vec()
+}
+
+// Appends a length-prefixed field
+fn put(out: &mut Vec<u8>, field: &[u8]) {
+    out.extend_from_slice(&(field.len() as u32).to_be_bytes());
+    out.extend_from_slice(field);
+}
+
+// Writes the fields shared by every kind of key file
+fn header<G: Group>(group: &G, name: &str, kind: KeyKind, y1: &G::Element, y2: &G::Element) -> Vec<u8> {
+    let mut out = MAGIC.to_vec();
+    out.push(VERSION);
+    out.push(kind as u8);
+    for field in [name.as_bytes(), &fingerprint(group), &group.encode_element(y1), &group.encode_element(y2)] {
+        put(&mut out, field);
+    }
+    out
+}
+
+// Exports the public keys of an identity in the group called name, e.g. to hand to a verifier
+pub fn export_public_key<G: Group>(group: &G, name: &str, y1: &G::Element, y2: &G::Element) -> Vec<u8> {
+    header(group, name, KeyKind::Public, y1, y2)
+}
+
+// Exports a key pair with its secret in the clear; the file must be kept private
+pub fn export_keypair<G: Group>(group: &G, name: &str, kp: &KeyPair<G>) -> Vec<u8> {
+    let mut out = header(group, name, KeyKind::Secret, &kp.y1, &kp.y2);
+    let mut x = group.encode_scalar(kp.secret().expose());
+    put(&mut out, &x);
+    x.zeroize();
+    out
+}
+
+// Exports a key pair with its secret encrypted under a key stretched from the password
+pub fn export_encrypted_keypair<G: Group, R: RngCore + CryptoRng + ?Sized>(
+    group: &G,
+    name: &str,
+    kp: &KeyPair<G>,
+    password: &[u8],
+    params: &KdfParams,
+    rng: &mut R,
+) -> Result<Vec<u8>, KeyFileError> {
+    let salt = password::generate_salt(rng);
+    let mut key = [0u8; KEY_LEN];
+    password::stretch(password, &salt, params, &mut key)?;
+    let mut nonce = [0u8; NONCE_LEN];
+    rng.fill_bytes(&mut nonce);
+
+    let mut out = header(group, name, KeyKind::EncryptedSecret, &kp.y1, &kp.y2);
+    put(&mut out, &salt);
+    for cost in [params.memory_kib, params.iterations, params.parallelism] {
+        out.extend_from_slice(&cost.to_be_bytes());
+    }
+    put(&mut out, &nonce);
+
+    let mut x = group.encode_scalar(kp.secret().expose());
+    let ciphertext = XChaCha20Poly1305::new(&key.into())
+        .encrypt(XNonce::from_slice(&nonce), Payload { msg: &x, aad: &out })
+        .expect("encryption of a short message cannot fail");
+    x.zeroize();
+    key.zeroize();
+    put(&mut out, &ciphertext);
+    Ok(out)
+}
+
+// Reads the fields of a key file in order
+struct Reader<'a> {
+    bytes: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> Reader<'a> {
+    fn take(&mut self, len: usize) -> Result<&'a [u8], KeyFileError> {
+        let end = self.pos.checked_add(len).filter(|&end| end <= self.bytes.len()).ok_or(KeyFileError::Truncated)?;
+        let out = &self.bytes[self.pos..end];
+        self.pos = end;
+        Ok(out)
+    }
+
+    fn u32(&mut self) -> Result<u32, KeyFileError> {
+        Ok(u32::from_be_bytes(self.take(4)?.try_into().expect("took 4 bytes")))
+    }
+
+    fn field(&mut self) -> Result<&'a [u8], KeyFileError> {
+        let len = self.u32()? as usize;
+        self.take(len)
+    }
+}
+
+// How the secret of a parsed key file is stored
+enum StoredSecret<'a> {
+    None,
+    Clear(&'a [u8]),
+    Encrypted {
+        salt: &'a [u8],
+        params: KdfParams,
+        nonce: &'a [u8],
+        aad: &'a [u8], // Everything before the ciphertext
+        ciphertext: &'a [u8],
+    },
+}
+
+// A key file split into its fields, before any of them is decoded in a group
+struct Parsed<'a> {
+    group: String,
+    fingerprint: &'a [u8],
+    y1: &'a [u8],
+    y2: &'a [u8],
+    secret: StoredSecret<'a>,
+}
+
+fn parse(bytes: &[u8]) -> Result<Parsed<'_>, KeyFileError> {
+    let mut r = Reader { bytes, pos: 0 };
+    if r.take(MAGIC.len()).map_err(|_| KeyFileError::BadMagic)? != MAGIC {
+        return Err(KeyFileError::BadMagic);
+    }
+    let version = r.take(1)?[0];
+    if version != VERSION {
+        return Err(KeyFileError::UnsupportedVersion(version));
+    }
+    let kind = r.take(1)?[0];
+    let group = String::from_utf8_lossy(r.field()?).into_owned();
+    let (fingerprint, y1, y2) = (r.field()?, r.field()?, r.field()?);
+    let secret = match kind {
+        k if k == KeyKind::Public as u8 => StoredSecret::None,
+        k if k == KeyKind::Secret as u8 => StoredSecret::Clear(r.field()?),
+        k if k == KeyKind::EncryptedSecret as u8 => {
+            let salt = r.field()?;
+            let params = KdfParams { memory_kib: r.u32()?, iterations: r.u32()?, parallelism: r.u32()? };
+            // Refused here, so a crafted file cannot make the importer allocate or spin without bound
+            params.check()?;
+            let nonce = r.field()?;
+            let aad = &bytes[..r.pos];
+            StoredSecret::Encrypted { salt, params, nonce, aad, ciphertext: r.field()? }
+        }
+        k => return Err(KeyFileError::UnknownKind(k)),
+    };
+    if r.pos != bytes.len() {
+        return Err(KeyFileError::TrailingBytes);
+    }
+    Ok(Parsed { group, fingerprint, y1, y2, secret })
+}
+
+// Returns the name of the group a key file was written for, so the caller can load it
+pub fn key_file_group(bytes: &[u8]) -> Result<String, KeyFileError> {
+    Ok(parse(bytes)?.group)
+}
+
+// Decodes and checks the public keys of a parsed file in the given group
+fn decode_public<G: Group>(group: &G, parsed: &Parsed) -> Result<PublicKey<G>, KeyFileError> {
+    if parsed.fingerprint != fingerprint(group).as_slice() {
+        return Err(KeyFileError::GroupMismatch);
+    }
+    let y1 = group.decode_element(parsed.y1).map_err(|e| KeyFileError::Field("y1", e))?;
+    let y2 = group.decode_element(parsed.y2).map_err(|e| KeyFileError::Field("y2", e))?;
+    group.check_named("y1", &y1).map_err(KeyFileError::Element)?;
+    group.check_named("y2", &y2).map_err(KeyFileError::Element)?;
+    Ok(PublicKey { y1, y2 })
+}
+
+// Imports the public keys from any kind of key file
+pub fn import_public_key<G: Group>(group: &G, bytes: &[u8]) -> Result<PublicKey<G>, KeyFileError> {
+    decode_public(group, &parse(bytes)?)
+}
+
+// Imports a key pair, decrypting its secret with the password if it is encrypted.
+// The secret is checked against the stored public keys.
+pub fn import_keypair<G: Group>(group: &G, bytes: &[u8], password: Option<&[u8]>) -> Result<KeyPair<G>, KeyFileError> {
+    let parsed = parse(bytes)?;
+    let public = decode_public(group, &parsed)?;
+    let mut x = match parsed.secret {
+        StoredSecret::None => return Err(KeyFileError::NoSecret),
+        StoredSecret::Clear(x) => x.to_vec(),
+        StoredSecret::Encrypted { salt, params, nonce, aad, ciphertext } => {
+            let password = password.ok_or(KeyFileError::PasswordRequired)?;
+            if nonce.len() != NONCE_LEN {
+                return Err(KeyFileError::Decryption);
+            }
+            let mut key = [0u8; KEY_LEN];
+            password::stretch(password, salt, &params, &mut key)?;
+            let x = XChaCha20Poly1305::new(&key.into())
+                .decrypt(XNonce::from_slice(nonce), Payload { msg: ciphertext, aad })
+                .map_err(|_| KeyFileError::Decryption);
+            key.zeroize();
+            x?
+        }
+    };
+    let decoded = group.decode_scalar(&x).map_err(|e| KeyFileError::Field("x", e));
+    x.zeroize();
+    let x = Secret::new(decoded?);
+    if x.expose() == &G::Scalar::default() {
+        return Err(KeyFileError::KeyMismatch);
+    }
+    let kp = KeyPair::from_secret(group, x);
+    match kp.y1 == public.y1 && kp.y2 == public.y2 {
+        true => Ok(kp),
+        false => Err(KeyFileError::KeyMismatch),
+    }
+}
+
+// Writes a key file, readable and writable only by its owner on Unix. The mode passed to open only
+// applies to new files, so an existing file is also restricted before the secret is written.
+pub fn write_key_file(path: &Path, bytes: &[u8]) -> io::Result<()> {
+    let mut options = fs::OpenOptions::new();
+    options.write(true).create(true).truncate(true);
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::OpenOptionsExt;
+        options.mode(0o600);
+    }
+    let mut file = options.open(path)?;
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        file.set_permissions(fs::Permissions::from_mode(0o600))?;
+    }
+    file.write_all(bytes)
+}
+
+#[cfg(test)]
+mod test {
+    use crate::zkrypto::chaum_pedersen::{ChaumPedersenProver, ChaumPedersenVerifier};
+    use crate::zkrypto::encoding::EncodingError;
+    use crate::zkrypto::generator::{get_default, get_named};
+    use crate::zkrypto::password::{KdfError, KdfParams};
+    use crate::zkrypto::ristretto::RistrettoGroup;
+    use super::{
+        export_encrypted_keypair, export_keypair, export_public_key, import_keypair, import_public_key,
+        key_file_group, write_key_file, KeyFileError, VERSION,
+    };
+
+    // Cheap parameters so the tests run quickly
+    const TEST_PARAMS: KdfParams = KdfParams { memory_kib: 64, iterations: 1, parallelism: 1 };
+
+    #[test]
+    fn test_public_key_roundtrip() {
+        let group = get_default();
+        let prover = ChaumPedersenProver::with_group(group.clone(), &mut rand::thread_rng());
+        let bytes = export_public_key(&group, "default", &prover.kp.y1, &prover.kp.y2);
+        assert_eq!(key_file_group(&bytes), Ok("default".to_string()));
+
+        let public = import_public_key(&group, &bytes).unwrap();
+        assert_eq!((&public.y1, &public.y2), (&prover.kp.y1, &prover.kp.y2));
+        assert_eq!(import_keypair(&group, &bytes, None).map(|_| ()), Err(KeyFileError::NoSecret));
+    }
+
+    #[test]
+    fn test_keypair_roundtrip() {
+        let mut rng = rand::thread_rng();
+        let group = RistrettoGroup::new();
+        let prover = ChaumPedersenProver::with_group(group.clone(), &mut rng);
+        let bytes = export_keypair(&group, "ristretto255", &prover.kp);
+        let kp = import_keypair(&group, &bytes, None).unwrap();
+        assert_eq!(kp.secret().expose(), prover.kp.secret().expose());
+
+        // The public part of a full key file is enough for a verifier
+        let public = import_public_key(&group, &bytes).unwrap();
+        let verifier = ChaumPedersenVerifier::with_group(group.clone(), public.y1, public.y2).unwrap();
+        let restored = ChaumPedersenProver::with_keypair(group, kp);
+        assert!(verifier.verify(&restored.prove(b"login", &mut rng), b"login"));
+    }
+
+    #[test]
+    fn test_encrypted_keypair_roundtrip() {
+        let mut rng = rand::thread_rng();
+        let group = get_default();
+        let prover = ChaumPedersenProver::with_group(group.clone(), &mut rng);
+        let bytes = export_encrypted_keypair(&group, "default", &prover.kp, b"hunter2", &TEST_PARAMS, &mut rng).unwrap();
+
+        let kp = import_keypair(&group, &bytes, Some(b"hunter2")).unwrap();
+        assert_eq!(kp.secret().expose(), prover.kp.secret().expose());
+        assert_eq!(import_keypair(&group, &bytes, Some(b"hunter3")).map(|_| ()), Err(KeyFileError::Decryption));
+        assert_eq!(import_keypair(&group, &bytes, None).map(|_| ()), Err(KeyFileError::PasswordRequired));
+        assert_eq!(import_public_key(&group, &bytes).unwrap().y1, prover.kp.y1);
+
+        // The public part is authenticated, so editing the group name or the ciphertext breaks decryption
+        let mut tampered = bytes.clone();
+        tampered[12] ^= 1;
+        assert_eq!(import_keypair(&group, &tampered, Some(b"hunter2")).map(|_| ()), Err(KeyFileError::Decryption));
+        let mut tampered = bytes.clone();
+        *tampered.last_mut().unwrap() ^= 1;
+        assert_eq!(import_keypair(&group, &tampered, Some(b"hunter2")).map(|_| ()), Err(KeyFileError::Decryption));
+    }
+
+    #[test]
+    fn test_rejects_malformed_files() {
+        let mut rng = rand::thread_rng();
+        let group = get_default();
+        let prover = ChaumPedersenProver::with_group(group.clone(), &mut rng);
+        let bytes = export_keypair(&group, "default", &prover.kp);
+
+        assert_eq!(import_public_key(&group, b"ZK").map(|_| ()), Err(KeyFileError::BadMagic));
+        assert_eq!(import_public_key(&group, b"not a key file").map(|_| ()), Err(KeyFileError::BadMagic));
+        assert_eq!(import_public_key(&group, &bytes[..bytes.len() - 1]).map(|_| ()), Err(KeyFileError::Truncated));
+        let trailing = [bytes.as_slice(), &[0]].concat();
+        assert_eq!(import_public_key(&group, &trailing).map(|_| ()), Err(KeyFileError::TrailingBytes));
+
+        let mut future = bytes.clone();
+        future[4] = VERSION + 1;
+        assert_eq!(import_public_key(&group, &future).map(|_| ()), Err(KeyFileError::UnsupportedVersion(VERSION + 1)));
+        let mut unknown = bytes.clone();
+        unknown[5] = 9;
+        assert_eq!(import_public_key(&group, &unknown).map(|_| ()), Err(KeyFileError::UnknownKind(9)));
+
+        let other = get_named("ffdhe2048").unwrap();
+        assert_eq!(import_keypair(&other, &bytes, None).map(|_| ()), Err(KeyFileError::GroupMismatch));
+        let ristretto = RistrettoGroup::new();
+        assert_eq!(import_keypair(&ristretto, &bytes, None).map(|_| ()), Err(KeyFileError::GroupMismatch));
+    }
+
+    #[test]
+    fn test_rejects_oversized_kdf_params() {
+        let mut rng = rand::thread_rng();
+        let group = get_default();
+        let prover = ChaumPedersenProver::with_group(group.clone(), &mut rng);
+        let bytes = export_encrypted_keypair(&group, "default", &prover.kp, b"hunter2", &TEST_PARAMS, &mut rng).unwrap();
+
+        // Raise memory_kib in place; the file is refused when parsed, before any password is stretched
+        let stored = [64u32, 1, 1].iter().flat_map(|v| v.to_be_bytes()).collect::<Vec<_>>();
+        let pos = bytes.windows(stored.len()).position(|w| w == stored.as_slice()).unwrap();
+        let mut oversized = bytes.clone();
+        oversized[pos..pos + 4].copy_from_slice(&(u32::MAX >> 1).to_be_bytes());
+        let params = KdfParams { memory_kib: u32::MAX >> 1, ..TEST_PARAMS };
+        let expected = Err(KeyFileError::Kdf(KdfError::AboveCeiling(params)));
+        assert_eq!(import_keypair(&group, &oversized, Some(b"hunter2")).map(|_| ()), expected);
+        assert_eq!(import_public_key(&group, &oversized).map(|_| ()), expected);
+    }
+
+    #[test]
+    fn test_rejects_mismatched_secret() {
+        let mut rng = rand::thread_rng();
+        let group = get_default();
+        let alice = ChaumPedersenProver::with_group(group.clone(), &mut rng);
+        let bob = ChaumPedersenProver::with_group(group.clone(), &mut rng);
+
+        // Splice bob's secret after alice's public keys
+        let public = export_public_key(&group, "default", &alice.kp.y1, &alice.kp.y2);
+        let full = export_keypair(&group, "default", &bob.kp);
+        let mut spliced = [public.as_slice(), &full[public.len()..]].concat();
+        spliced[5] = 1;
+        assert_eq!(import_keypair(&group, &spliced, None).map(|_| ()), Err(KeyFileError::KeyMismatch));
+
+        // A secret of the wrong length is rejected when decoded
+        let mut short = full[..full.len() - 1].to_vec();
+        let len = short.len() - public.len() - 4;
+        short.splice(public.len()..public.len() + 4, (len as u32).to_be_bytes());
+        assert!(matches!(
+            import_keypair(&group, &short, None),
+            Err(KeyFileError::Field("x", EncodingError::InvalidLength { .. }))
+        ));
+    }
+
+    #[test]
+    fn test_key_file_survives_restart() {
+        let mut rng = rand::thread_rng();
+        let group = RistrettoGroup::new();
+        let prover = ChaumPedersenProver::with_group(group.clone(), &mut rng);
+        let bytes = export_encrypted_keypair(&group, "ristretto255", &prover.kp, b"pw", &TEST_PARAMS, &mut rng).unwrap();
+
+        let path = std::env::temp_dir().join(format!("zkp-keyfile-{}.key", std::process::id()));
+        write_key_file(&path, &bytes).unwrap();
+        let loaded = std::fs::read(&path).unwrap();
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            assert_eq!(std::fs::metadata(&path).unwrap().permissions().mode() & 0o777, 0o600);
+        }
+        std::fs::remove_file(&path).unwrap();
+
+        let kp = import_keypair(&group, &loaded, Some(b"pw")).unwrap();
+        assert_eq!(kp.y1, prover.kp.y1);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_overwritten_key_file_is_private() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let path = std::env::temp_dir().join(format!("zkp-keyfile-{}-overwrite.key", std::process::id()));
+        std::fs::write(&path, b"public").unwrap();
+        std::fs::set_permissions(&path, std::fs::Permissions::from_mode(0o644)).unwrap();
+
+        write_key_file(&path, b"secret").unwrap();
+        let mode = std::fs::metadata(&path).unwrap().permissions().mode() & 0o777;
+        let loaded = std::fs::read(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(mode, 0o600);
+        assert_eq!(loaded, b"secret");
+    }
+}
@@ -1,3 +1,17 @@
 pub mod prime;
 pub mod generator;
-pub mod chaum_pedersen;
\ No newline at end of file
+pub mod chaum_pedersen;
+pub mod encoding;
+pub mod group;
+pub mod ristretto;
+pub mod scalar;
+pub mod montgomery;
+pub mod nonce;
+pub mod password;
+pub mod keyfile;
+pub mod batch;
+pub mod multiexp;
+pub mod schnorr;
+pub mod composition;
+#[cfg(any(test, feature = "test-utils"))]
+pub mod testing;
\ No newline at end of file
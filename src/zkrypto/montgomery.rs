@@ -0,0 +1,219 @@
+use num_bigint::{BigInt, Sign};
+use num_traits::{One, Signed};
+
+// Window width of the fixed-window exponentiation
+const WINDOW: usize = 4;
+
+// Number of precomputed powers base^0 .. base^(2^WINDOW - 1)
+const TABLE_SIZE: usize = 1 << WINDOW;
+
+#[cfg(test)]
+thread_local! {
+    // Montgomery multiplications and table scans performed on this thread, for the timing harness
+    static OPERATIONS: std::cell::Cell<u64> = const { std::cell::Cell::new(0) };
+}
+
+// Counts one multiplication or table scan when the timing harness is compiled in
+#[inline(always)]
+fn count_operation() {
+    #[cfg(test)]
+    OPERATIONS.with(|ops| ops.set(ops.get() + 1));
+}
+
+// Returns all ones if a == b and zero otherwise, without branching on either value
+fn ct_eq_mask(a: usize, b: usize) -> u64 {
+    let d = (a ^ b) as u64;
+    ((d | d.wrapping_neg()) >> 63).wrapping_sub(1)
+}
+
+// Montgomery arithmetic modulo an odd p, over fixed-length little-endian 64-bit limbs.
+// Every operation runs the same instruction sequence whatever the values involved.
+pub struct Montgomery {
+    modulus: BigInt, // p
+    p: Vec<u64>,     // Limbs of p
+    n0: u64,         // -p^-1 mod 2^64
+    r2: Vec<u64>,    // R^2 mod p, where R = 2^(64 * limbs)
+    one: Vec<u64>,   // R mod p, i.e. 1 in Montgomery form
+}
+
+impl Montgomery {
+    // Creates the context for an odd modulus p > 1
+    pub fn new(p: &BigInt) -> Montgomery {
+        assert!(p.is_positive() && p.bit(0) && !p.is_one(), "Montgomery modulus must be odd and above 1");
+        let limbs = p.magnitude().to_u64_digits();
+        let n = limbs.len();
+
+        // Newton iteration for p^-1 mod 2^64; each step doubles the number of correct bits
+        let mut inv: u64 = 1;
+        for _ in 0..6 {
+            inv = inv.wrapping_mul(2u64.wrapping_sub(limbs[0].wrapping_mul(inv)));
+        }
+
+        let r: BigInt = BigInt::one() << (64 * n);
+        Montgomery {
+            n0: inv.wrapping_neg(),
+            r2: to_limbs(&((&r * &r) % p), n),
+            one: to_limbs(&(&r % p), n),
+            modulus: p.clone(),
+            p: limbs,
+        }
+    }
+
+    // Computes a * b * R^-1 mod p with coarsely integrated operand scanning
+    pub fn mul(&self, a: &[u64], b: &[u64]) -> Vec<u64> {
+        count_operation();
+        let n = self.p.len();
+        let mut t = vec![0u64; n + 2];
+        for &bi in b.iter().take(n) {
+            let mut carry: u64 = 0;
+            for j in 0..n {
+                let v = t[j] as u128 + (a[j] as u128) * (bi as u128) + carry as u128;
+                t[j] = v as u64;
+                carry = (v >> 64) as u64;
+            }
+            let v = t[n] as u128 + carry as u128;
+            t[n] = v as u64;
+            t[n + 1] = (v >> 64) as u64;
+
+            let m = t[0].wrapping_mul(self.n0);
+            let v = t[0] as u128 + (m as u128) * (self.p[0] as u128);
+            let mut carry = (v >> 64) as u64;
+            for j in 1..n {
+                let v = t[j] as u128 + (m as u128) * (self.p[j] as u128) + carry as u128;
+                t[j - 1] = v as u64;
+                carry = (v >> 64) as u64;
+            }
+            let v = t[n] as u128 + carry as u128;
+            t[n - 1] = v as u64;
+            t[n] = t[n + 1] + (v >> 64) as u64;
+        }
+
+        // t < 2p, so one subtraction of p suffices; it is always computed and selected by mask
+        let mut d = vec![0u64; n];
+        let mut borrow: u64 = 0;
+        for j in 0..n {
+            let (x, b1) = t[j].overflowing_sub(self.p[j]);
+            let (x, b2) = x.overflowing_sub(borrow);
+            d[j] = x;
+            borrow = (b1 | b2) as u64;
+        }
+        let (_, under) = t[n].overflowing_sub(borrow);
+        let keep_t = (under as u64).wrapping_neg();
+        (0..n).map(|j| (t[j] & keep_t) | (d[j] & !keep_t)).collect()
+    }
+
+    // Reads table[index] by touching every entry, so the memory access pattern hides the index
+    fn lookup(&self, table: &[Vec<u64>], index: usize) -> Vec<u64> {
+        count_operation();
+        let mut out = vec![0u64; self.p.len()];
+        for (i, entry) in table.iter().enumerate() {
+            let mask = ct_eq_mask(i, index);
+            for (o, &x) in out.iter_mut().zip(entry) {
+                *o |= x & mask;
+            }
+        }
+        out
+    }
+
+    // Converts x into Montgomery form x * R mod p
+    pub fn to_montgomery(&self, x: &BigInt) -> Vec<u64> {
+        let x = to_limbs(&x.modpow(&BigInt::one(), &self.modulus), self.p.len());
+        self.mul(&x, &self.r2)
+    }
+
+    // Converts a value in Montgomery form back into [0, p)
+    pub fn from_montgomery(&self, a: &[u64]) -> BigInt {
+        let mut unit = vec![0u64; self.p.len()];
+        unit[0] = 1;
+        let digits: Vec<u32> = self.mul(a, &unit).iter().flat_map(|&l| [l as u32, (l >> 32) as u32]).collect();
+        BigInt::new(Sign::Plus, digits)
+    }
+
+    // Computes base^e mod p for a secret exponent e in [0, 2^exp_bits), using a fixed 4-bit window.
+    // The sequence of squarings, multiplications and table scans depends only on exp_bits.
+    pub fn pow(&self, base: &BigInt, e: &BigInt, exp_bits: u64) -> BigInt {
+        let mut table = Vec::with_capacity(TABLE_SIZE);
+        table.push(self.one.clone());
+        table.push(self.to_montgomery(base));
+        for i in 2..TABLE_SIZE {
+            let next = self.mul(&table[i - 1], &table[1]);
+            table.push(next);
+        }
+
+        let windows = (exp_bits as usize).div_ceil(WINDOW);
+        let e = to_limbs(e, (windows * WINDOW).div_ceil(64).max(1));
+        let mut acc = self.one.clone();
+        for w in (0..windows).rev() {
+            for _ in 0..WINDOW {
+                acc = self.mul(&acc, &acc);
+            }
+            let bit = w * WINDOW;
+            let digit = ((e[bit / 64] >> (bit % 64)) & (TABLE_SIZE as u64 - 1)) as usize;
+            let entry = self.lookup(&table, digit);
+            acc = self.mul(&acc, &entry);
+        }
+
+        self.from_montgomery(&acc)
+    }
+}
+
+// Writes a non-negative x into exactly n little-endian limbs
+fn to_limbs(x: &BigInt, n: usize) -> Vec<u64> {
+    let mut limbs = x.magnitude().to_u64_digits();
+    limbs.resize(n, 0);
+    limbs
+}
+
+#[cfg(test)]
+mod test {
+    use num_bigint::{BigInt, RandBigInt};
+    use num_traits::One;
+
+    use crate::zkrypto::generator::{get_debug, get_default, get_named};
+    use super::{Montgomery, OPERATIONS};
+
+    // Runs f and returns how many multiplications and table scans it performed
+    fn count<T>(f: impl FnOnce() -> T) -> (T, u64) {
+        OPERATIONS.with(|ops| ops.set(0));
+        let out = f();
+        (out, OPERATIONS.with(|ops| ops.get()))
+    }
+
+    #[test]
+    fn test_matches_modpow() {
+        let mut rng = rand::thread_rng();
+        for group in [get_debug(), get_default(), get_named("ffdhe2048").unwrap()] {
+            let mont = Montgomery::new(&group.p);
+            for _ in 0..4 {
+                let e = rng.gen_bigint_range(&BigInt::from(0), &group.q);
+                let base = rng.gen_bigint_range(&BigInt::from(0), &group.p);
+                assert_eq!(mont.pow(&base, &e, group.q.bits()), base.modpow(&e, &group.p));
+            }
+            let q_minus_one: BigInt = &group.q - 1u32;
+            assert_eq!(mont.pow(&group.g, &q_minus_one, group.q.bits()), group.g.modpow(&q_minus_one, &group.p));
+            assert_eq!(mont.pow(&group.g, &BigInt::from(0), group.q.bits()), BigInt::one());
+        }
+    }
+
+    #[test]
+    fn test_operation_count_is_independent_of_exponent() {
+        let group = get_named("modp2048-256").unwrap();
+        let mont = Montgomery::new(&group.p);
+        let bits = group.q.bits();
+        let mut rng = rand::thread_rng();
+        let exponents = [
+            BigInt::from(0),
+            BigInt::one(),
+            BigInt::one() << (bits - 1),
+            &group.q - 1u32,
+            rng.gen_bigint_range(&BigInt::from(0), &group.q),
+            rng.gen_bigint_range(&BigInt::from(0), &group.q),
+        ];
+        let counts: Vec<u64> = exponents.iter().map(|e| count(|| mont.pow(&group.g, e, bits)).1).collect();
+        assert!(counts.iter().all(|&c| c == counts[0]), "operation counts differ: {:?}", counts);
+
+        // 15 table entries, 4 squarings, a lookup and a multiplication per window, and the final conversion
+        let windows = bits.div_ceil(4);
+        assert_eq!(counts[0], 15 + windows * 6 + 1);
+    }
+}
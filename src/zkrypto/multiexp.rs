@@ -0,0 +1,212 @@
+use num_bigint::BigInt;
+use num_traits::{One, Signed};
+
+use crate::zkrypto::montgomery::Montgomery;
+
+// Number of terms from which Pippenger's bucket method beats Straus' interleaved windows
+pub const PIPPENGER_THRESHOLD: usize = 64;
+
+// Computes the product of bases[i]^exponents[i] mod an odd prime p for non-negative exponents,
+// in variable time, picking Straus for few terms and Pippenger for many. The products are
+// taken in Montgomery form, which avoids a division per multiplication.
+pub fn multi_exp(bases: &[BigInt], exponents: &[BigInt], p: &BigInt) -> BigInt {
+    match bases.len() < PIPPENGER_THRESHOLD {
+        true => straus(bases, exponents, p),
+        false => pippenger(bases, exponents, p),
+    }
+}
+
+// Computes a * b in Montgomery form, treating None as the identity so that no multiplication by 1 is made
+fn mul(mont: &Montgomery, a: Option<Vec<u64>>, b: &[u64]) -> Vec<u64> {
+    match a {
+        None => b.to_vec(),
+        Some(a) => mont.mul(&a, b),
+    }
+}
+
+// Squares acc width times
+fn square(mont: &Montgomery, acc: &mut Option<Vec<u64>>, width: usize) {
+    if let Some(a) = acc.as_mut() {
+        for _ in 0..width {
+            *a = mont.mul(a, a);
+        }
+    }
+}
+
+// Converts the accumulated product back into an integer
+fn finish(mont: &Montgomery, acc: Option<Vec<u64>>) -> BigInt {
+    acc.map_or_else(BigInt::one, |a| mont.from_montgomery(&a))
+}
+
+// Little-endian limbs of each exponent, together with the bit length of the largest
+fn limbs(bases: &[BigInt], exponents: &[BigInt]) -> (Vec<Vec<u64>>, usize) {
+    assert_eq!(bases.len(), exponents.len(), "multi_exp needs one exponent per base");
+    assert!(exponents.iter().all(|e| !e.is_negative()), "multi_exp needs non-negative exponents");
+    let bits = exponents.iter().map(|e| e.bits()).max().unwrap_or(0) as usize;
+    (exponents.iter().map(|e| e.magnitude().to_u64_digits()).collect(), bits)
+}
+
+// Reads the width-bit digit of an exponent starting at the given bit
+fn digit(limbs: &[u64], bit: usize, width: usize) -> usize {
+    let (i, shift) = (bit / 64, bit % 64);
+    let mut d = limbs.get(i).map_or(0, |l| l >> shift);
+    if shift + width > 64 {
+        d |= limbs.get(i + 1).map_or(0, |l| l << (64 - shift));
+    }
+    (d & ((1 << width) - 1)) as usize
+}
+
+// Window width for Straus, growing with the exponent length so the tables pay for themselves
+fn straus_window(bits: usize) -> usize {
+    match bits {
+        0..=32 => 2,
+        33..=128 => 3,
+        129..=768 => 4,
+        _ => 5,
+    }
+}
+
+// Window width for Pippenger, about log2(n) - 1 so the buckets cost no more than the terms
+fn pippenger_window(n: usize) -> usize {
+    ((usize::BITS - n.leading_zeros()) as usize).saturating_sub(2).clamp(2, 16)
+}
+
+// Straus' (Shamir's) trick: the exponents are scanned together in w-bit windows from the top,
+// sharing one chain of squarings, with a table of base^1 .. base^(2^w - 1) for every base
+pub fn straus(bases: &[BigInt], exponents: &[BigInt], p: &BigInt) -> BigInt {
+    let (limbs, bits) = limbs(bases, exponents);
+    let mont = Montgomery::new(p);
+    let w = straus_window(bits);
+    let tables: Vec<Vec<Vec<u64>>> = bases
+        .iter()
+        .map(|b| {
+            let mut table = vec![mont.to_montgomery(b)];
+            for d in 1..(1 << w) - 1 {
+                let next = mont.mul(&table[d - 1], &table[0]);
+                table.push(next);
+            }
+            table
+        })
+        .collect();
+
+    let mut acc = None;
+    for window in (0..bits.div_ceil(w)).rev() {
+        square(&mont, &mut acc, w);
+        for (table, limbs) in tables.iter().zip(&limbs) {
+            let d = digit(limbs, window * w, w);
+            if d != 0 {
+                acc = Some(mul(&mont, acc, &table[d - 1]));
+            }
+        }
+    }
+    finish(&mont, acc)
+}
+
+// Pippenger's bucket method: in every c-bit window each base is multiplied into the bucket of
+// its digit, and prod_j bucket_j^j is formed with running products, so a window costs about
+// n + 2^(c+1) multiplications however many terms there are
+pub fn pippenger(bases: &[BigInt], exponents: &[BigInt], p: &BigInt) -> BigInt {
+    let (limbs, bits) = limbs(bases, exponents);
+    let mont = Montgomery::new(p);
+    let c = pippenger_window(bases.len());
+    let bases: Vec<Vec<u64>> = bases.iter().map(|b| mont.to_montgomery(b)).collect();
+
+    let mut acc = None;
+    for window in (0..bits.div_ceil(c)).rev() {
+        square(&mont, &mut acc, c);
+        let mut buckets: Vec<Option<Vec<u64>>> = vec![None; (1 << c) - 1];
+        for (b, limbs) in bases.iter().zip(&limbs) {
+            let d = digit(limbs, window * c, c);
+            if d != 0 {
+                buckets[d - 1] = Some(mul(&mont, buckets[d - 1].take(), b));
+            }
+        }
+
+        // After bucket j, running holds the product of buckets j and above, so multiplying
+        // every running value into sum raises bucket j to the power j
+        let (mut running, mut sum) = (None, None);
+        for bucket in buckets.into_iter().rev() {
+            if let Some(b) = bucket {
+                running = Some(mul(&mont, running, &b));
+            }
+            if let Some(r) = &running {
+                sum = Some(mul(&mont, sum, r));
+            }
+        }
+        if let Some(s) = sum {
+            acc = Some(mul(&mont, acc, &s));
+        }
+    }
+    finish(&mont, acc)
+}
+
+#[cfg(test)]
+mod test {
+    use num_bigint::{BigInt, RandBigInt};
+    use num_traits::One;
+    use rand::rngs::StdRng;
+    use rand::{Rng, SeedableRng};
+
+    use crate::zkrypto::generator::{get_debug, get_named, Generator};
+    use super::{digit, multi_exp, pippenger, straus, PIPPENGER_THRESHOLD};
+
+    fn naive(bases: &[BigInt], exponents: &[BigInt], p: &BigInt) -> BigInt {
+        bases.iter().zip(exponents).fold(BigInt::one(), |acc, (b, e)| acc * b.modpow(e, p) % p)
+    }
+
+    // Random subgroup elements and exponents in [0, q), with the extreme exponents mixed in
+    fn terms(group: &Generator, n: usize, rng: &mut StdRng) -> (Vec<BigInt>, Vec<BigInt>) {
+        let q_minus_one: BigInt = &group.q - 1u32;
+        let bases = (0..n).map(|_| group.g.modpow(&rng.gen_bigint_range(&BigInt::one(), &group.q), &group.p)).collect();
+        let exponents = (0..n)
+            .map(|i| match i % 7 {
+                0 => BigInt::from(0),
+                1 => BigInt::one(),
+                2 => q_minus_one.clone(),
+                3 => BigInt::from(rng.gen_range(0u64..1 << 20)),
+                _ => rng.gen_bigint_range(&BigInt::from(0), &group.q),
+            })
+            .collect();
+        (bases, exponents)
+    }
+
+    #[test]
+    fn test_matches_naive_product() {
+        let mut rng = StdRng::seed_from_u64(20);
+        for group in [get_debug(), get_named("modp2048-256").unwrap()] {
+            for n in [0, 1, 2, 3, 4, 7, 16, 33, PIPPENGER_THRESHOLD - 1, PIPPENGER_THRESHOLD] {
+                let (bases, exponents) = terms(&group, n, &mut rng);
+                let expected = naive(&bases, &exponents, &group.p);
+                assert_eq!(straus(&bases, &exponents, &group.p), expected, "straus, n = {}", n);
+                assert_eq!(pippenger(&bases, &exponents, &group.p), expected, "pippenger, n = {}", n);
+                assert_eq!(multi_exp(&bases, &exponents, &group.p), expected, "multi_exp, n = {}", n);
+            }
+        }
+    }
+
+    #[test]
+    fn test_arbitrary_bases_and_exponents() {
+        // Bases anywhere in [0, p) and exponents longer than q, as the routine is not tied to the subgroup
+        let mut rng = StdRng::seed_from_u64(21);
+        let p = get_named("modp2048-256").unwrap().p;
+        for n in [1, 5, PIPPENGER_THRESHOLD + 2] {
+            let bases: Vec<BigInt> = (0..n).map(|_| rng.gen_bigint_range(&BigInt::from(0), &p)).collect();
+            let exponents: Vec<BigInt> = (0..n).map(|_| rng.gen_biguint(800).into()).collect();
+            let expected = naive(&bases, &exponents, &p);
+            assert_eq!(straus(&bases, &exponents, &p), expected);
+            assert_eq!(pippenger(&bases, &exponents, &p), expected);
+        }
+        let all_zero = vec![BigInt::from(0); 3];
+        assert_eq!(multi_exp(&all_zero, &all_zero, &p), BigInt::one());
+    }
+
+    #[test]
+    fn test_digits_cross_limbs() {
+        let limbs = [0xF000_0000_0000_0001u64, 0x5];
+        assert_eq!(digit(&limbs, 0, 4), 1);
+        assert_eq!(digit(&limbs, 60, 4), 0xF);
+        assert_eq!(digit(&limbs, 62, 4), 0b0111);
+        assert_eq!(digit(&limbs, 64, 3), 0b101);
+        assert_eq!(digit(&limbs, 128, 5), 0);
+    }
+}
@@ -0,0 +1,216 @@
+use hmac::{Hmac, Mac};
+use num_bigint::{BigInt, Sign};
+use num_traits::One;
+use rand::{CryptoRng, RngCore};
+use sha2::{Digest, Sha256};
+use zeroize::Zeroize;
+
+use crate::zkrypto::encoding;
+use crate::zkrypto::group::Group;
+use crate::zkrypto::scalar::Secret;
+
+// Domain separation tag for the hash of the public inputs bound into a nonce
+const NONCE_DOMAIN: &[u8] = b"zkp_auth/nonce/v1";
+
+// Bytes of fresh randomness mixed into hedged nonces
+const HEDGE_LEN: usize = 32;
+
+// How the prover obtains the nonce k of each proof
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum NonceMode {
+    #[default]
+    Random,        // Sampled from the system RNG
+    Deterministic, // Derived from the key, group, public keys and context with HMAC-DRBG; Fiat-Shamir only
+    Hedged,        // Derived like Deterministic, with fresh randomness mixed into the seed
+}
+
+impl NonceMode {
+    // Mode used for interactive commitments. A deterministic nonce would repeat whenever a login
+    // context repeats and leak x once answered for two challenges, so it is hedged there instead.
+    pub fn interactive(self) -> NonceMode {
+        match self {
+            NonceMode::Deterministic => NonceMode::Hedged,
+            mode => mode,
+        }
+    }
+}
+
+type HmacSha256 = Hmac<Sha256>;
+
+// HMAC-DRBG with SHA-256 as instantiated by RFC 6979 section 3.2
+pub struct HmacDrbg {
+    k: [u8; 32],
+    v: [u8; 32],
+}
+
+impl HmacDrbg {
+    // Instantiates the generator from the seed material, steps b to g of RFC 6979 3.2
+    pub fn new(seed: &[u8]) -> HmacDrbg {
+        let mut drbg = HmacDrbg { k: [0u8; 32], v: [1u8; 32] };
+        drbg.k = drbg.hmac(&[&drbg.v, &[0x00], seed]);
+        drbg.v = drbg.hmac(&[&drbg.v]);
+        drbg.k = drbg.hmac(&[&drbg.v, &[0x01], seed]);
+        drbg.v = drbg.hmac(&[&drbg.v]);
+        drbg
+    }
+
+    fn hmac(&self, parts: &[&[u8]]) -> [u8; 32] {
+        let mut mac = HmacSha256::new_from_slice(&self.k).expect("HMAC accepts any key length");
+        for part in parts {
+            mac.update(part);
+        }
+        mac.finalize().into_bytes().into()
+    }
+
+    // Produces the next len bytes of output
+    pub fn fill(&mut self, len: usize) -> Vec<u8> {
+        let mut out = Vec::with_capacity(len + 32);
+        while out.len() < len {
+            self.v = self.hmac(&[&self.v]);
+            out.extend_from_slice(&self.v);
+        }
+        out
+    }
+
+    // Updates the state after a rejected candidate, step h.3 of RFC 6979 3.2
+    pub fn reject(&mut self) {
+        self.k = self.hmac(&[&self.v, &[0x00]]);
+        self.v = self.hmac(&[&self.v]);
+    }
+
+    // Draws integers of q's bit length until one falls in [1, q)
+    pub fn generate(&mut self, q: &BigInt) -> BigInt {
+        let qlen = q.bits();
+        let rlen = encoding::byte_len(q);
+        loop {
+            let mut t = self.fill(rlen);
+            let candidate = BigInt::from_bytes_be(Sign::Plus, &t) >> (t.len() as u64 * 8 - qlen);
+            t.zeroize();
+            if candidate >= BigInt::one() && &candidate < q {
+                return candidate;
+            }
+            self.reject();
+        }
+    }
+}
+
+impl Drop for HmacDrbg {
+    fn drop(&mut self) {
+        self.k.zeroize();
+        self.v.zeroize();
+    }
+}
+
+// Derives the nonce for a proof by key x in the given group. The public inputs (the public
+// elements of the statement and the context) are hashed into h1 and the DRBG is seeded with
+// x || h1 (|| extra), following RFC 6979 with the message hash replaced by the public inputs.
+// Passing fresh randomness as extra hedges against fault attacks. With a broken RNG the nonce only
+// stays safe if the context is unique per proof, e.g. a login nonce the verifier accepts once.
+pub fn derive_nonce<G: Group>(
+    group: &G,
+    x: &Secret<G::Scalar>,
+    public: &[&G::Element],
+    context: &[u8],
+    extra: Option<&[u8]>,
+) -> Secret<G::Scalar> {
+    let mut hasher = Sha256::new();
+    let mut absorb = |part: &[u8]| {
+        hasher.update((part.len() as u64).to_be_bytes());
+        hasher.update(part);
+    };
+    absorb(NONCE_DOMAIN);
+    absorb(&group.encode_parameters());
+    for e in public {
+        absorb(&group.encode_element(e));
+    }
+    absorb(context);
+    let mut seed = group.encode_scalar(x.expose());
+    seed.extend_from_slice(&hasher.finalize());
+    if let Some(extra) = extra {
+        seed.extend_from_slice(extra);
+    }
+    let k = HmacDrbg::new(&seed).generate(&group.order());
+    seed.zeroize();
+    Secret::new(group.scalar_from_bigint(&k))
+}
+
+// Derives the nonce for the given mode, drawing fresh randomness from rng where the mode asks for it
+pub fn nonce<G: Group, R: RngCore + CryptoRng + ?Sized>(
+    mode: NonceMode,
+    group: &G,
+    x: &Secret<G::Scalar>,
+    public: &[&G::Element],
+    context: &[u8],
+    rng: &mut R,
+) -> Secret<G::Scalar> {
+    match mode {
+        NonceMode::Random => Secret::new(group.random_nonzero_scalar(rng)),
+        NonceMode::Deterministic => derive_nonce(group, x, public, context, None),
+        NonceMode::Hedged => {
+            let mut extra = [0u8; HEDGE_LEN];
+            rng.fill_bytes(&mut extra);
+            let k = derive_nonce(group, x, public, context, Some(&extra));
+            extra.zeroize();
+            k
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use num_bigint::{BigInt, Sign};
+    use num_traits::Num;
+    use sha2::{Digest, Sha256};
+
+    use crate::zkrypto::encoding::encode;
+    use crate::zkrypto::generator::get_debug;
+    use crate::zkrypto::group::Group;
+    use crate::zkrypto::ristretto::RistrettoGroup;
+    use crate::zkrypto::scalar::Secret;
+    use super::{derive_nonce, nonce, HmacDrbg, NonceMode};
+
+    #[test]
+    fn test_rfc6979_vector() {
+        // RFC 6979 A.1.2: the 163-bit example curve order with SHA-256 over "sample"
+        let q = BigInt::from_str_radix("4000000000000000000020108A2E0CC0D99F8A5EF", 16).unwrap();
+        let x = BigInt::from_str_radix("09A4D6792295A7F730FC3F2B49CBC0F62E862272F", 16).unwrap();
+        let h1 = BigInt::from_bytes_be(Sign::Plus, &Sha256::digest(b"sample")) >> (256 - 163);
+        let seed = [encode(&x, &q).unwrap(), encode(&(h1 % &q), &q).unwrap()].concat();
+        let k = HmacDrbg::new(&seed).generate(&q);
+        assert_eq!(k, BigInt::from_str_radix("23AF4074C90A02B3FE61D286D5C87F425E6BDD81B", 16).unwrap());
+    }
+
+    #[test]
+    fn test_deterministic_nonces() {
+        let mut rng = rand::thread_rng();
+        let group = get_debug();
+        let x = Secret::new(group.random_nonzero_scalar(&mut rng));
+        let y1 = group.exp(&group.g(), x.expose());
+        let y2 = group.exp(&group.h(), x.expose());
+
+        let k = |context: &[u8]| derive_nonce(&group, &x, &[&y1, &y2], context, None).expose().clone();
+        assert_eq!(k(b"login 1"), k(b"login 1"));
+        assert!(!k(b"login 1").is_zero() && k(b"login 1").value() < &group.q);
+        let distinct: std::collections::HashSet<_> = (0u32..20).map(|i| k(&i.to_be_bytes())).collect();
+        assert!(distinct.len() > 10);
+
+        // Fresh randomness changes the nonce
+        let hedged: std::collections::HashSet<_> = (0..20)
+            .map(|_| nonce(NonceMode::Hedged, &group, &x, &[&y1, &y2], b"login 1", &mut rng).expose().clone())
+            .collect();
+        assert!(hedged.len() > 10);
+    }
+
+    #[test]
+    fn test_deterministic_nonces_over_ristretto() {
+        let mut rng = rand::thread_rng();
+        let group = RistrettoGroup::new();
+        let x = Secret::new(group.random_nonzero_scalar(&mut rng));
+        let (y1, y2) = (group.exp(&group.g(), x.expose()), group.exp(&group.h(), x.expose()));
+        let a = derive_nonce(&group, &x, &[&y1, &y2], b"context", None);
+        let b = derive_nonce(&group, &x, &[&y1, &y2], b"context", None);
+        let c = derive_nonce(&group, &x, &[&y1, &y2], b"other context", None);
+        assert_eq!(a.expose(), b.expose());
+        assert_ne!(a.expose(), c.expose());
+    }
+}
@@ -0,0 +1,235 @@
+use std::fmt;
+
+use argon2::{Algorithm, Argon2, Params, Version};
+use rand::{CryptoRng, RngCore};
+use sha2::{Digest, Sha256};
+use zeroize::Zeroize;
+
+use crate::zkrypto::group::Group;
+use crate::zkrypto::scalar::Secret;
+
+// Domain separation tag for the hash binding the user and group into the Argon2id salt
+const PASSWORD_DOMAIN: &[u8] = b"zkp_auth/password/v1";
+
+// Length of the salts generated at registration, and the shortest salt accepted
+pub const SALT_LEN: usize = 16;
+
+// Bytes of Argon2id output reduced into Z_q, so the reduction bias is negligible in every group
+const OUTPUT_LEN: usize = 64;
+
+// Argon2id cost parameters, stored by the server next to the salt
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct KdfParams {
+    pub memory_kib: u32,  // Memory cost in KiB
+    pub iterations: u32,  // Number of passes over the memory
+    pub parallelism: u32, // Number of lanes
+}
+
+// The OWASP baseline for Argon2id: 19 MiB, two passes and one lane
+impl Default for KdfParams {
+    fn default() -> KdfParams {
+        KdfParams {
+            memory_kib: Params::DEFAULT_M_COST,
+            iterations: Params::DEFAULT_T_COST,
+            parallelism: Params::DEFAULT_P_COST,
+        }
+    }
+}
+
+// Costliest parameters ever derived with, so that parameters sent by a server or read from a key
+// file cannot make the client allocate or spin without bound: 1 GiB, ten passes and eight lanes
+pub const MAX_PARAMS: KdfParams = KdfParams { memory_kib: 1 << 20, iterations: 10, parallelism: 8 };
+
+// Errors raised while deriving a secret from a password
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum KdfError {
+    SaltTooShort { len: usize, min: usize }, // The salt has fewer than SALT_LEN bytes
+    BelowFloor(KdfParams),                   // The parameters are cheaper than the OWASP baseline
+    AboveCeiling(KdfParams),                 // The parameters exceed MAX_PARAMS
+    Argon2(argon2::Error),                   // Argon2id rejected the parameters or inputs
+}
+
+impl fmt::Display for KdfError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            KdfError::SaltTooShort { len, min } => write!(f, "salt has {} bytes, expected at least {}", len, min),
+            KdfError::BelowFloor(p) => write!(f, "parameters {:?} are below the floor {:?}", p, KdfParams::default()),
+            KdfError::AboveCeiling(p) => write!(f, "parameters {:?} exceed the ceiling {:?}", p, MAX_PARAMS),
+            KdfError::Argon2(e) => write!(f, "argon2id: {}", e),
+        }
+    }
+}
+
+impl std::error::Error for KdfError {}
+
+impl KdfParams {
+    // Checks the parameters against the limits of Argon2id and the ceiling MAX_PARAMS, which
+    // every derivation enforces; clients call it on parameters they did not choose
+    pub fn check(&self) -> Result<(), KdfError> {
+        self.argon2(OUTPUT_LEN).map(|_| ())
+    }
+
+    // Checks the parameters as check does, and also against the floor of the OWASP baseline that
+    // the server requires of new registrations
+    pub fn check_registration(&self) -> Result<(), KdfError> {
+        let floor = KdfParams::default();
+        if self.memory_kib < floor.memory_kib || self.iterations < floor.iterations || self.parallelism < floor.parallelism {
+            return Err(KdfError::BelowFloor(*self));
+        }
+        self.check()
+    }
+
+    fn argon2(&self, output_len: usize) -> Result<Argon2<'static>, KdfError> {
+        if self.memory_kib > MAX_PARAMS.memory_kib || self.iterations > MAX_PARAMS.iterations || self.parallelism > MAX_PARAMS.parallelism {
+            return Err(KdfError::AboveCeiling(*self));
+        }
+        let params = Params::new(self.memory_kib, self.iterations, self.parallelism, Some(output_len))
+            .map_err(KdfError::Argon2)?;
+        Ok(Argon2::new(Algorithm::Argon2id, Version::V0x13, params))
+    }
+}
+
+// Fills out with the Argon2id hash of the password under the given salt and parameters
+pub fn stretch(password: &[u8], salt: &[u8], params: &KdfParams, out: &mut [u8]) -> Result<(), KdfError> {
+    if salt.len() < SALT_LEN {
+        return Err(KdfError::SaltTooShort { len: salt.len(), min: SALT_LEN });
+    }
+    params.argon2(out.len())?.hash_password_into(password, salt, out).map_err(KdfError::Argon2)
+}
+
+// Draws a fresh salt for a registration
+pub fn generate_salt<R: RngCore + CryptoRng + ?Sized>(rng: &mut R) -> Vec<u8> {
+    let mut salt = vec![0u8; SALT_LEN];
+    rng.fill_bytes(&mut salt);
+    salt
+}
+
+// Maps (username, password, salt, group) to a nonzero secret x with Argon2id.
+// The username and group parameters are hashed into the Argon2id salt together with the
+// stored salt, so the same password yields unrelated keys for different users and groups.
+pub fn derive_secret<G: Group>(
+    group: &G,
+    username: &str,
+    password: &[u8],
+    salt: &[u8],
+    params: &KdfParams,
+) -> Result<Secret<G::Scalar>, KdfError> {
+    if salt.len() < SALT_LEN {
+        return Err(KdfError::SaltTooShort { len: salt.len(), min: SALT_LEN });
+    }
+
+    let mut hasher = Sha256::new();
+    for part in [PASSWORD_DOMAIN, &group.encode_parameters(), username.as_bytes(), salt] {
+        hasher.update((part.len() as u64).to_be_bytes());
+        hasher.update(part);
+    }
+    let kdf_salt = hasher.finalize();
+
+    // The output and a trailing counter byte are hashed into Z_q, retrying in the unlikely event of zero
+    let mut input = [0u8; OUTPUT_LEN + 1];
+    stretch(password, &kdf_salt, params, &mut input[..OUTPUT_LEN])?;
+    let x = loop {
+        let x = group.scalar_from_hash(&input);
+        if x != G::Scalar::default() {
+            break x;
+        }
+        input[OUTPUT_LEN] += 1;
+    };
+    input.zeroize();
+    Ok(Secret::new(x))
+}
+
+#[cfg(test)]
+mod test {
+    use crate::zkrypto::chaum_pedersen::{ChaumPedersenProver, ChaumPedersenVerifier};
+    use crate::zkrypto::generator::{get_debug, get_default, get_named};
+    use crate::zkrypto::ristretto::RistrettoGroup;
+    use super::{derive_secret, generate_salt, KdfError, KdfParams, MAX_PARAMS, SALT_LEN};
+
+    // Cheap parameters so the tests run quickly
+    const TEST_PARAMS: KdfParams = KdfParams { memory_kib: 64, iterations: 1, parallelism: 1 };
+
+    #[test]
+    fn test_derivation_is_deterministic() {
+        let group = get_default();
+        let salt = generate_salt(&mut rand::thread_rng());
+        let x = |user: &str, password: &[u8], salt: &[u8]| {
+            derive_secret(&group, user, password, salt, &TEST_PARAMS).unwrap().expose().clone()
+        };
+        assert_eq!(x("alice", b"hunter2", &salt), x("alice", b"hunter2", &salt));
+        assert_ne!(x("alice", b"hunter2", &salt), x("alice", b"hunter3", &salt));
+        assert_ne!(x("alice", b"hunter2", &salt), x("bob", b"hunter2", &salt));
+        assert_ne!(x("alice", b"hunter2", &salt), x("alice", b"hunter2", &[0u8; SALT_LEN]));
+    }
+
+    #[test]
+    fn test_derivation_depends_on_params_and_group() {
+        let salt = [7u8; SALT_LEN];
+        let other = KdfParams { iterations: 2, ..TEST_PARAMS };
+        let a = derive_secret(&get_default(), "alice", b"pw", &salt, &TEST_PARAMS).unwrap();
+        let b = derive_secret(&get_default(), "alice", b"pw", &salt, &other).unwrap();
+        assert_ne!(a.expose(), b.expose());
+
+        let c = derive_secret(&get_named("ffdhe2048").unwrap(), "alice", b"pw", &salt, &TEST_PARAMS).unwrap();
+        assert_ne!(a.expose(), c.expose());
+
+        // The toy group has a tiny q, which the derivation still maps to a nonzero scalar
+        for user in ["alice", "bob", "carol", "dave"] {
+            let x = derive_secret(&get_debug(), user, b"pw", &salt, &TEST_PARAMS).unwrap();
+            assert!(x.expose().value() < &get_debug().q && !x.expose().is_zero());
+        }
+    }
+
+    #[test]
+    fn test_derived_key_logs_in_twice() {
+        let mut rng = rand::thread_rng();
+        let group = RistrettoGroup::new();
+        let salt = generate_salt(&mut rng);
+        let x = derive_secret(&group, "alice", b"hunter2", &salt, &TEST_PARAMS).unwrap();
+        let registered = ChaumPedersenProver::with_secret(group.clone(), x);
+        let verifier = ChaumPedersenVerifier::with_group(group.clone(), registered.kp.y1, registered.kp.y2).unwrap();
+        drop(registered);
+
+        // A later login re-derives the same key from the stored salt and parameters
+        let x = derive_secret(&group, "alice", b"hunter2", &salt, &TEST_PARAMS).unwrap();
+        let prover = ChaumPedersenProver::with_secret(group.clone(), x);
+        assert!(verifier.verify(&prover.prove(b"login", &mut rng), b"login"));
+
+        let x = derive_secret(&group, "alice", b"wrong", &salt, &TEST_PARAMS).unwrap();
+        let impostor = ChaumPedersenProver::with_secret(group, x);
+        assert!(!verifier.verify(&impostor.prove(b"login", &mut rng), b"login"));
+    }
+
+    #[test]
+    fn test_rejects_bad_inputs() {
+        let group = get_default();
+        assert_eq!(
+            derive_secret(&group, "alice", b"pw", &[0u8; 8], &TEST_PARAMS).map(|_| ()),
+            Err(KdfError::SaltTooShort { len: 8, min: SALT_LEN })
+        );
+        let params = KdfParams { memory_kib: 1, ..TEST_PARAMS };
+        assert!(matches!(params.check(), Err(KdfError::Argon2(_))));
+        assert!(KdfParams::default().check().is_ok());
+    }
+
+    #[test]
+    fn test_enforces_floor_and_ceiling() {
+        // Registrations need the OWASP baseline, while cheaper parameters stay usable for derivation
+        assert!(KdfParams::default().check_registration().is_ok());
+        assert_eq!(TEST_PARAMS.check_registration(), Err(KdfError::BelowFloor(TEST_PARAMS)));
+        assert!(TEST_PARAMS.check().is_ok());
+
+        // Anything costlier than the ceiling is refused before Argon2id allocates its memory
+        assert!(MAX_PARAMS.check().is_ok());
+        for params in [
+            KdfParams { memory_kib: u32::MAX >> 1, ..KdfParams::default() },
+            KdfParams { iterations: MAX_PARAMS.iterations + 1, ..KdfParams::default() },
+            KdfParams { parallelism: MAX_PARAMS.parallelism + 1, ..KdfParams::default() },
+        ] {
+            assert_eq!(params.check(), Err(KdfError::AboveCeiling(params)));
+            assert_eq!(params.check_registration(), Err(KdfError::AboveCeiling(params)));
+            let derived = derive_secret(&get_default(), "alice", b"pw", &[7u8; SALT_LEN], &params);
+            assert_eq!(derived.map(|_| ()), Err(KdfError::AboveCeiling(params)));
+        }
+    }
+}
@@ -2,8 +2,9 @@
 use num_bigint::{BigInt, RandBigInt};
 use num_traits::{Zero, One};
 use num_iter;
+use rand::{CryptoRng, RngCore};
 
-pub fn miller_rabin_primality_test(n: &BigInt, k: i64) -> bool {
+pub fn miller_rabin_primality_test<R: RngCore + CryptoRng + ?Sized>(n: &BigInt, k: i64, rng: &mut R) -> bool {
     if n <= &BigInt::one() {
         return false;
     }
@@ -22,39 +23,37 @@ pub fn miller_rabin_primality_test(n: &BigInt, k: i64) -> bool {
 
     
     // Witness loop
-    let mut rng = rand::thread_rng();
     for _ in 0..k {
         // Choose a random number
 
         let a: BigInt = rng.gen_bigint_range(&BigInt::from(2), &n_);
 
-        let mut x: BigInt = a.modpow(&d, &n);  // Compute a^d % n
+        let mut x: BigInt = a.modpow(&d, n);  // Compute a^d % n
         if x.eq(&BigInt::one()) || x.eq(&n_) {
             continue;
         }
 
         // Check whether it is a squared base.
         for _ in num_iter::range(BigInt::zero(), &s - 1) {
-            x = x.modpow(&BigInt::from(2), &n.clone());  // Square x and take the modulo
+            x = x.modpow(&BigInt::from(2), n);  // Square x and take the modulo
             if x.eq(&n_) {
                 break;
             }
         }
-        if x.ne(&BigInt::from(n-1)){
+        if x.ne(&(n - 1)){
             return false;  // n is composite
         }
     }
 
-    return true;  // n is probably prime
+    true  // n is probably prime
 }
 
-pub fn n_bit_prime(bits: u64) -> BigInt {
-    let mut rng = rand::thread_rng();
+pub fn n_bit_prime<R: RngCore + CryptoRng + ?Sized>(bits: u64, rng: &mut R) -> BigInt {
     let mut candidate: BigInt;
     loop { // Instead of while true {}
         candidate = rng.gen_bigint(bits);
-        candidate = candidate | BigInt::one();
-        if miller_rabin_primality_test(&candidate, 5) {
+        candidate |= BigInt::one();
+        if miller_rabin_primality_test(&candidate, 5, rng) {
             return candidate;
         }
     }
@@ -63,23 +62,34 @@ pub fn n_bit_prime(bits: u64) -> BigInt {
 #[cfg(test)]
 mod test{
     use num_bigint::BigInt;
+    use rand::rngs::StdRng;
+    use rand::SeedableRng;
     use super::{miller_rabin_primality_test, n_bit_prime};
 
     #[test]
     fn test_miller_rabin_primality_test() {
         let prime = BigInt::from(29); // A prime number
         let non_prime = BigInt::from(30); // A non-prime number
+        let mut rng = rand::thread_rng();
 
-        assert!(miller_rabin_primality_test(&prime, 5));
-        assert!(!miller_rabin_primality_test(&non_prime, 5));
+        assert!(miller_rabin_primality_test(&prime, 5, &mut rng));
+        assert!(!miller_rabin_primality_test(&non_prime, 5, &mut rng));
     }
 
     #[test]
     fn test_n_bit_prime() {
         let bits = 32;
-        let prime = n_bit_prime(bits);
+        let mut rng = rand::thread_rng();
+        let prime = n_bit_prime(bits, &mut rng);
 
-        assert!(miller_rabin_primality_test(&prime, 5));
+        assert!(miller_rabin_primality_test(&prime, 5, &mut rng));
         //assert!(prime.bits() > bits);
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn test_seeded_rng_is_reproducible() {
+        let a = n_bit_prime(64, &mut StdRng::seed_from_u64(7));
+        let b = n_bit_prime(64, &mut StdRng::seed_from_u64(7));
+        assert_eq!(a, b);
+    }
+}
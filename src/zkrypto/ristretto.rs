@@ -0,0 +1,236 @@
+use curve25519_dalek::constants::RISTRETTO_BASEPOINT_POINT;
+use curve25519_dalek::ristretto::{CompressedRistretto, RistrettoPoint};
+use curve25519_dalek::scalar::Scalar;
+use curve25519_dalek::traits::{Identity, VartimeMultiscalarMul};
+use num_bigint::{BigInt, Sign};
+use rand::{CryptoRng, RngCore};
+use sha2::{Digest, Sha512};
+
+use crate::zkrypto::encoding::EncodingError;
+use crate::zkrypto::group::{Group, MembershipError};
+
+// Seed hashed to the curve to obtain the second generator h, so nobody knows log_g(h)
+const H_SEED: &[u8] = b"zkp_auth/ristretto255/h";
+
+// Byte length of compressed points and canonical scalars
+const ENCODED_LEN: usize = 32;
+
+// The prime-order Ristretto255 group, with g the standard basepoint and h hashed to the curve
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct RistrettoGroup {
+    h: RistrettoPoint,
+}
+
+impl RistrettoGroup {
+    // Creates the group, deriving h from a fixed public seed
+    pub fn new() -> RistrettoGroup {
+        RistrettoGroup {
+            h: RistrettoPoint::hash_from_bytes::<Sha512>(H_SEED),
+        }
+    }
+}
+
+impl Default for RistrettoGroup {
+    fn default() -> RistrettoGroup {
+        RistrettoGroup::new()
+    }
+}
+
+// Checks that a byte string has the fixed encoding length and copies it into an array
+fn to_array(bytes: &[u8]) -> Result<[u8; ENCODED_LEN], EncodingError> {
+    bytes.try_into().map_err(|_| EncodingError::InvalidLength {
+        expected: ENCODED_LEN,
+        actual: bytes.len(),
+    })
+}
+
+impl Group for RistrettoGroup {
+    type Element = RistrettoPoint;
+    type Scalar = Scalar;
+
+    fn g(&self) -> RistrettoPoint {
+        RISTRETTO_BASEPOINT_POINT
+    }
+
+    fn h(&self) -> RistrettoPoint {
+        self.h
+    }
+
+    fn identity(&self) -> RistrettoPoint {
+        RistrettoPoint::identity()
+    }
+
+    // l = 2^252 + 27742317777372353535851937790883648493
+    fn order(&self) -> BigInt {
+        let minus_one = (-Scalar::ONE).to_bytes();
+        BigInt::from_bytes_le(Sign::Plus, &minus_one) + 1
+    }
+
+    // Every decoded Ristretto point lies in the prime-order group, so only the identity is rejected
+    fn check_element(&self, e: &RistrettoPoint) -> Result<(), MembershipError> {
+        match e == &RistrettoPoint::identity() {
+            true => Err(MembershipError::OutOfRange),
+            false => Ok(()),
+        }
+    }
+
+    // Variable-base scalar multiplication in curve25519-dalek is constant time, so exp_secret keeps the default
+    fn exp(&self, base: &RistrettoPoint, e: &Scalar) -> RistrettoPoint {
+        base * e
+    }
+
+    fn mul(&self, a: &RistrettoPoint, b: &RistrettoPoint) -> RistrettoPoint {
+        a + b
+    }
+
+    // curve25519-dalek picks Straus or Pippenger depending on the number of terms
+    fn multi_exp(&self, bases: &[RistrettoPoint], exponents: &[Scalar]) -> RistrettoPoint {
+        assert_eq!(bases.len(), exponents.len(), "multi_exp needs one exponent per base");
+        RistrettoPoint::vartime_multiscalar_mul(exponents, bases)
+    }
+
+    // Reduces 512 random bits, as Scalar::random does, without requiring a sized RNG
+    fn random_scalar<R: RngCore + CryptoRng + ?Sized>(&self, rng: &mut R) -> Scalar {
+        let mut wide = [0u8; 64];
+        rng.fill_bytes(&mut wide);
+        Scalar::from_bytes_mod_order_wide(&wide)
+    }
+
+    fn random_nonzero_scalar<R: RngCore + CryptoRng + ?Sized>(&self, rng: &mut R) -> Scalar {
+        loop {
+            let s = self.random_scalar(rng);
+            if s != Scalar::ZERO {
+                return s;
+            }
+        }
+    }
+
+    fn scalar_add(&self, a: &Scalar, b: &Scalar) -> Scalar {
+        a + b
+    }
+
+    fn scalar_sub(&self, a: &Scalar, b: &Scalar) -> Scalar {
+        a - b
+    }
+
+    fn scalar_mul(&self, a: &Scalar, b: &Scalar) -> Scalar {
+        a * b
+    }
+
+    fn scalar_inv(&self, a: &Scalar) -> Option<Scalar> {
+        (a != &Scalar::ZERO).then(|| a.invert())
+    }
+
+    fn scalar_from_bigint(&self, x: &BigInt) -> Scalar {
+        let (_, mut bytes) = (x % self.order()).to_bytes_le();
+        bytes.resize(ENCODED_LEN, 0);
+        Scalar::from_bytes_mod_order(to_array(&bytes).expect("reduced scalars fit in 32 bytes"))
+    }
+
+    // Expands the digest to 512 bits before reducing so the result is close to uniform
+    fn scalar_from_hash(&self, digest: &[u8]) -> Scalar {
+        Scalar::from_hash(Sha512::new().chain_update(digest))
+    }
+
+    fn element_len(&self) -> usize {
+        ENCODED_LEN
+    }
+
+    fn scalar_len(&self) -> usize {
+        ENCODED_LEN
+    }
+
+    fn encode_element(&self, e: &RistrettoPoint) -> Vec<u8> {
+        e.compress().to_bytes().to_vec()
+    }
+
+    fn decode_element(&self, bytes: &[u8]) -> Result<RistrettoPoint, EncodingError> {
+        CompressedRistretto(to_array(bytes)?)
+            .decompress()
+            .ok_or(EncodingError::InvalidPoint)
+    }
+
+    fn encode_scalar(&self, s: &Scalar) -> Vec<u8> {
+        s.to_bytes().to_vec()
+    }
+
+    fn decode_scalar(&self, bytes: &[u8]) -> Result<Scalar, EncodingError> {
+        Option::from(Scalar::from_canonical_bytes(to_array(bytes)?)).ok_or(EncodingError::OutOfRange)
+    }
+
+    fn encode_parameters(&self) -> Vec<u8> {
+        [b"ristretto255".as_slice(), &self.encode_element(&self.h)].concat()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use num_bigint::BigInt;
+    use num_traits::One;
+
+    use crate::zkrypto::chaum_pedersen::{ChaumPedersenProver, ChaumPedersenVerifier};
+    use crate::zkrypto::encoding::EncodingError;
+    use crate::zkrypto::group::{ElementError, Group, MembershipError};
+    use super::RistrettoGroup;
+
+    #[test]
+    fn test_order() {
+        let l: BigInt = (BigInt::one() << 252)
+            + "27742317777372353535851937790883648493".parse::<BigInt>().unwrap();
+        assert_eq!(RistrettoGroup::new().order(), l);
+    }
+
+    #[test]
+    fn test_encoding_roundtrip() {
+        let mut rng = rand::thread_rng();
+        let group = RistrettoGroup::new();
+        let x = group.random_scalar(&mut rng);
+        let y = group.exp(&group.h(), &x);
+
+        let y_bytes = group.encode_element(&y);
+        let x_bytes = group.encode_scalar(&x);
+        assert_eq!(y_bytes.len(), 32);
+        assert_eq!(x_bytes.len(), 32);
+        assert_eq!(group.decode_element(&y_bytes), Ok(y));
+        assert_eq!(group.decode_scalar(&x_bytes), Ok(x));
+    }
+
+    #[test]
+    fn test_decoding_rejects_invalid() {
+        let group = RistrettoGroup::new();
+        assert_eq!(group.decode_element(&[0xff; 32]), Err(EncodingError::InvalidPoint));
+        assert_eq!(group.decode_scalar(&[0xff; 32]), Err(EncodingError::OutOfRange));
+        assert_eq!(
+            group.decode_element(&[0u8; 31]),
+            Err(EncodingError::InvalidLength { expected: 32, actual: 31 })
+        );
+    }
+
+    #[test]
+    fn test_chaum_pedersen_protocol() {
+        let mut rng = rand::thread_rng();
+        let prover = ChaumPedersenProver::with_group(RistrettoGroup::new(), &mut rng);
+        let verifier = ChaumPedersenVerifier::with_group(RistrettoGroup::new(), prover.kp.y1, prover.kp.y2).unwrap();
+
+        let commitment = prover.prove_sync_a(&mut rng);
+        let challenge = verifier.verify_sync_a(*commitment.r1(), *commitment.r2(), &mut rng).unwrap();
+        let s = commitment.prove_sync_b(challenge.c());
+        assert!(verifier.verify_sync_b(challenge, s));
+
+        let proof = prover.prove(b"ristretto", &mut rng);
+        assert!(verifier.verify(&proof, b"ristretto"));
+        assert!(!verifier.verify(&proof, b"other context"));
+    }
+
+    #[test]
+    fn test_rejects_identity() {
+        let mut rng = rand::thread_rng();
+        let group = RistrettoGroup::new();
+        let prover = ChaumPedersenProver::with_group(RistrettoGroup::new(), &mut rng);
+        let identity = group.identity();
+        assert_eq!(
+            ChaumPedersenVerifier::with_group(group, identity, prover.kp.y2).map(|_| ()),
+            Err(ElementError { name: "y1", error: MembershipError::OutOfRange })
+        );
+    }
+}
@@ -0,0 +1,191 @@
+use std::fmt;
+
+use num_bigint::{BigInt, RandBigInt, Sign};
+use num_traits::{One, Signed, Zero};
+use rand::{CryptoRng, RngCore};
+use sha2::{Digest, Sha256};
+use zeroize::Zeroize;
+
+use crate::zkrypto::encoding;
+
+// Extra bytes drawn when mapping a hash to Z_q, so the reduction bias is below 2^-128
+const HASH_EXTRA_BYTES: usize = 16;
+
+// An integer modulo q, always kept reduced into [0, q).
+// The modulus is not stored; arithmetic takes it explicitly, as the owning group knows q.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Hash)]
+pub struct Zq(BigInt);
+
+impl Zq {
+    // Reduces x into [0, q), following mod_floor semantics for negative values
+    pub fn new(x: &BigInt, q: &BigInt) -> Zq {
+        let r = x % q;
+        match r.is_negative() {
+            true => Zq(r + q),
+            false => Zq(r),
+        }
+    }
+
+    // Canonical representative in [0, q)
+    pub fn value(&self) -> &BigInt {
+        &self.0
+    }
+
+    pub fn is_zero(&self) -> bool {
+        self.0.is_zero()
+    }
+
+    // Samples uniformly from [0, q)
+    pub fn random<R: RngCore + CryptoRng + ?Sized>(q: &BigInt, rng: &mut R) -> Zq {
+        Zq(rng.gen_bigint_range(&BigInt::zero(), q))
+    }
+
+    // Samples uniformly from [1, q)
+    pub fn random_nonzero<R: RngCore + CryptoRng + ?Sized>(q: &BigInt, rng: &mut R) -> Zq {
+        Zq(rng.gen_bigint_range(&BigInt::one(), q))
+    }
+
+    // Expands a digest with SHA-256 in counter mode to 16 bytes more than q needs and
+    // reduces the result, so the output is statistically close to uniform in Z_q
+    pub fn from_hash(digest: &[u8], q: &BigInt) -> Zq {
+        let len = encoding::byte_len(q) + HASH_EXTRA_BYTES;
+        let mut wide = Vec::with_capacity(len + 32);
+        let mut counter: u32 = 0;
+        while wide.len() < len {
+            wide.extend_from_slice(&Sha256::new().chain_update(counter.to_be_bytes()).chain_update(digest).finalize());
+            counter += 1;
+        }
+        wide.truncate(len);
+        Zq::new(&BigInt::from_bytes_be(Sign::Plus, &wide), q)
+    }
+
+    // Computes self + other mod q
+    pub fn add(&self, other: &Zq, q: &BigInt) -> Zq {
+        Zq::new(&(&self.0 + &other.0), q)
+    }
+
+    // Computes self - other mod q
+    pub fn sub(&self, other: &Zq, q: &BigInt) -> Zq {
+        Zq::new(&(&self.0 - &other.0), q)
+    }
+
+    // Computes self * other mod q
+    pub fn mul(&self, other: &Zq, q: &BigInt) -> Zq {
+        Zq::new(&(&self.0 * &other.0), q)
+    }
+
+    // Computes -self mod q
+    pub fn neg(&self, q: &BigInt) -> Zq {
+        Zq::new(&-&self.0, q)
+    }
+
+    // Computes self^-1 mod q as self^(q-2), which relies on q being prime; zero has no inverse
+    pub fn inv(&self, q: &BigInt) -> Option<Zq> {
+        match self.is_zero() {
+            true => None,
+            false => Some(Zq(self.0.modpow(&(q - 2), q))),
+        }
+    }
+}
+
+// Clears the digits in place from the least significant bit up, so the top digit is only
+// dropped once every digit below it is already zero and no copy of the value is left behind
+impl Zeroize for Zq {
+    fn zeroize(&mut self) {
+        for bit in 0..self.0.bits() {
+            self.0.set_bit(bit, false);
+        }
+    }
+}
+
+// A secret scalar, i.e. a private key or a nonce, which is wiped when dropped and never printed.
+// Temporaries created by arithmetic on the exposed value are not covered.
+#[derive(Clone)]
+pub struct Secret<S: Zeroize>(S);
+
+impl<S: Zeroize> Secret<S> {
+    pub fn new(s: S) -> Secret<S> {
+        Secret(s)
+    }
+
+    // Borrows the secret value for use in arithmetic
+    pub fn expose(&self) -> &S {
+        &self.0
+    }
+}
+
+impl<S: Zeroize> Drop for Secret<S> {
+    fn drop(&mut self) {
+        self.0.zeroize();
+    }
+}
+
+impl<S: Zeroize> fmt::Debug for Secret<S> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "Secret(<redacted>)")
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use num_bigint::BigInt;
+    use num_traits::One;
+    use zeroize::Zeroize;
+
+    use super::{Secret, Zq};
+
+    #[test]
+    fn test_reduction() {
+        let q = BigInt::from(61);
+        assert_eq!(Zq::new(&BigInt::from(-1), &q).value(), &BigInt::from(60));
+        assert_eq!(Zq::new(&BigInt::from(61), &q).value(), &BigInt::from(0));
+        assert_eq!(Zq::new(&BigInt::from(-122), &q), Zq::default());
+
+        let a = Zq::new(&BigInt::from(40), &q);
+        let b = Zq::new(&BigInt::from(30), &q);
+        assert_eq!(a.add(&b, &q).value(), &BigInt::from(9));
+        assert_eq!(b.sub(&a, &q).value(), &BigInt::from(51));
+        assert_eq!(a.mul(&b, &q).value(), &BigInt::from(41));
+        assert_eq!(a.add(&a.neg(&q), &q), Zq::default());
+        assert_eq!(a.inv(&q).unwrap().value(), &BigInt::from(29));
+        assert_eq!(a.mul(&a.inv(&q).unwrap(), &q).value(), &BigInt::from(1));
+        assert_eq!(Zq::default().inv(&q), None);
+    }
+
+    #[test]
+    fn test_sampling_ranges() {
+        let q = BigInt::from(5);
+        let mut rng = rand::thread_rng();
+        let mut seen = [0u32; 5];
+        for _ in 0..1000 {
+            let x = Zq::random(&q, &mut rng);
+            seen[usize::try_from(x.value()).unwrap()] += 1;
+            assert!(!Zq::random_nonzero(&q, &mut rng).is_zero());
+        }
+        assert!(seen.iter().all(|&n| n > 0));
+    }
+
+    #[test]
+    fn test_from_hash_is_reduced() {
+        let q = BigInt::from(61);
+        for i in 0u8..=255 {
+            assert!(Zq::from_hash(&[i], &q).value() < &q);
+        }
+        assert_eq!(Zq::from_hash(b"abc", &q), Zq::from_hash(b"abc", &q));
+    }
+
+    #[test]
+    fn test_zeroize() {
+        let q: BigInt = (BigInt::one() << 2048) - 159;
+        let mut x = Zq::random_nonzero(&q, &mut rand::thread_rng());
+        x.zeroize();
+        assert!(x.is_zero());
+    }
+
+    #[test]
+    fn test_secret_is_redacted() {
+        let secret = Secret::new(Zq::new(&BigInt::from(123456789), &BigInt::from(1000000007)));
+        assert_eq!(format!("{:?}", secret), "Secret(<redacted>)");
+        assert_eq!(secret.expose().value(), &BigInt::from(123456789));
+    }
+}
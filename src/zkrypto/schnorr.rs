@@ -0,0 +1,364 @@
+use num_bigint::BigInt;
+use log::{debug};
+use rand::{CryptoRng, RngCore};
+use sha2::{Digest, Sha256};
+use std::fmt::Debug;
+
+use crate::zkrypto::chaum_pedersen::{absorb, KeyPair};
+use crate::zkrypto::generator::{Generator, get_default};
+use crate::zkrypto::group::{ElementError, Group};
+use crate::zkrypto::nonce::{self, NonceMode};
+use crate::zkrypto::scalar::Secret;
+
+// Domain separation tag for the Fiat-Shamir challenge hash
+const FIAT_SHAMIR_DOMAIN: &[u8] = b"zkp_auth/schnorr/fiat-shamir/v1";
+
+// Non-interactive Schnorr proof of knowledge of log_g(y), obtained through the Fiat-Shamir transform
+#[derive(Clone, Debug, PartialEq)]
+pub struct SchnorrProof<G: Group = Generator> {
+    pub r: G::Element, // Commitment g^k
+    pub c: G::Scalar,  // Challenge derived from the transcript hash
+    pub s: G::Scalar,  // Response k - c * x mod q
+}
+
+// Computes the Fiat-Shamir challenge H(domain, group, y, r, context) mod q
+pub fn fiat_shamir_challenge<G: Group>(group: &G, y: &G::Element, r: &G::Element, context: &[u8]) -> G::Scalar {
+    let mut hasher = Sha256::new();
+    absorb(&mut hasher, FIAT_SHAMIR_DOMAIN);
+    absorb(&mut hasher, &group.encode_parameters());
+    for x in [y, r] {
+        absorb(&mut hasher, &group.encode_element(x));
+    }
+    absorb(&mut hasher, context);
+    group.scalar_from_hash(&hasher.finalize())
+}
+
+// Represents a prover for the Schnorr identification protocol. It holds a Chaum-Pedersen key
+// pair and proves knowledge of x for y = y1 = g^x only, so one secret serves both protocols.
+pub struct SchnorrProver<G: Group = Generator> {
+    g: G,                   // Group instance
+    pub kp: KeyPair<G>,     // Key pair, of which only y1 is public
+    nonce_mode: NonceMode,  // How nonces are obtained
+}
+
+// Commitment of a single interactive attempt, holding the nonce it was made with.
+// Like the Chaum-Pedersen commitment, answering consumes it so a nonce is used once.
+pub struct SchnorrCommitment<'a, G: Group = Generator> {
+    prover: &'a SchnorrProver<G>, // Prover whose key answers the challenge
+    k: Secret<G::Scalar>,         // Random nonce k, wiped on drop
+    r: G::Element,                // Commitment g^k
+}
+
+impl<G: Group> Debug for SchnorrCommitment<'_, G> {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(f, "SchnorrCommitment <-")
+    }
+}
+
+impl<G: Group> Debug for SchnorrProver<G> {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(f, "SchnorrProver <-")
+    }
+}
+
+impl SchnorrProver {
+    // Creates a new instance of SchnorrProver over the default group
+    pub fn new<R: RngCore + CryptoRng + ?Sized>(rng: &mut R) -> SchnorrProver {
+        SchnorrProver::with_group(get_default(), rng)
+    }
+}
+
+impl<G: Group> SchnorrProver<G> {
+    // Creates a new instance of SchnorrProver with a fresh key in the given group
+    pub fn with_group<R: RngCore + CryptoRng + ?Sized>(g: G, rng: &mut R) -> SchnorrProver<G> {
+        let x = Secret::new(g.random_nonzero_scalar(rng));
+        SchnorrProver::with_secret(g, x)
+    }
+
+    // Creates a new instance of SchnorrProver for a known secret x in the given group
+    pub fn with_secret(g: G, x: Secret<G::Scalar>) -> SchnorrProver<G> {
+        let kp = KeyPair::from_secret(&g, x);
+        SchnorrProver::with_keypair(g, kp)
+    }
+
+    // Creates a new instance of SchnorrProver for a key pair in the given group, e.g. one loaded from a key file
+    pub fn with_keypair(g: G, kp: KeyPair<G>) -> SchnorrProver<G> {
+        SchnorrProver {
+            g,
+            kp,
+            nonce_mode: NonceMode::default(),
+        }
+    }
+
+    // Selects how nonces are obtained; see NonceMode
+    pub fn with_nonce_mode(mut self, mode: NonceMode) -> SchnorrProver<G> {
+        self.nonce_mode = mode;
+        self
+    }
+
+    // Public key y = g^x
+    pub fn y(&self) -> &G::Element {
+        &self.kp.y1
+    }
+
+    // Returns the group used by this prover
+    pub fn group(&self) -> &G {
+        &self.g
+    }
+
+    // Obtains the nonce for a proof. The kind is prefixed with "schnorr/", so a deterministic
+    // nonce never coincides with the Chaum-Pedersen nonce of the same key and context.
+    fn nonce<R: RngCore + CryptoRng + ?Sized>(&self, mode: NonceMode, kind: &[u8], context: &[u8], rng: &mut R) -> Secret<G::Scalar> {
+        let context = [b"schnorr/".as_slice(), kind, context].concat();
+        nonce::nonce(mode, &self.g, self.kp.secret(), &[&self.kp.y1, &self.kp.y2], &context, rng)
+    }
+
+    // Proves part A of the protocol synchronously, committing to a fresh nonce.
+    // As for Chaum-Pedersen, the deterministic mode is hedged here.
+    pub fn prove_sync_a<R: RngCore + CryptoRng + ?Sized>(&self, rng: &mut R) -> SchnorrCommitment<'_, G> {
+        self.commit(&[], rng)
+    }
+
+    // Proves part A of the protocol for a login identified by context, which is bound into the
+    // nonce alongside fresh randomness
+    pub fn prove_sync_a_with_context<R: RngCore + CryptoRng + ?Sized>(&self, context: &[u8], rng: &mut R) -> SchnorrCommitment<'_, G> {
+        self.commit(context, rng)
+    }
+
+    fn commit<R: RngCore + CryptoRng + ?Sized>(&self, context: &[u8], rng: &mut R) -> SchnorrCommitment<'_, G> {
+        debug!("[P] SCHNORR PROVE SYNC A");
+        let k = self.nonce(self.nonce_mode.interactive(), b"interactive/", context, rng);
+        let r = self.g.exp_secret(&self.g.g(), k.expose());
+        debug!("[P] y: {:?}, r: {:?}", &self.kp.y1, &r);
+        debug!("[P] END SCHNORR PROVE SYNC A");
+        SchnorrCommitment { prover: self, k, r }
+    }
+
+    // Produces a non-interactive proof bound to the caller-supplied context
+    pub fn prove<R: RngCore + CryptoRng + ?Sized>(&self, context: &[u8], rng: &mut R) -> SchnorrProof<G> {
+        debug!("[P] SCHNORR PROVE");
+        let k = self.nonce(self.nonce_mode, b"fiat-shamir/", context, rng);
+        let r = self.g.exp_secret(&self.g.g(), k.expose());
+        let c = fiat_shamir_challenge(&self.g, &self.kp.y1, &r, context);
+        let xc = Secret::new(self.g.scalar_mul(self.kp.secret().expose(), &c));
+        let s = self.g.scalar_sub(k.expose(), xc.expose());
+
+        debug!("[P] END SCHNORR PROVE");
+        SchnorrProof { r, c, s }
+    }
+}
+
+impl<G: Group> SchnorrCommitment<'_, G> {
+    // Commitment g^k, sent to the verifier
+    pub fn r(&self) -> &G::Element {
+        &self.r
+    }
+
+    // Proves part B of the protocol synchronously, consuming the commitment and its nonce
+    pub fn prove_sync_b(self, c: &G::Scalar) -> G::Scalar {
+        debug!("[P] SCHNORR PROVE SYNC B");
+        let g = &self.prover.g;
+        let xc = Secret::new(g.scalar_mul(self.prover.kp.secret().expose(), c));
+        let s = g.scalar_sub(self.k.expose(), xc.expose());
+        debug!("[P] END SCHNORR PROVE SYNC B");
+        s
+    }
+}
+
+// Challenge state of a single interactive attempt, consumed when its answer is checked
+#[derive(Debug)]
+pub struct SchnorrChallenge<G: Group = Generator> {
+    r: G::Element, // Commitment received from the prover
+    c: G::Scalar,  // Challenge value sent back to the prover
+}
+
+impl<G: Group> SchnorrChallenge<G> {
+    // Challenge value to send back to the prover
+    pub fn c(&self) -> &G::Scalar {
+        &self.c
+    }
+}
+
+// Represents a verifier for the Schnorr protocol, holding only the prover's public key
+pub struct SchnorrVerifier<G: Group = Generator> {
+    g: G,          // Group instance
+    y: G::Element, // Public key g^x
+}
+
+impl<G: Group> Debug for SchnorrVerifier<G> {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(f, "SchnorrVerifier <-")
+    }
+}
+
+impl SchnorrVerifier {
+    // Creates a new instance of SchnorrVerifier over the default group
+    pub fn new(y: BigInt) -> Result<SchnorrVerifier, ElementError> {
+        SchnorrVerifier::with_group(get_default(), y)
+    }
+}
+
+impl<G: Group> SchnorrVerifier<G> {
+    // Creates a new instance of SchnorrVerifier for a public key in the given group,
+    // rejecting keys outside its prime-order subgroup
+    pub fn with_group(g: G, y: G::Element) -> Result<SchnorrVerifier<G>, ElementError> {
+        g.check_named("y", &y)?;
+        Ok(SchnorrVerifier { g, y })
+    }
+
+    // Returns the group used by this verifier
+    pub fn group(&self) -> &G {
+        &self.g
+    }
+
+    // Public key g^x
+    pub fn y(&self) -> &G::Element {
+        &self.y
+    }
+
+    // Verifies part A of the protocol synchronously, returning the state of this attempt.
+    // Commitments outside the prime-order subgroup are rejected before a challenge is drawn.
+    pub fn verify_sync_a<R: RngCore + CryptoRng + ?Sized>(&self, r: G::Element, rng: &mut R) -> Result<SchnorrChallenge<G>, ElementError> {
+        debug!("[V] SCHNORR VERIFY SYNC A");
+        self.g.check_named("r", &r)?;
+        let c = self.g.random_scalar(rng);
+        debug!("[V] y: {:?}, r: {:?}", &self.y, &r);
+        debug!("[V] END SCHNORR VERIFY SYNC A");
+        Ok(SchnorrChallenge { r, c })
+    }
+
+    // Verifies part B of the protocol synchronously, consuming the state of the same attempt
+    pub fn verify_sync_b(&self, challenge: SchnorrChallenge<G>, s: G::Scalar) -> bool {
+        debug!("[V] SCHNORR VERIFY SYNC B");
+        let result = self.check(&challenge.r, &challenge.c, &s);
+        debug!("[V] END SCHNORR VERIFY SYNC B");
+        result
+    }
+
+    // Verifies a non-interactive proof against the context it was produced for
+    pub fn verify(&self, proof: &SchnorrProof<G>, context: &[u8]) -> bool {
+        debug!("[V] SCHNORR VERIFY");
+        let c = fiat_shamir_challenge(&self.g, &self.y, &proof.r, context);
+        let result = self.g.check_element(&proof.r).is_ok() && c == proof.c && self.check(&proof.r, &proof.c, &proof.s);
+        debug!("[V] END SCHNORR VERIFY");
+        result
+    }
+
+    // Checks r = g^s * y^c as one multi-exponentiation
+    fn check(&self, r: &G::Element, c: &G::Scalar, s: &G::Scalar) -> bool {
+        &self.g.multi_exp(&[self.g.g(), self.y.clone()], &[s.clone(), c.clone()]) == r
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use num_bigint::BigInt;
+
+    use crate::zkrypto::chaum_pedersen::{ChaumPedersenProver, ChaumPedersenVerifier};
+    use crate::zkrypto::generator::{get_debug, get_named};
+    use crate::zkrypto::group::{ElementError, Group, MembershipError};
+    use crate::zkrypto::nonce::NonceMode;
+    use crate::zkrypto::ristretto::RistrettoGroup;
+    use crate::zkrypto::scalar::Zq;
+    use super::{SchnorrProver, SchnorrVerifier};
+
+    #[test]
+    fn test_schnorr_protocol() {
+        let mut rng = rand::thread_rng();
+        let prover = SchnorrProver::new(&mut rng);
+        let verifier = SchnorrVerifier::new(prover.y().clone()).unwrap();
+
+        let commitment = prover.prove_sync_a(&mut rng);
+        let challenge = verifier.verify_sync_a(commitment.r().clone(), &mut rng).unwrap();
+        let s = commitment.prove_sync_b(challenge.c());
+        assert!(verifier.verify_sync_b(challenge, s));
+
+        // An answer for one challenge does not satisfy another
+        let commitment = prover.prove_sync_a(&mut rng);
+        let a = verifier.verify_sync_a(commitment.r().clone(), &mut rng).unwrap();
+        let b = verifier.verify_sync_a(commitment.r().clone(), &mut rng).unwrap();
+        let s = commitment.prove_sync_b(a.c());
+        assert!(!verifier.verify_sync_b(b, s.clone()));
+        assert!(verifier.verify_sync_b(a, s));
+    }
+
+    #[test]
+    fn test_fiat_shamir_protocol() {
+        let mut rng = rand::thread_rng();
+        let prover = SchnorrProver::new(&mut rng);
+        let verifier = SchnorrVerifier::new(prover.y().clone()).unwrap();
+        let proof = prover.prove(b"login", &mut rng);
+        assert!(verifier.verify(&proof, b"login"));
+        assert!(!verifier.verify(&proof, b"other context"));
+
+        let mut bad_s = proof.clone();
+        bad_s.s = Zq::new(&(bad_s.s.value() + 1u32), &verifier.group().q);
+        assert!(!verifier.verify(&bad_s, b"login"));
+
+        let mut bad_r = proof;
+        bad_r.r = (&bad_r.r * &verifier.group().g) % &verifier.group().p;
+        assert!(!verifier.verify(&bad_r, b"login"));
+
+        let other = SchnorrProver::new(&mut rng);
+        assert!(!verifier.verify(&other.prove(b"login", &mut rng), b"login"));
+    }
+
+    #[test]
+    fn test_protocol_over_other_groups() {
+        let mut rng = rand::thread_rng();
+        let g = RistrettoGroup::new();
+        let prover = SchnorrProver::with_group(g.clone(), &mut rng);
+        let verifier = SchnorrVerifier::with_group(g, *prover.y()).unwrap();
+        let commitment = prover.prove_sync_a(&mut rng);
+        let challenge = verifier.verify_sync_a(*commitment.r(), &mut rng).unwrap();
+        let s = commitment.prove_sync_b(challenge.c());
+        assert!(verifier.verify_sync_b(challenge, s));
+        assert!(verifier.verify(&prover.prove(b"ristretto", &mut rng), b"ristretto"));
+
+        let g = get_named("ffdhe2048").unwrap();
+        let prover = SchnorrProver::with_group(g.clone(), &mut rng);
+        let verifier = SchnorrVerifier::with_group(g, prover.y().clone()).unwrap();
+        assert!(verifier.verify(&prover.prove(b"ffdhe", &mut rng), b"ffdhe"));
+    }
+
+    #[test]
+    fn test_shares_keys_with_chaum_pedersen() {
+        let mut rng = rand::thread_rng();
+        let g = get_named("modp2048-256").unwrap();
+        let chaum_pedersen = ChaumPedersenProver::with_group(g.clone(), &mut rng);
+        let schnorr = SchnorrProver::with_secret(g.clone(), chaum_pedersen.kp.secret().clone())
+            .with_nonce_mode(NonceMode::Deterministic);
+        assert_eq!(schnorr.y(), &chaum_pedersen.kp.y1);
+
+        let verifier = SchnorrVerifier::with_group(g.clone(), chaum_pedersen.kp.y1.clone()).unwrap();
+        let proof = schnorr.prove(b"login", &mut rng);
+        assert!(verifier.verify(&proof, b"login"));
+        assert_eq!(schnorr.prove(b"login", &mut rng), proof);
+
+        // Deterministic nonces of the two protocols differ for the same key and context
+        let chaum_pedersen = ChaumPedersenProver::with_keypair(g.clone(), schnorr.kp).with_nonce_mode(NonceMode::Deterministic);
+        let cp_verifier = ChaumPedersenVerifier::with_group(g, chaum_pedersen.kp.y1.clone(), chaum_pedersen.kp.y2.clone()).unwrap();
+        let cp_proof = chaum_pedersen.prove(b"login", &mut rng);
+        assert!(cp_verifier.verify(&cp_proof, b"login"));
+        assert_ne!(cp_proof.r1, proof.r);
+    }
+
+    #[test]
+    fn test_rejects_non_members() {
+        let mut rng = rand::thread_rng();
+        let prover = SchnorrProver::with_group(get_debug(), &mut rng);
+        let verifier = SchnorrVerifier::with_group(get_debug(), prover.y().clone()).unwrap();
+        let mut proof = prover.prove(b"login", &mut rng);
+
+        // 1 is degenerate and 366 = p - 1 has order 2 in Z_367^*
+        for (bad, error) in [(1, MembershipError::OutOfRange), (366, MembershipError::NotInSubgroup)] {
+            let bad = BigInt::from(bad);
+            let expected = |name| Err(ElementError { name, error: error.clone() });
+            assert_eq!(SchnorrVerifier::with_group(get_debug(), bad.clone()).map(|_| ()), expected("y"));
+            assert_eq!(verifier.verify_sync_a(bad.clone(), &mut rng).map(|_| ()), expected("r"));
+            proof.r = bad;
+            assert!(!verifier.verify(&proof, b"login"));
+        }
+        assert_ne!(prover.prove_sync_a(&mut rng).r(), &get_debug().identity());
+    }
+}
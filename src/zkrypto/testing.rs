@@ -0,0 +1,186 @@
+// Honest-verifier simulator and special-soundness extractor for Chaum-Pedersen transcripts, built
+// only for tests or with the test-utils feature. Security tests use them to show that transcripts
+// reveal nothing about x, and that a commitment answered for two challenges reveals all of it.
+use std::fmt;
+
+use rand::{CryptoRng, RngCore};
+
+use crate::zkrypto::chaum_pedersen::ChaumPedersenProof;
+use crate::zkrypto::group::Group;
+use crate::zkrypto::scalar::Secret;
+
+// Errors raised when two transcripts cannot be combined into the secret
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ExtractionError {
+    DifferentCommitments, // The transcripts do not share (r1, r2)
+    SameChallenge,        // The transcripts answer the same challenge
+}
+
+impl fmt::Display for ExtractionError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ExtractionError::DifferentCommitments => write!(f, "transcripts have different commitments"),
+            ExtractionError::SameChallenge => write!(f, "transcripts have the same challenge"),
+        }
+    }
+}
+
+impl std::error::Error for ExtractionError {}
+
+// Produces an accepting interactive transcript (r1, r2, c, s) for the public keys y1, y2 and the
+// challenge c without knowing x, by drawing s and solving r1 = g^s * y1^c and r2 = h^s * y2^c.
+// Identity commitments, which an honest prover's nonzero nonce never yields, are redrawn, so for
+// a uniform c the transcript is distributed exactly like an honest one.
+pub fn simulate<G: Group, R: RngCore + CryptoRng + ?Sized>(
+    group: &G,
+    y1: &G::Element,
+    y2: &G::Element,
+    c: &G::Scalar,
+    rng: &mut R,
+) -> ChaumPedersenProof<G> {
+    loop {
+        let s = group.random_scalar(rng);
+        let exponents = [s.clone(), c.clone()];
+        let r1 = group.multi_exp(&[group.g(), y1.clone()], &exponents);
+        if r1 != group.identity() {
+            let r2 = group.multi_exp(&[group.h(), y2.clone()], &exponents);
+            return ChaumPedersenProof { r1, r2, c: c.clone(), s };
+        }
+    }
+}
+
+// Recovers x from two transcripts sharing (r1, r2) with different challenges: s_a = k - c_a * x
+// and s_b = k - c_b * x give x = (s_a - s_b) / (c_b - c_a) mod q. The result is only the secret
+// if both transcripts are accepting, so callers compare g^x with y1.
+pub fn extract<G: Group>(
+    group: &G,
+    a: &ChaumPedersenProof<G>,
+    b: &ChaumPedersenProof<G>,
+) -> Result<Secret<G::Scalar>, ExtractionError> {
+    if a.r1 != b.r1 || a.r2 != b.r2 {
+        return Err(ExtractionError::DifferentCommitments);
+    }
+    let inverse = group.scalar_inv(&group.scalar_sub(&b.c, &a.c)).ok_or(ExtractionError::SameChallenge)?;
+    Ok(Secret::new(group.scalar_mul(&group.scalar_sub(&a.s, &b.s), &inverse)))
+}
+
+// Finds the first two transcripts of a log that answer one commitment for different challenges,
+// i.e. a reused nonce from which extract recovers the prover's secret
+pub fn find_nonce_reuse<G: Group>(transcripts: &[ChaumPedersenProof<G>]) -> Option<(usize, usize)> {
+    (0..transcripts.len())
+        .flat_map(|i| (i + 1..transcripts.len()).map(move |j| (i, j)))
+        .find(|&(i, j)| {
+            let (a, b) = (&transcripts[i], &transcripts[j]);
+            a.r1 == b.r1 && a.r2 == b.r2 && a.c != b.c
+        })
+}
+
+#[cfg(test)]
+mod test {
+    use std::collections::HashSet;
+
+    use rand::rngs::StdRng;
+    use rand::{CryptoRng, RngCore, SeedableRng};
+
+    use crate::zkrypto::chaum_pedersen::{ChaumPedersenProof, ChaumPedersenProver, ChaumPedersenVerifier};
+    use crate::zkrypto::generator::{get_debug, get_default};
+    use crate::zkrypto::group::Group;
+    use crate::zkrypto::ristretto::RistrettoGroup;
+
+    use super::{extract, find_nonce_reuse, simulate, ExtractionError};
+
+    // Runs the interactive protocol, returning its transcript
+    fn transcript<G: Group, R: RngCore + CryptoRng>(
+        prover: &ChaumPedersenProver<G>,
+        verifier: &ChaumPedersenVerifier<G>,
+        rng: &mut R,
+    ) -> ChaumPedersenProof<G> {
+        let commitment = prover.prove_sync_a(rng);
+        let (r1, r2) = (commitment.r1().clone(), commitment.r2().clone());
+        let challenge = verifier.verify_sync_a(r1.clone(), r2.clone(), rng).unwrap();
+        let c = challenge.c().clone();
+        let s = commitment.prove_sync_b(&c);
+        assert!(verifier.verify_sync_b(challenge, s.clone()));
+        ChaumPedersenProof { r1, r2, c, s }
+    }
+
+    // Answers a random challenge for the nonce k as a prover with secret x would, so that calls
+    // with the same k stand in for a prover that reuses its nonce
+    fn reused_nonce_transcript<G: Group, R: RngCore + CryptoRng>(
+        group: &G,
+        x: &G::Scalar,
+        k: &G::Scalar,
+        rng: &mut R,
+    ) -> ChaumPedersenProof<G> {
+        let c = group.random_scalar(rng);
+        let s = group.scalar_sub(k, &group.scalar_mul(x, &c));
+        ChaumPedersenProof { r1: group.exp(&group.g(), k), r2: group.exp(&group.h(), k), c, s }
+    }
+
+    fn check_simulation<G: Group>(group: G) {
+        let mut rng = rand::thread_rng();
+        let prover = ChaumPedersenProver::with_group(group.clone(), &mut rng);
+        let verifier = ChaumPedersenVerifier::with_group(group.clone(), prover.kp.y1.clone(), prover.kp.y2.clone()).unwrap();
+
+        // Honest and simulated transcripts are both accepted, for any challenge
+        assert!(verifier.accepts(&transcript(&prover, &verifier, &mut rng)));
+        for _ in 0..5 {
+            let c = group.random_scalar(&mut rng);
+            let mut simulated = simulate(&group, &prover.kp.y1, &prover.kp.y2, &c, &mut rng);
+            assert_eq!(simulated.c, c);
+            assert!(verifier.accepts(&simulated));
+            simulated.s = group.scalar_add(&simulated.s, &group.scalar_from_bigint(&1.into()));
+            assert!(!verifier.accepts(&simulated));
+        }
+    }
+
+    #[test]
+    fn test_simulated_transcripts_are_accepted() {
+        check_simulation(get_default());
+        check_simulation(RistrettoGroup::new());
+    }
+
+    #[test]
+    fn test_simulated_commitments_cover_the_subgroup() {
+        // q = 61, so an honest prover's commitments take all 60 non-identity values; a simulator
+        // that missed some, or produced the identity, would be told apart from it
+        let mut rng = StdRng::seed_from_u64(61);
+        let group = get_debug();
+        let prover = ChaumPedersenProver::with_group(group.clone(), &mut rng);
+        let c = group.random_scalar(&mut rng);
+        let commitments: HashSet<_> = (0..3000).map(|_| simulate(&group, &prover.kp.y1, &prover.kp.y2, &c, &mut rng).r1).collect();
+        assert_eq!(commitments.len(), 60);
+        assert!(!commitments.contains(&group.identity()));
+    }
+
+    fn check_extraction<G: Group>(group: G) {
+        // Seeded, as with q = 455033 in the default group random challenges would collide about
+        // once in 455,000 runs and hide the reuse
+        let mut rng = StdRng::seed_from_u64(25);
+        let prover = ChaumPedersenProver::with_group(group.clone(), &mut rng);
+        let verifier = ChaumPedersenVerifier::with_group(group.clone(), prover.kp.y1.clone(), prover.kp.y2.clone()).unwrap();
+        // The prover never reuses a nonce itself, so the first and last transcripts are built by hand
+        let (x, k) = (prover.kp.secret().expose(), group.random_nonzero_scalar(&mut rng));
+        let log = vec![
+            reused_nonce_transcript(&group, x, &k, &mut rng),
+            transcript(&prover, &verifier, &mut rng),
+            reused_nonce_transcript(&group, x, &k, &mut rng),
+        ];
+        assert!(log.iter().all(|t| verifier.accepts(t)));
+
+        assert_eq!(find_nonce_reuse(&log), Some((0, 2)));
+        let x = extract(&group, &log[0], &log[2]).unwrap();
+        assert_eq!(x.expose(), prover.kp.secret().expose());
+        assert_eq!(group.exp(&group.g(), x.expose()), prover.kp.y1);
+
+        assert_eq!(extract(&group, &log[0], &log[1]).err(), Some(ExtractionError::DifferentCommitments));
+        assert_eq!(extract(&group, &log[0], &log[0]).err(), Some(ExtractionError::SameChallenge));
+        assert_eq!(find_nonce_reuse(&log[..2]), None);
+    }
+
+    #[test]
+    fn test_extract_recovers_secret_from_reused_nonce() {
+        check_extraction(get_default());
+        check_extraction(RistrettoGroup::new());
+    }
+}
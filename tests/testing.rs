@@ -0,0 +1,64 @@
+// Security tests of the public API with the simulator and extractor of the test-utils feature.
+// Run with `cargo test --features test-utils`.
+use rand::rngs::StdRng;
+use rand::SeedableRng;
+
+use zkp::zkrypto::chaum_pedersen::{ChaumPedersenProof, ChaumPedersenProver, ChaumPedersenVerifier};
+use zkp::zkrypto::generator::get_default;
+use zkp::zkrypto::group::Group;
+use zkp::zkrypto::nonce::NonceMode;
+use zkp::zkrypto::ristretto::RistrettoGroup;
+use zkp::zkrypto::testing::{extract, find_nonce_reuse, simulate};
+
+// Runs an interactive login whose commitment is bound to context. The prover's RNG restarts from
+// the same seed on every login, as a broken RNG would, while the verifier's stays healthy.
+fn login_with_broken_rng<G: Group>(
+    prover: &ChaumPedersenProver<G>,
+    verifier: &ChaumPedersenVerifier<G>,
+    context: &[u8],
+    rng: &mut StdRng,
+) -> ChaumPedersenProof<G> {
+    let commitment = prover.prove_sync_a_with_context(context, &mut StdRng::seed_from_u64(0));
+    let (r1, r2) = (commitment.r1().clone(), commitment.r2().clone());
+    let challenge = verifier.verify_sync_a(r1.clone(), r2.clone(), rng).unwrap();
+    let c = challenge.c().clone();
+    let s = commitment.prove_sync_b(&c);
+    assert!(verifier.verify_sync_b(challenge, s.clone()));
+    ChaumPedersenProof { r1, r2, c, s }
+}
+
+fn check_broken_rng<G: Group>(group: G) {
+    let mut rng = StdRng::seed_from_u64(15);
+    let prover = ChaumPedersenProver::with_group(group.clone(), &mut rng).with_nonce_mode(NonceMode::Hedged);
+    let verifier = ChaumPedersenVerifier::with_group(group.clone(), prover.kp.y1.clone(), prover.kp.y2.clone()).unwrap();
+
+    // Repeating the context repeats the hedged nonce, and a captured log then reveals x
+    let log: Vec<_> = (0..2).map(|_| login_with_broken_rng(&prover, &verifier, b"login", &mut rng)).collect();
+    let (a, b) = find_nonce_reuse(&log).unwrap();
+    let x = extract(&group, &log[a], &log[b]).unwrap();
+    assert_eq!(group.exp(&group.g(), x.expose()), prover.kp.y1);
+
+    // A context unique per login, such as a server-issued login nonce, keeps the nonces apart
+    let log: Vec<_> = [b"login 1", b"login 2"]
+        .iter()
+        .map(|context| login_with_broken_rng(&prover, &verifier, *context, &mut rng))
+        .collect();
+    assert_eq!(find_nonce_reuse(&log), None);
+}
+
+#[test]
+fn test_unique_login_contexts_survive_a_broken_rng() {
+    check_broken_rng(get_default());
+    check_broken_rng(RistrettoGroup::new());
+}
+
+#[test]
+fn test_simulated_transcripts_are_accepted() {
+    let mut rng = StdRng::seed_from_u64(25);
+    let group = get_default();
+    let prover = ChaumPedersenProver::with_group(group.clone(), &mut rng);
+    let verifier = ChaumPedersenVerifier::with_group(group.clone(), prover.kp.y1.clone(), prover.kp.y2.clone()).unwrap();
+    let c = group.random_scalar(&mut rng);
+    let simulated = simulate(&group, &prover.kp.y1, &prover.kp.y2, &c, &mut rng);
+    assert!(verifier.accepts(&simulated));
+}